@@ -16,6 +16,12 @@
 #[cfg(feature = "auto_send")]
 pub mod auto_send;
 
+/// [`AutoMigrate`] bot adaptor which handles group to supergroup migrations.
+///
+/// [`AutoMigrate`]: auto_migrate::AutoMigrate
+#[cfg(feature = "auto_migrate")]
+pub mod auto_migrate;
+
 /// [`CacheMe`] bot adaptor which caches [`GetMe`] requests.
 ///
 /// [`CacheMe`]: cache_me::CacheMe
@@ -23,12 +29,37 @@ pub mod auto_send;
 #[cfg(feature = "cache_me")]
 pub mod cache_me;
 
+/// [`Cache`] bot adaptor which caches responses to read-only requests.
+///
+/// [`Cache`]: cache::Cache
+#[cfg(feature = "cache")]
+pub mod cache;
+
+/// [`DryRun`] bot adaptor which fabricates responses instead of sending
+/// requests.
+///
+/// [`DryRun`]: dry_run::DryRun
+#[cfg(feature = "dry_run")]
+pub mod dry_run;
+
 /// [`Trace`] bot adaptor which traces requests.
 ///
 /// [`Trace`]: trace::Trace
 #[cfg(feature = "trace_adaptor")]
 pub mod trace;
 
+/// [`CircuitBreaker`] bot adaptor which fails fast during telegram outages.
+///
+/// [`CircuitBreaker`]: circuit_breaker::CircuitBreaker
+#[cfg(feature = "circuit_breaker")]
+pub mod circuit_breaker;
+
+/// [`CoalesceEdits`] bot adaptor which coalesces edits of the same message.
+///
+/// [`CoalesceEdits`]: coalesce_edits::CoalesceEdits
+#[cfg(feature = "coalesce_edits")]
+pub mod coalesce_edits;
+
 /// [`ErasedRequester`] bot adaptor which allows to erase type of
 /// [`Requester`].
 ///
@@ -37,6 +68,19 @@ pub mod trace;
 #[cfg(feature = "erased")]
 pub mod erased;
 
+/// [`IgnoreNotModified`] bot adaptor which treats "message is not modified"
+/// errors as success.
+///
+/// [`IgnoreNotModified`]: ignore_not_modified::IgnoreNotModified
+#[cfg(feature = "ignore_not_modified")]
+pub mod ignore_not_modified;
+
+/// [`Metrics`] bot adaptor which collects metrics of requests.
+///
+/// [`Metrics`]: metrics::Metrics
+#[cfg(feature = "metrics")]
+pub mod metrics;
+
 /// [`Throttle`] bot adaptor which allows automatically throttle when hitting
 /// API limits.
 ///
@@ -44,17 +88,33 @@ pub mod erased;
 #[cfg(feature = "throttle")]
 pub mod throttle;
 
+mod default_values;
 mod parse_mode;
 
+#[cfg(feature = "auto_migrate")]
+pub use auto_migrate::AutoMigrate;
 #[cfg(feature = "auto_send")]
 pub use auto_send::AutoSend;
+#[cfg(feature = "cache")]
+pub use cache::Cache;
 #[cfg(feature = "cache_me")]
 pub use cache_me::CacheMe;
+#[cfg(feature = "circuit_breaker")]
+pub use circuit_breaker::CircuitBreaker;
+#[cfg(feature = "coalesce_edits")]
+pub use coalesce_edits::CoalesceEdits;
+#[cfg(feature = "dry_run")]
+pub use dry_run::DryRun;
 #[cfg(feature = "erased")]
 pub use erased::ErasedRequester;
+#[cfg(feature = "ignore_not_modified")]
+pub use ignore_not_modified::IgnoreNotModified;
+#[cfg(feature = "metrics")]
+pub use metrics::Metrics;
 #[cfg(feature = "throttle")]
 pub use throttle::Throttle;
 #[cfg(feature = "trace_adaptor")]
 pub use trace::Trace;
 
+pub use default_values::{DefaultValues, Defaults};
 pub use parse_mode::DefaultParseMode;
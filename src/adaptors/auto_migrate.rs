@@ -0,0 +1,577 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, RwLock},
+    task::{Context, Poll},
+};
+
+use url::Url;
+
+use crate::{
+    errors::AsResponseParameters,
+    requests::{HasPayload, Output, Request, Requester},
+    types::*,
+};
+
+/// Automatic handling of group to supergroup migrations.
+///
+/// When a group is upgraded to a supergroup, requests to the old group fail
+/// with [`RequestError::MigrateToChatId`]. This bot adaptor catches such
+/// errors and transparently retries requests with the new chat id.
+///
+/// Migrations are remembered in an in-memory table, so later requests to the
+/// old chat id are rewritten automatically. Every time a new migration is
+/// found, a user-supplied callback is invoked with old and new chat ids, so
+/// you can persist the mapping (e.g. update ids in your database). Persisted
+/// mappings can be loaded back with [`AutoMigrate::add_migration`].
+///
+/// Only requests which have a `chat_id` (of type [`Recipient`]) are affected.
+/// Note that a request is retried at most once.
+///
+/// ## Examples
+///
+/// ```
+/// use teloxide_core::{requests::RequesterExt, Bot};
+///
+/// let bot =
+///     Bot::new("TOKEN").auto_migrate(|old, new| { /* update `old` to `new` in the database */ });
+/// ```
+///
+/// [`RequestError::MigrateToChatId`]: crate::RequestError::MigrateToChatId
+#[derive(Clone)]
+pub struct AutoMigrate<B> {
+    bot: B,
+    migrations: Migrations,
+}
+
+impl<B> AutoMigrate<B> {
+    /// Creates new [`AutoMigrate`].
+    ///
+    /// `on_migrate` is called with old and new chat ids every time a new
+    /// migration is found.
+    ///
+    /// Note: it's recommended to use [`RequesterExt::auto_migrate`] instead.
+    ///
+    /// [`RequesterExt::auto_migrate`]: crate::requests::RequesterExt::auto_migrate
+    pub fn new<F>(bot: B, on_migrate: F) -> Self
+    where
+        F: Fn(ChatId, ChatId) + Send + Sync + 'static,
+    {
+        Self {
+            bot,
+            migrations: Migrations {
+                table: <_>::default(),
+                on_migrate: Arc::new(on_migrate),
+            },
+        }
+    }
+
+    /// Allows to access the inner bot.
+    pub fn inner(&self) -> &B {
+        &self.bot
+    }
+
+    /// Unwraps the inner bot.
+    pub fn into_inner(self) -> B {
+        self.bot
+    }
+
+    /// Returns id of the supergroup the chat `from` was migrated to, if the
+    /// migration is known.
+    pub fn migrated_to(&self, from: ChatId) -> Option<ChatId> {
+        self.migrations.get(from)
+    }
+
+    /// Adds a known migration (e.g. a persisted one), so requests to `from`
+    /// are sent to `to` instead.
+    ///
+    /// Note: this doesn't call the `on_migrate` callback.
+    pub fn add_migration(&self, from: ChatId, to: ChatId) {
+        self.migrations.insert(from, to);
+    }
+}
+
+impl<B: fmt::Debug> fmt::Debug for AutoMigrate<B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AutoMigrate")
+            .field("bot", &self.bot)
+            .field("migrations", &self.migrations.table)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The migration table shared between [`AutoMigrate`] and its requests.
+#[derive(Clone)]
+struct Migrations {
+    table: Arc<RwLock<HashMap<ChatId, ChatId>>>,
+    on_migrate: Arc<dyn Fn(ChatId, ChatId) + Send + Sync>,
+}
+
+impl Migrations {
+    fn get(&self, from: ChatId) -> Option<ChatId> {
+        self.table.read().unwrap().get(&from).copied()
+    }
+
+    fn insert(&self, from: ChatId, to: ChatId) -> bool {
+        self.table.write().unwrap().insert(from, to) != Some(to)
+    }
+
+    /// Replaces `chat_id` with the id of the supergroup it was migrated to (if
+    /// the migration is known).
+    fn rewrite(&self, chat_id: &mut Recipient) {
+        if let Recipient::Id(id) = chat_id {
+            if let Some(new) = self.get(*id) {
+                *id = new;
+            }
+        }
+    }
+
+    /// Remembers a migration found from an error and rewrites `chat_id`.
+    fn migrate(&self, chat_id: &mut Recipient, to: ChatId) {
+        if let Recipient::Id(from) = *chat_id {
+            if self.insert(from, to) {
+                (self.on_migrate)(from, to);
+            }
+        }
+
+        *chat_id = Recipient::Id(to);
+    }
+}
+
+macro_rules! f {
+    ($m:ident $this:ident ($($arg:ident : $T:ty),*)) => {
+        {
+            let mut inner = $this.inner().$m($($arg),*);
+            $this.migrations.rewrite(&mut inner.payload_mut().chat_id);
+
+            MigratingRequest {
+                inner,
+                chat_id: |p| &mut p.chat_id,
+                migrations: $this.migrations.clone(),
+            }
+        }
+    };
+}
+
+macro_rules! fty {
+    ($T:ident) => {
+        MigratingRequest<B::$T>
+    };
+}
+
+macro_rules! fid {
+    ($m:ident $this:ident ($($arg:ident : $T:ty),*)) => {
+        $this.inner().$m($($arg),*)
+    };
+}
+
+macro_rules! ftyid {
+    ($T:ident) => {
+        B::$T
+    };
+}
+
+impl<B> Requester for AutoMigrate<B>
+where
+    B: Requester,
+    B::Err: AsResponseParameters,
+
+    B::ForwardMessage: Clone + Send,
+    B::CopyMessage: Clone + Send,
+    B::SendMessage: Clone + Send,
+    B::SendPhoto: Clone + Send,
+    B::SendAudio: Clone + Send,
+    B::SendDocument: Clone + Send,
+    B::SendVideo: Clone + Send,
+    B::SendAnimation: Clone + Send,
+    B::SendVoice: Clone + Send,
+    B::SendVideoNote: Clone + Send,
+    B::SendMediaGroup: Clone + Send,
+    B::SendLocation: Clone + Send,
+    B::EditMessageLiveLocation: Clone + Send,
+    B::StopMessageLiveLocation: Clone + Send,
+    B::SendVenue: Clone + Send,
+    B::SendContact: Clone + Send,
+    B::SendPoll: Clone + Send,
+    B::SendDice: Clone + Send,
+    B::SendChatAction: Clone + Send,
+    B::KickChatMember: Clone + Send,
+    B::BanChatMember: Clone + Send,
+    B::UnbanChatMember: Clone + Send,
+    B::RestrictChatMember: Clone + Send,
+    B::PromoteChatMember: Clone + Send,
+    B::SetChatAdministratorCustomTitle: Clone + Send,
+    B::BanChatSenderChat: Clone + Send,
+    B::UnbanChatSenderChat: Clone + Send,
+    B::SetChatPermissions: Clone + Send,
+    B::ExportChatInviteLink: Clone + Send,
+    B::CreateChatInviteLink: Clone + Send,
+    B::EditChatInviteLink: Clone + Send,
+    B::RevokeChatInviteLink: Clone + Send,
+    B::SetChatPhoto: Clone + Send,
+    B::DeleteChatPhoto: Clone + Send,
+    B::SetChatTitle: Clone + Send,
+    B::SetChatDescription: Clone + Send,
+    B::PinChatMessage: Clone + Send,
+    B::UnpinChatMessage: Clone + Send,
+    B::UnpinAllChatMessages: Clone + Send,
+    B::LeaveChat: Clone + Send,
+    B::GetChat: Clone + Send,
+    B::GetChatAdministrators: Clone + Send,
+    B::GetChatMembersCount: Clone + Send,
+    B::GetChatMemberCount: Clone + Send,
+    B::GetChatMember: Clone + Send,
+    B::SetChatStickerSet: Clone + Send,
+    B::DeleteChatStickerSet: Clone + Send,
+    B::EditMessageText: Clone + Send,
+    B::EditMessageCaption: Clone + Send,
+    B::EditMessageMedia: Clone + Send,
+    B::EditMessageReplyMarkup: Clone + Send,
+    B::StopPoll: Clone + Send,
+    B::DeleteMessage: Clone + Send,
+    B::SendSticker: Clone + Send,
+    B::SendInvoice: Clone + Send,
+    B::ApproveChatJoinRequest: Clone + Send,
+    B::DeclineChatJoinRequest: Clone + Send,
+{
+    type Err = B::Err;
+
+    requester_forward! {
+        forward_message,
+        copy_message,
+        send_message,
+        send_photo,
+        send_audio,
+        send_document,
+        send_video,
+        send_animation,
+        send_voice,
+        send_video_note,
+        send_media_group,
+        send_location,
+        edit_message_live_location,
+        stop_message_live_location,
+        send_venue,
+        send_contact,
+        send_poll,
+        send_dice,
+        send_chat_action,
+        kick_chat_member,
+        ban_chat_member,
+        unban_chat_member,
+        restrict_chat_member,
+        promote_chat_member,
+        set_chat_administrator_custom_title,
+        ban_chat_sender_chat,
+        unban_chat_sender_chat,
+        set_chat_permissions,
+        export_chat_invite_link,
+        create_chat_invite_link,
+        edit_chat_invite_link,
+        revoke_chat_invite_link,
+        set_chat_photo,
+        delete_chat_photo,
+        set_chat_title,
+        set_chat_description,
+        pin_chat_message,
+        unpin_chat_message,
+        unpin_all_chat_messages,
+        leave_chat,
+        get_chat,
+        get_chat_administrators,
+        get_chat_members_count,
+        get_chat_member_count,
+        get_chat_member,
+        set_chat_sticker_set,
+        delete_chat_sticker_set,
+        edit_message_text,
+        edit_message_caption,
+        edit_message_media,
+        edit_message_reply_markup,
+        stop_poll,
+        delete_message,
+        send_sticker,
+        send_invoice,
+        approve_chat_join_request,
+        decline_chat_join_request
+        => f, fty
+    }
+
+    requester_forward! {
+        get_me,
+        log_out,
+        close,
+        get_updates,
+        set_webhook,
+        delete_webhook,
+        get_webhook_info,
+        edit_message_live_location_inline,
+        stop_message_live_location_inline,
+        get_user_profile_photos,
+        get_file,
+        answer_callback_query,
+        set_my_commands,
+        get_my_commands,
+        set_chat_menu_button,
+        get_chat_menu_button,
+        set_my_default_administrator_rights,
+        get_my_default_administrator_rights,
+        delete_my_commands,
+        answer_inline_query,
+        answer_web_app_query,
+        edit_message_text_inline,
+        edit_message_caption_inline,
+        edit_message_media_inline,
+        edit_message_reply_markup_inline,
+        get_sticker_set,
+        upload_sticker_file,
+        create_new_sticker_set,
+        add_sticker_to_set,
+        set_sticker_position_in_set,
+        delete_sticker_from_set,
+        set_sticker_set_thumb,
+        create_invoice_link,
+        answer_shipping_query,
+        answer_pre_checkout_query,
+        set_passport_data_errors,
+        send_game,
+        set_game_score,
+        set_game_score_inline,
+        get_game_high_scores
+        => fid, ftyid
+    }
+}
+
+download_forward! {
+    'w
+    B
+    AutoMigrate<B>
+    { this => this.inner() }
+}
+
+/// Request returned by [`AutoMigrate`] methods.
+#[must_use = "Requests are lazy and do nothing unless sent"]
+pub struct MigratingRequest<R: HasPayload> {
+    inner: R,
+    chat_id: fn(&mut R::Payload) -> &mut Recipient,
+    migrations: Migrations,
+}
+
+impl<R: HasPayload> HasPayload for MigratingRequest<R> {
+    type Payload = R::Payload;
+
+    fn payload_mut(&mut self) -> &mut Self::Payload {
+        self.inner.payload_mut()
+    }
+
+    fn payload_ref(&self) -> &Self::Payload {
+        self.inner.payload_ref()
+    }
+}
+
+impl<R> Request for MigratingRequest<R>
+where
+    R: Request + Clone + Send,
+    R::Err: AsResponseParameters,
+{
+    type Err = R::Err;
+    type Send = MigratingSend<R>;
+    type SendRef = MigratingSend<R>;
+
+    fn send(self) -> Self::Send {
+        MigratingSend {
+            state: State::First {
+                fut: self.inner.send_ref(),
+                request: Some(self.inner),
+            },
+            chat_id: self.chat_id,
+            migrations: self.migrations,
+        }
+    }
+
+    fn send_ref(&self) -> Self::SendRef {
+        MigratingSend {
+            state: State::First {
+                fut: self.inner.send_ref(),
+                request: Some(self.inner.clone()),
+            },
+            chat_id: self.chat_id,
+            migrations: self.migrations.clone(),
+        }
+    }
+}
+
+/// Future returned by [`MigratingRequest`]s.
+#[pin_project::pin_project]
+pub struct MigratingSend<R: Request> {
+    #[pin]
+    state: State<R>,
+    chat_id: fn(&mut R::Payload) -> &mut Recipient,
+    migrations: Migrations,
+}
+
+#[pin_project::pin_project(project = StateProj)]
+enum State<R: Request> {
+    /// The request is sent for the first time, `request` is kept to retry it
+    /// if the chat was migrated.
+    First {
+        #[pin]
+        fut: R::SendRef,
+        request: Option<R>,
+    },
+    /// The request is retried with the new chat id.
+    Retry {
+        #[pin]
+        fut: R::Send,
+    },
+}
+
+impl<R> Future for MigratingSend<R>
+where
+    R: Request,
+    R::Err: AsResponseParameters,
+{
+    type Output = Result<Output<R>, R::Err>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut this = self.project();
+
+        loop {
+            match this.state.as_mut().project() {
+                StateProj::First { fut, request } => {
+                    let res = futures::ready!(fut.poll(cx));
+
+                    let to = match res.as_ref().err().and_then(<_>::migrate_to_chat_id) {
+                        Some(to) => ChatId(to),
+                        None => return Poll::Ready(res),
+                    };
+
+                    let mut request = request.take().expect("request is only taken once");
+                    this.migrations
+                        .migrate((this.chat_id)(request.payload_mut()), to);
+
+                    log::debug!("Chat was migrated to {}, retrying the request", to);
+
+                    this.state.set(State::Retry {
+                        fut: request.send(),
+                    });
+                }
+                StateProj::Retry { fut } => return fut.poll(cx),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    };
+
+    use futures::future::{ready, Ready};
+
+    use crate::{
+        adaptors::AutoMigrate,
+        errors::RequestError,
+        payloads::SendMessage,
+        requests::{HasPayload, Request},
+        types::{ChatId, Message, Recipient},
+        Bot,
+    };
+
+    use super::MigratingRequest;
+
+    /// Request which fails with [`RequestError::MigrateToChatId`] for chat
+    /// `-1` and records chat ids of all sent payloads.
+    #[derive(Clone)]
+    struct StubRequest {
+        payload: SendMessage,
+        sent: Arc<Mutex<Vec<Recipient>>>,
+    }
+
+    impl HasPayload for StubRequest {
+        type Payload = SendMessage;
+
+        fn payload_mut(&mut self) -> &mut SendMessage {
+            &mut self.payload
+        }
+
+        fn payload_ref(&self) -> &SendMessage {
+            &self.payload
+        }
+    }
+
+    impl Request for StubRequest {
+        type Err = RequestError;
+        type Send = Ready<Result<Message, RequestError>>;
+        type SendRef = Ready<Result<Message, RequestError>>;
+
+        fn send(self) -> Self::Send {
+            self.send_ref()
+        }
+
+        fn send_ref(&self) -> Self::SendRef {
+            let chat_id = self.payload.chat_id.clone();
+            self.sent.lock().unwrap().push(chat_id.clone());
+
+            if chat_id == Recipient::Id(ChatId(-1)) {
+                return ready(Err(RequestError::MigrateToChatId(-100)));
+            }
+
+            let message = serde_json::from_value(serde_json::json!({
+                "message_id": 1,
+                "date": 0,
+                "chat": { "id": -100, "type": "supergroup", "title": "A" },
+                "text": self.payload.text,
+            }));
+            ready(Ok(message.unwrap()))
+        }
+    }
+
+    #[tokio::test]
+    async fn retry() {
+        let bot = AutoMigrate::new(Bot::new("TOKEN"), |_, _| {});
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let request = MigratingRequest {
+            inner: StubRequest {
+                payload: SendMessage::new(ChatId(-1), "text"),
+                sent: Arc::clone(&sent),
+            },
+            chat_id: |p| &mut p.chat_id,
+            migrations: bot.migrations.clone(),
+        };
+
+        let message = request.send().await.unwrap();
+        assert_eq!(message.chat.id, ChatId(-100));
+        assert_eq!(
+            *sent.lock().unwrap(),
+            [Recipient::Id(ChatId(-1)), Recipient::Id(ChatId(-100))]
+        );
+        assert_eq!(bot.migrated_to(ChatId(-1)), Some(ChatId(-100)));
+    }
+
+    #[test]
+    fn migrate_rewrites() {
+        let called = Arc::new(AtomicBool::new(false));
+        let bot = AutoMigrate::new(Bot::new("TOKEN"), {
+            let called = Arc::clone(&called);
+            move |from, to| {
+                assert_eq!((from, to), (ChatId(-1), ChatId(-100)));
+                called.store(true, Ordering::Relaxed);
+            }
+        });
+
+        let mut chat_id = Recipient::Id(ChatId(-1));
+        bot.migrations.migrate(&mut chat_id, ChatId(-100));
+        assert_eq!(chat_id, Recipient::Id(ChatId(-100)));
+        assert!(called.load(Ordering::Relaxed));
+        assert_eq!(bot.migrated_to(ChatId(-1)), Some(ChatId(-100)));
+
+        let mut chat_id = Recipient::Id(ChatId(-1));
+        bot.migrations.rewrite(&mut chat_id);
+        assert_eq!(chat_id, Recipient::Id(ChatId(-100)));
+    }
+}
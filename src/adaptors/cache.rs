@@ -0,0 +1,709 @@
+use std::{
+    any::Any,
+    cmp,
+    collections::HashMap,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use futures::future::BoxFuture;
+use serde::Serialize;
+use url::Url;
+
+use crate::{
+    payloads::{
+        GetChat, GetChatAdministrators, GetChatMember, GetChatMemberCount, GetFile, GetMyCommands,
+        GetStickerSet,
+    },
+    requests::{HasPayload, Output, Payload, Request, Requester},
+    types::*,
+};
+
+/// Caching of responses to read-only requests.
+///
+/// Responses to the following methods are cached for a configurable time
+/// (see [`Ttls`]):
+/// - [`get_chat`]
+/// - [`get_chat_administrators`]
+/// - [`get_chat_member`]
+/// - [`get_chat_member_count`]
+/// - [`get_my_commands`]
+/// - [`get_sticker_set`]
+/// - [`get_file`]
+///
+/// Requests are cached by their payload, i.e. only requests with exactly the
+/// same parameters share a response. Concurrent identical requests are
+/// coalesced: only one of them is sent, the others wait for its response.
+/// Errors are never cached.
+///
+/// The cache can't know when the data changes, so if you change it yourself
+/// (e.g. promote a chat member) or learn about the change from an update,
+/// invalidate the cache with [`Cache::invalidate_chat`] or similar methods.
+///
+/// All other requests are forwarded to the inner bot unchanged.
+///
+/// ## Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use teloxide_core::{adaptors::cache::Ttls, requests::RequesterExt, Bot};
+///
+/// let bot =
+///     Bot::new("TOKEN").cache(Ttls::default().get_chat_member(Some(Duration::from_secs(10))));
+/// ```
+///
+/// [`get_chat`]: crate::requests::Requester::get_chat
+/// [`get_chat_administrators`]: crate::requests::Requester::get_chat_administrators
+/// [`get_chat_member`]: crate::requests::Requester::get_chat_member
+/// [`get_chat_member_count`]: crate::requests::Requester::get_chat_member_count
+/// [`get_my_commands`]: crate::requests::Requester::get_my_commands
+/// [`get_sticker_set`]: crate::requests::Requester::get_sticker_set
+/// [`get_file`]: crate::requests::Requester::get_file
+#[derive(Clone, Debug)]
+pub struct Cache<B> {
+    bot: B,
+    cache: Arc<Entries>,
+}
+
+impl<B> Cache<B> {
+    /// Creates new cache.
+    ///
+    /// Note: it's recommended to use [`RequesterExt::cache`] instead.
+    ///
+    /// [`RequesterExt::cache`]: crate::requests::RequesterExt::cache
+    pub fn new(bot: B, ttls: Ttls) -> Self {
+        Self {
+            bot,
+            cache: Arc::new(Entries {
+                ttls,
+                state: <_>::default(),
+            }),
+        }
+    }
+
+    /// Allows to access the inner bot.
+    pub fn inner(&self) -> &B {
+        &self.bot
+    }
+
+    /// Unwraps the inner bot.
+    pub fn into_inner(self) -> B {
+        self.bot
+    }
+
+    /// Returns time-to-live settings of this cache.
+    pub fn ttls(&self) -> Ttls {
+        self.cache.ttls
+    }
+
+    /// Removes all cached responses.
+    ///
+    /// Note that the cache is shared between clones of self.
+    pub fn clear(&self) {
+        self.cache.invalidate(|_| true);
+    }
+
+    /// Removes cached responses to all requests with payload `P`.
+    pub fn invalidate<P: Payload>(&self) {
+        self.cache.invalidate(|key| key.method == P::NAME);
+    }
+
+    /// Removes the cached response to the request with the given payload.
+    pub fn invalidate_payload<P>(&self, payload: &P)
+    where
+        P: Payload + Serialize,
+    {
+        if let Some(key) = Key::new(payload) {
+            self.cache.invalidate(|k| *k == key);
+        }
+    }
+
+    /// Removes cached responses to all requests related to `chat`
+    /// ([`get_chat`], [`get_chat_administrators`], [`get_chat_member`] and
+    /// [`get_chat_member_count`]).
+    ///
+    /// [`get_chat`]: crate::requests::Requester::get_chat
+    /// [`get_chat_administrators`]: crate::requests::Requester::get_chat_administrators
+    /// [`get_chat_member`]: crate::requests::Requester::get_chat_member
+    /// [`get_chat_member_count`]: crate::requests::Requester::get_chat_member_count
+    pub fn invalidate_chat<C>(&self, chat: C)
+    where
+        C: Into<Recipient>,
+    {
+        let chat = Some(chat.into());
+        self.cache.invalidate(|key| key.chat == chat);
+    }
+}
+
+/// Time-to-live of cached responses used by [`Cache`].
+///
+/// `None` disables caching of the corresponding method.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub struct Ttls {
+    pub get_chat: Option<Duration>,
+    pub get_chat_administrators: Option<Duration>,
+    pub get_chat_member: Option<Duration>,
+    pub get_chat_member_count: Option<Duration>,
+    pub get_my_commands: Option<Duration>,
+    pub get_sticker_set: Option<Duration>,
+    pub get_file: Option<Duration>,
+}
+
+impl Ttls {
+    pub fn get_chat(mut self, val: Option<Duration>) -> Self {
+        self.get_chat = val;
+        self
+    }
+
+    pub fn get_chat_administrators(mut self, val: Option<Duration>) -> Self {
+        self.get_chat_administrators = val;
+        self
+    }
+
+    pub fn get_chat_member(mut self, val: Option<Duration>) -> Self {
+        self.get_chat_member = val;
+        self
+    }
+
+    pub fn get_chat_member_count(mut self, val: Option<Duration>) -> Self {
+        self.get_chat_member_count = val;
+        self
+    }
+
+    pub fn get_my_commands(mut self, val: Option<Duration>) -> Self {
+        self.get_my_commands = val;
+        self
+    }
+
+    pub fn get_sticker_set(mut self, val: Option<Duration>) -> Self {
+        self.get_sticker_set = val;
+        self
+    }
+
+    pub fn get_file(mut self, val: Option<Duration>) -> Self {
+        self.get_file = val;
+        self
+    }
+}
+
+/// By default chat related responses are cached for a minute, commands for 5
+/// minutes, sticker sets for an hour and files for 30 minutes (telegram
+/// guarantees that file links are valid for at least an hour).
+impl Default for Ttls {
+    fn default() -> Self {
+        const MINUTE: Duration = Duration::from_secs(60);
+
+        Self {
+            get_chat: Some(MINUTE),
+            get_chat_administrators: Some(MINUTE),
+            get_chat_member: Some(MINUTE),
+            get_chat_member_count: Some(MINUTE),
+            get_my_commands: Some(5 * MINUTE),
+            get_sticker_set: Some(60 * MINUTE),
+            get_file: Some(30 * MINUTE),
+        }
+    }
+}
+
+/// Payloads of requests cached by [`Cache`].
+pub trait CachedPayload: Payload + Serialize {
+    /// Returns time-to-live of responses to requests with this payload.
+    fn ttl(ttls: &Ttls) -> Option<Duration>;
+}
+
+macro_rules! impl_cached_payload {
+    ($($P:ident => $ttl:ident;)*) => {
+        $(
+            impl CachedPayload for $P {
+                fn ttl(ttls: &Ttls) -> Option<Duration> {
+                    ttls.$ttl
+                }
+            }
+        )*
+    };
+}
+
+impl_cached_payload! {
+    GetChat => get_chat;
+    GetChatAdministrators => get_chat_administrators;
+    GetChatMember => get_chat_member;
+    GetChatMemberCount => get_chat_member_count;
+    GetMyCommands => get_my_commands;
+    GetStickerSet => get_sticker_set;
+    GetFile => get_file;
+}
+
+/// Cached responses shared between [`Cache`] and its requests.
+#[derive(Debug)]
+struct Entries {
+    ttls: Ttls,
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    entries: HashMap<Key, Entry>,
+    /// Locks of keys for which requests are currently in flight.
+    in_flight: HashMap<Key, Arc<futures::lock::Mutex<()>>>,
+    /// Incremented on every invalidation, so responses to requests which were
+    /// sent before an invalidation are not cached.
+    generation: u64,
+    /// Number of entries at which expired entries are swept.
+    sweep_at: usize,
+}
+
+/// Minimal number of entries at which expired entries are swept.
+const MIN_SWEEP_AT: usize = 64;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct Key {
+    method: &'static str,
+    chat: Option<Recipient>,
+    payload: String,
+}
+
+struct Entry {
+    value: Box<dyn Any + Send + Sync>,
+    expires: Instant,
+}
+
+impl std::fmt::Debug for Entry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Entry")
+            .field("expires", &self.expires)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Key {
+    fn new<P>(payload: &P) -> Option<Self>
+    where
+        P: Payload + Serialize,
+    {
+        let json = serde_json::to_value(payload).ok()?;
+        let chat = json
+            .get("chat_id")
+            .and_then(|chat| serde_json::from_value(chat.clone()).ok());
+
+        Some(Self {
+            method: P::NAME,
+            chat,
+            payload: json.to_string(),
+        })
+    }
+}
+
+impl Entries {
+    fn get<T>(&self, key: &Key) -> Option<T>
+    where
+        T: Clone + 'static,
+    {
+        let mut state = self.state.lock().unwrap();
+
+        match state.entries.get(key) {
+            Some(entry) if entry.expires > Instant::now() => entry.value.downcast_ref().cloned(),
+            Some(_) => {
+                state.entries.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn insert<T>(&self, key: Key, value: T, ttl: Duration, generation: u64)
+    where
+        T: Send + Sync + 'static,
+    {
+        let mut state = self.state.lock().unwrap();
+        if state.generation != generation {
+            return;
+        }
+
+        // Expired entries are removed on lookup, the ones which are never looked
+        // up again are swept when the number of entries doubles
+        let now = Instant::now();
+        if state.entries.len() >= state.sweep_at {
+            state.entries.retain(|_, entry| entry.expires > now);
+            state.sweep_at = cmp::max(state.entries.len() * 2, MIN_SWEEP_AT);
+        }
+
+        state.entries.insert(
+            key,
+            Entry {
+                value: Box::new(value),
+                expires: now + ttl,
+            },
+        );
+    }
+
+    fn invalidate(&self, mut pred: impl FnMut(&Key) -> bool) {
+        let mut state = self.state.lock().unwrap();
+        state.entries.retain(|key, _| !pred(key));
+        state.generation += 1;
+    }
+
+    /// Returns the lock of `key` and the current generation.
+    fn lock(self: &Arc<Self>, key: &Key) -> InFlight {
+        let mut state = self.state.lock().unwrap();
+        let lock = state.in_flight.entry(key.clone()).or_default();
+
+        InFlight {
+            lock: Arc::clone(lock),
+            key: key.clone(),
+            entries: Arc::clone(self),
+        }
+    }
+
+    fn generation(&self) -> u64 {
+        self.state.lock().unwrap().generation
+    }
+}
+
+/// Lock of a key, removes itself from [`State::in_flight`] when the last
+/// request for the key is done.
+struct InFlight {
+    lock: Arc<futures::lock::Mutex<()>>,
+    key: Key,
+    entries: Arc<Entries>,
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        let mut state = self.entries.state.lock().unwrap();
+
+        // One reference is held by the map and one by `self`
+        if Arc::strong_count(&self.lock) == 2 {
+            state.in_flight.remove(&self.key);
+        }
+    }
+}
+
+macro_rules! f {
+    ($m:ident $this:ident ($($arg:ident : $T:ty),*)) => {
+        $this.inner().$m($($arg),*)
+    };
+}
+
+macro_rules! fty {
+    ($T:ident) => {
+        B::$T
+    };
+}
+
+macro_rules! fcached {
+    ($m:ident $this:ident ($($arg:ident : $T:ty),*)) => {
+        CachingRequest {
+            inner: $this.inner().$m($($arg),*),
+            cache: Arc::clone(&$this.cache),
+        }
+    };
+}
+
+macro_rules! ftycached {
+    ($T:ident) => {
+        CachingRequest<B::$T>
+    };
+}
+
+impl<B> Requester for Cache<B>
+where
+    B: Requester,
+    B::Err: std::marker::Send,
+    B::GetChat: 'static,
+    B::GetChatAdministrators: 'static,
+    B::GetChatMember: 'static,
+    B::GetChatMemberCount: 'static,
+    B::GetMyCommands: 'static,
+    B::GetStickerSet: 'static,
+    B::GetFile: 'static,
+{
+    type Err = B::Err;
+
+    requester_forward! {
+        get_chat,
+        get_chat_administrators,
+        get_chat_member,
+        get_chat_member_count,
+        get_my_commands,
+        get_sticker_set,
+        get_file
+        => fcached, ftycached
+    }
+
+    requester_forward! {
+        get_me,
+        log_out,
+        close,
+        get_updates,
+        set_webhook,
+        delete_webhook,
+        get_webhook_info,
+        forward_message,
+        copy_message,
+        send_message,
+        send_photo,
+        send_audio,
+        send_document,
+        send_video,
+        send_animation,
+        send_voice,
+        send_video_note,
+        send_media_group,
+        send_location,
+        edit_message_live_location,
+        edit_message_live_location_inline,
+        stop_message_live_location,
+        stop_message_live_location_inline,
+        send_venue,
+        send_contact,
+        send_poll,
+        send_dice,
+        send_chat_action,
+        get_user_profile_photos,
+        kick_chat_member,
+        ban_chat_member,
+        unban_chat_member,
+        restrict_chat_member,
+        promote_chat_member,
+        set_chat_administrator_custom_title,
+        ban_chat_sender_chat,
+        unban_chat_sender_chat,
+        set_chat_permissions,
+        export_chat_invite_link,
+        create_chat_invite_link,
+        edit_chat_invite_link,
+        revoke_chat_invite_link,
+        set_chat_photo,
+        delete_chat_photo,
+        set_chat_title,
+        set_chat_description,
+        pin_chat_message,
+        unpin_chat_message,
+        unpin_all_chat_messages,
+        leave_chat,
+        get_chat_members_count,
+        set_chat_sticker_set,
+        delete_chat_sticker_set,
+        answer_callback_query,
+        set_my_commands,
+        set_chat_menu_button,
+        get_chat_menu_button,
+        set_my_default_administrator_rights,
+        get_my_default_administrator_rights,
+        delete_my_commands,
+        answer_inline_query,
+        answer_web_app_query,
+        edit_message_text,
+        edit_message_text_inline,
+        edit_message_caption,
+        edit_message_caption_inline,
+        edit_message_media,
+        edit_message_media_inline,
+        edit_message_reply_markup,
+        edit_message_reply_markup_inline,
+        stop_poll,
+        delete_message,
+        send_sticker,
+        upload_sticker_file,
+        create_new_sticker_set,
+        add_sticker_to_set,
+        set_sticker_position_in_set,
+        delete_sticker_from_set,
+        set_sticker_set_thumb,
+        send_invoice,
+        create_invoice_link,
+        answer_shipping_query,
+        answer_pre_checkout_query,
+        set_passport_data_errors,
+        send_game,
+        set_game_score,
+        set_game_score_inline,
+        get_game_high_scores,
+        approve_chat_join_request,
+        decline_chat_join_request
+        => f, fty
+    }
+}
+
+download_forward! {
+    'w
+    B
+    Cache<B>
+    { this => this.inner() }
+}
+
+/// Request returned by [`Cache`] methods.
+#[must_use = "Requests are lazy and do nothing unless sent"]
+pub struct CachingRequest<R> {
+    inner: R,
+    cache: Arc<Entries>,
+}
+
+/// Future returned by [`CachingRequest`]s.
+#[pin_project::pin_project]
+pub struct CachingSend<R: Request>(#[pin] BoxFuture<'static, Result<Output<R>, R::Err>>);
+
+impl<R: HasPayload> HasPayload for CachingRequest<R> {
+    type Payload = R::Payload;
+
+    fn payload_mut(&mut self) -> &mut Self::Payload {
+        self.inner.payload_mut()
+    }
+
+    fn payload_ref(&self) -> &Self::Payload {
+        self.inner.payload_ref()
+    }
+}
+
+impl<R> Request for CachingRequest<R>
+where
+    R: Request + 'static,
+    R::Payload: CachedPayload,
+    R::Err: std::marker::Send,
+    Output<R>: Clone + std::marker::Send + Sync,
+{
+    type Err = R::Err;
+    type Send = CachingSend<R>;
+    type SendRef = CachingSend<R>;
+
+    fn send(self) -> Self::Send {
+        let key = self.key();
+        CachingSend(Box::pin(send(self.cache, key, self.inner.send())))
+    }
+
+    fn send_ref(&self) -> Self::SendRef {
+        CachingSend(Box::pin(send(
+            Arc::clone(&self.cache),
+            self.key(),
+            self.inner.send_ref(),
+        )))
+    }
+}
+
+impl<R> CachingRequest<R>
+where
+    R: Request,
+    R::Payload: CachedPayload,
+{
+    /// Returns the cache key and ttl, or `None` if the request shouldn't be
+    /// cached.
+    fn key(&self) -> Option<(Key, Duration)> {
+        let ttl = R::Payload::ttl(&self.cache.ttls)?;
+        let key = Key::new(self.payload_ref())?;
+
+        Some((key, ttl))
+    }
+}
+
+impl<R: Request> Future for CachingSend<R> {
+    type Output = Result<Output<R>, R::Err>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.project().0.poll(cx)
+    }
+}
+
+/// Actual implementation of the `CachingSend` future
+async fn send<F, T, E>(cache: Arc<Entries>, key: Option<(Key, Duration)>, fut: F) -> Result<T, E>
+where
+    F: Future<Output = Result<T, E>>,
+    T: Clone + std::marker::Send + Sync + 'static,
+{
+    let (key, ttl) = match key {
+        Some(key) => key,
+        None => return fut.await,
+    };
+
+    if let Some(value) = cache.get(&key) {
+        return Ok(value);
+    }
+
+    let in_flight = cache.lock(&key);
+    let _guard = in_flight.lock.lock().await;
+
+    // The response could be cached by an identical request while we were
+    // waiting for the lock
+    if let Some(value) = cache.get(&key) {
+        return Ok(value);
+    }
+
+    let generation = cache.generation();
+    let value = fut.await?;
+    cache.insert(key, value.clone(), ttl, generation);
+
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            atomic::{AtomicU32, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use crate::{
+        adaptors::{cache::Ttls, Cache},
+        payloads::GetChatMemberCount,
+        types::ChatId,
+        Bot,
+    };
+
+    use super::{send, Entries, Key};
+
+    #[tokio::test]
+    async fn coalesce_and_invalidate() {
+        let bot = Cache::new(Bot::new("TOKEN"), Ttls::default());
+        let sent = Arc::new(AtomicU32::new(0));
+
+        let payload = GetChatMemberCount::new(ChatId(1));
+        let key = || Some((Key::new(&payload).unwrap(), Duration::from_secs(60)));
+        let fetch = || {
+            let sent = Arc::clone(&sent);
+            async move {
+                tokio::task::yield_now().await;
+                Ok::<_, ()>(sent.fetch_add(1, Ordering::SeqCst))
+            }
+        };
+
+        let (a, b) = tokio::join!(
+            send(Arc::clone(&bot.cache), key(), fetch()),
+            send(Arc::clone(&bot.cache), key(), fetch())
+        );
+        assert_eq!((a, b), (Ok(0), Ok(0)));
+        assert_eq!(sent.load(Ordering::SeqCst), 1);
+        assert!(bot.cache.state.lock().unwrap().in_flight.is_empty());
+
+        bot.invalidate_chat(ChatId(2));
+        assert_eq!(send(Arc::clone(&bot.cache), key(), fetch()).await, Ok(0));
+
+        bot.invalidate_chat(ChatId(1));
+        assert_eq!(send(Arc::clone(&bot.cache), key(), fetch()).await, Ok(1));
+    }
+
+    #[test]
+    fn sweep() {
+        let entries = Entries {
+            ttls: Ttls::default(),
+            state: <_>::default(),
+        };
+        let key = |n: i64| Key::new(&GetChatMemberCount::new(ChatId(n))).unwrap();
+
+        for n in 0..1000 {
+            entries.insert(key(n), (), Duration::ZERO, 0);
+        }
+        // Expired entries are swept, so the map doesn't grow
+        assert!(entries.state.lock().unwrap().entries.len() <= 64);
+
+        entries.insert(key(-1), (), Duration::from_secs(60), 0);
+        assert_eq!(entries.get::<()>(&key(-1)), Some(()));
+        assert_eq!(entries.get::<()>(&key(999)), None);
+    }
+}
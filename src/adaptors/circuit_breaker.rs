@@ -0,0 +1,549 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use url::Url;
+
+use crate::{
+    errors::{is_server_error, ApiError, RequestError},
+    requests::{HasPayload, Request, Requester},
+    types::*,
+};
+
+/// Circuit breaker which stops sending requests during outages.
+///
+/// When telegram (or a proxy in front of it) is down, every request waits for
+/// timeouts, so a lot of requests can pile up. This bot adaptor counts
+/// consecutive failures which indicate an outage (network errors and server
+/// errors, see [`OutageError::is_outage`]). After
+/// [`Settings::failure_threshold`] such failures the circuit _opens_ and all
+/// requests fail fast with [`BreakerError::Open`] (without being sent)
+/// for [`Settings::cool_down`]. After that a single _probe_ request is sent: if
+/// it succeeds the circuit _closes_ and requests are sent as usual, otherwise
+/// it opens again.
+///
+/// Note that errors which don't indicate an outage (e.g. "chat not found")
+/// reset the failure counter, just as successful requests.
+///
+/// Errors of the inner bot are wrapped in [`BreakerError::Inner`], so adaptors
+/// which require `Err = RequestError` can only be used _below_ this adaptor.
+///
+/// ## Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use teloxide_core::{adaptors::circuit_breaker::Settings, requests::RequesterExt, Bot};
+///
+/// let bot = Bot::new("TOKEN").circuit_breaker(
+///     Settings::default()
+///         .failure_threshold(10)
+///         .cool_down(Duration::from_secs(60)),
+/// );
+/// ```
+#[derive(Clone, Debug)]
+pub struct CircuitBreaker<B> {
+    bot: B,
+    breaker: Arc<Breaker>,
+}
+
+impl<B> CircuitBreaker<B> {
+    /// Creates new [`CircuitBreaker`].
+    ///
+    /// Note: it's recommended to use [`RequesterExt::circuit_breaker`] instead.
+    ///
+    /// [`RequesterExt::circuit_breaker`]: crate::requests::RequesterExt::circuit_breaker
+    pub fn new(bot: B, settings: Settings) -> Self {
+        Self {
+            bot,
+            breaker: Arc::new(Breaker {
+                settings,
+                state: Mutex::new(State::Closed { failures: 0 }),
+            }),
+        }
+    }
+
+    /// Allows to access the inner bot.
+    pub fn inner(&self) -> &B {
+        &self.bot
+    }
+
+    /// Unwraps the inner bot.
+    pub fn into_inner(self) -> B {
+        self.bot
+    }
+
+    /// Returns current state of the circuit.
+    pub fn state(&self) -> CircuitState {
+        match *self.breaker.state.lock().unwrap() {
+            State::Closed { .. } => CircuitState::Closed,
+            State::Open { until } if until > Instant::now() => CircuitState::Open,
+            State::Open { .. } | State::Probing => CircuitState::HalfOpen,
+        }
+    }
+}
+
+/// Settings used by [`CircuitBreaker`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub struct Settings {
+    /// Number of consecutive failures after which the circuit opens.
+    pub failure_threshold: u32,
+
+    /// For how long the circuit stays open before a probe request is sent.
+    pub cool_down: Duration,
+}
+
+impl Settings {
+    pub fn failure_threshold(mut self, val: u32) -> Self {
+        self.failure_threshold = val;
+        self
+    }
+
+    pub fn cool_down(mut self, val: Duration) -> Self {
+        self.cool_down = val;
+        self
+    }
+}
+
+/// By default the circuit opens after 5 consecutive failures, for 30 seconds.
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            cool_down: Duration::from_secs(30),
+        }
+    }
+}
+
+/// State of the circuit of a [`CircuitBreaker`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum CircuitState {
+    /// Requests are sent as usual.
+    Closed,
+
+    /// Requests fail fast without being sent.
+    Open,
+
+    /// The cool-down period has passed, the next request is (or is being) sent
+    /// as a probe. Other requests fail fast until the probe is done.
+    HalfOpen,
+}
+
+/// An error returned by requests of [`CircuitBreaker`].
+#[derive(Debug, thiserror::Error)]
+pub enum BreakerError<E> {
+    /// The request wasn't sent because the circuit is open.
+    #[error("The request wasn't sent because the circuit breaker is open")]
+    Open,
+
+    /// An error returned by the inner bot.
+    #[error(transparent)]
+    Inner(E),
+}
+
+/// Errors which can be classified by [`CircuitBreaker`].
+pub trait OutageError {
+    /// Returns `true` if the error indicates that telegram is unavailable.
+    fn is_outage(&self) -> bool;
+}
+
+/// Network errors, server errors (`5xx`) and responses that are not valid JSON
+/// (usually, error pages of proxies) are considered outages.
+impl OutageError for RequestError {
+    fn is_outage(&self) -> bool {
+        match self {
+            RequestError::Network(_) | RequestError::InvalidJson { .. } => true,
+            RequestError::Api(ApiError::Unknown(description)) => is_server_error(description),
+            _ => false,
+        }
+    }
+}
+
+/// State shared between [`CircuitBreaker`] and its requests.
+#[derive(Debug)]
+struct Breaker {
+    settings: Settings,
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum State {
+    Closed { failures: u32 },
+    Open { until: Instant },
+    Probing,
+}
+
+/// Permission to send a request.
+struct Permit {
+    breaker: Arc<Breaker>,
+    probe: bool,
+}
+
+impl Breaker {
+    /// Returns a permit to send a request, or `None` if the request should fail
+    /// fast.
+    fn acquire(self: &Arc<Self>) -> Option<Permit> {
+        let mut state = self.state.lock().unwrap();
+
+        let probe = match *state {
+            State::Closed { .. } => false,
+            State::Open { until } if until <= Instant::now() => {
+                log::debug!("Circuit is half-open, sending a probe request");
+                *state = State::Probing;
+                true
+            }
+            State::Open { .. } | State::Probing => return None,
+        };
+
+        Some(Permit {
+            breaker: Arc::clone(self),
+            probe,
+        })
+    }
+}
+
+impl Permit {
+    fn record(mut self, outage: bool) {
+        let settings = self.breaker.settings;
+        let mut state = self.breaker.state.lock().unwrap();
+
+        *state = match (*state, outage) {
+            // Requests sent before the circuit was opened may finish after, don't
+            // prolong the cool-down, close the circuit or interfere with the
+            // probe because of them
+            (state @ (State::Open { .. } | State::Probing), _) if !self.probe => state,
+            (_, false) => {
+                if self.probe {
+                    log::info!("Probe request succeeded, circuit is closed");
+                }
+
+                State::Closed { failures: 0 }
+            }
+            (State::Closed { failures }, true) if failures + 1 < settings.failure_threshold => {
+                State::Closed {
+                    failures: failures + 1,
+                }
+            }
+            (_, true) => {
+                log::warn!(
+                    "Telegram seems to be unavailable, circuit is open for {:?}",
+                    settings.cool_down
+                );
+
+                State::Open {
+                    until: Instant::now() + settings.cool_down,
+                }
+            }
+        };
+
+        drop(state);
+        // The probe is done, `Drop` shouldn't reset the state
+        self.probe = false;
+    }
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        // The probe was cancelled, allow sending another one
+        if self.probe {
+            let mut state = self.breaker.state.lock().unwrap();
+            if let State::Probing = *state {
+                *state = State::Open {
+                    until: Instant::now(),
+                };
+            }
+        }
+    }
+}
+
+macro_rules! f {
+    ($m:ident $this:ident ($($arg:ident : $T:ty),*)) => {
+        BreakerRequest {
+            inner: $this.inner().$m($($arg),*),
+            breaker: Arc::clone(&$this.breaker),
+        }
+    };
+}
+
+macro_rules! fty {
+    ($T:ident) => {
+        BreakerRequest<B::$T>
+    };
+}
+
+impl<B> Requester for CircuitBreaker<B>
+where
+    B: Requester,
+    B::Err: OutageError,
+{
+    type Err = BreakerError<B::Err>;
+
+    requester_forward! {
+        get_me,
+        log_out,
+        close,
+        get_updates,
+        set_webhook,
+        delete_webhook,
+        get_webhook_info,
+        forward_message,
+        copy_message,
+        send_message,
+        send_photo,
+        send_audio,
+        send_document,
+        send_video,
+        send_animation,
+        send_voice,
+        send_video_note,
+        send_media_group,
+        send_location,
+        edit_message_live_location,
+        edit_message_live_location_inline,
+        stop_message_live_location,
+        stop_message_live_location_inline,
+        send_venue,
+        send_contact,
+        send_poll,
+        send_dice,
+        send_chat_action,
+        get_user_profile_photos,
+        get_file,
+        kick_chat_member,
+        ban_chat_member,
+        unban_chat_member,
+        restrict_chat_member,
+        promote_chat_member,
+        set_chat_administrator_custom_title,
+        ban_chat_sender_chat,
+        unban_chat_sender_chat,
+        set_chat_permissions,
+        export_chat_invite_link,
+        create_chat_invite_link,
+        edit_chat_invite_link,
+        revoke_chat_invite_link,
+        set_chat_photo,
+        delete_chat_photo,
+        set_chat_title,
+        set_chat_description,
+        pin_chat_message,
+        unpin_chat_message,
+        unpin_all_chat_messages,
+        leave_chat,
+        get_chat,
+        get_chat_administrators,
+        get_chat_members_count,
+        get_chat_member_count,
+        get_chat_member,
+        set_chat_sticker_set,
+        delete_chat_sticker_set,
+        answer_callback_query,
+        set_my_commands,
+        get_my_commands,
+        set_chat_menu_button,
+        get_chat_menu_button,
+        set_my_default_administrator_rights,
+        get_my_default_administrator_rights,
+        delete_my_commands,
+        answer_inline_query,
+        answer_web_app_query,
+        edit_message_text,
+        edit_message_text_inline,
+        edit_message_caption,
+        edit_message_caption_inline,
+        edit_message_media,
+        edit_message_media_inline,
+        edit_message_reply_markup,
+        edit_message_reply_markup_inline,
+        stop_poll,
+        delete_message,
+        send_sticker,
+        get_sticker_set,
+        upload_sticker_file,
+        create_new_sticker_set,
+        add_sticker_to_set,
+        set_sticker_position_in_set,
+        delete_sticker_from_set,
+        set_sticker_set_thumb,
+        send_invoice,
+        create_invoice_link,
+        answer_shipping_query,
+        answer_pre_checkout_query,
+        set_passport_data_errors,
+        send_game,
+        set_game_score,
+        set_game_score_inline,
+        get_game_high_scores,
+        approve_chat_join_request,
+        decline_chat_join_request
+        => f, fty
+    }
+}
+
+download_forward! {
+    'w
+    B
+    CircuitBreaker<B>
+    { this => this.inner() }
+}
+
+/// Request returned by [`CircuitBreaker`] methods.
+#[must_use = "Requests are lazy and do nothing unless sent"]
+pub struct BreakerRequest<R> {
+    inner: R,
+    breaker: Arc<Breaker>,
+}
+
+impl<R: HasPayload> HasPayload for BreakerRequest<R> {
+    type Payload = R::Payload;
+
+    fn payload_mut(&mut self) -> &mut Self::Payload {
+        self.inner.payload_mut()
+    }
+
+    fn payload_ref(&self) -> &Self::Payload {
+        self.inner.payload_ref()
+    }
+}
+
+impl<R> Request for BreakerRequest<R>
+where
+    R: Request,
+    R::Err: OutageError,
+{
+    type Err = BreakerError<R::Err>;
+    type Send = BreakerSend<R::Send>;
+    type SendRef = BreakerSend<R::SendRef>;
+
+    fn send(self) -> Self::Send {
+        BreakerSend {
+            inner: self.inner.send(),
+            breaker: self.breaker,
+            permit: None,
+        }
+    }
+
+    fn send_ref(&self) -> Self::SendRef {
+        BreakerSend {
+            inner: self.inner.send_ref(),
+            breaker: Arc::clone(&self.breaker),
+            permit: None,
+        }
+    }
+}
+
+/// Future returned by [`BreakerRequest`]s.
+#[pin_project::pin_project]
+pub struct BreakerSend<F> {
+    #[pin]
+    inner: F,
+    breaker: Arc<Breaker>,
+    permit: Option<Permit>,
+}
+
+impl<F, T, E> Future for BreakerSend<F>
+where
+    F: Future<Output = Result<T, E>>,
+    E: OutageError,
+{
+    type Output = Result<T, BreakerError<E>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+
+        // The circuit is checked on the first poll
+        if this.permit.is_none() {
+            match this.breaker.acquire() {
+                Some(permit) => *this.permit = Some(permit),
+                None => return Poll::Ready(Err(BreakerError::Open)),
+            }
+        }
+
+        let res = futures::ready!(this.inner.poll(cx));
+        if let Some(permit) = this.permit.take() {
+            permit.record(matches!(&res, Err(err) if err.is_outage()));
+        }
+
+        Poll::Ready(res.map_err(BreakerError::Inner))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use crate::{
+        adaptors::circuit_breaker::{CircuitBreaker, CircuitState, OutageError, Permit, Settings},
+        errors::{ApiError, RequestError},
+        Bot,
+    };
+
+    #[test]
+    fn outages() {
+        let unknown =
+            |description: &str| RequestError::Api(ApiError::Unknown(description.to_owned()));
+
+        assert!(unknown("Bad Gateway").is_outage());
+        assert!(unknown("Internal Server Error: restart").is_outage());
+        // Only reason phrases at the start count
+        assert!(!unknown("Bad Request: Bad Gateway").is_outage());
+        assert!(!RequestError::Api(ApiError::ChatNotFound).is_outage());
+    }
+
+    #[test]
+    fn open_probe_close() {
+        let bot = CircuitBreaker::new(
+            Bot::new("TOKEN"),
+            Settings::default()
+                .failure_threshold(2)
+                .cool_down(Duration::ZERO),
+        );
+        let breaker = &bot.breaker;
+
+        breaker.acquire().unwrap().record(true);
+        assert_eq!(bot.state(), CircuitState::Closed);
+        breaker.acquire().unwrap().record(true);
+        assert_eq!(bot.state(), CircuitState::HalfOpen);
+
+        // Only one probe at a time
+        let probe = breaker.acquire().unwrap();
+        assert!(breaker.acquire().is_none());
+
+        // Cancelled probe allows another one
+        drop(probe);
+        let probe = breaker.acquire().unwrap();
+
+        // Requests which were sent before the circuit opened don't affect it
+        let early = || Permit {
+            breaker: Arc::clone(breaker),
+            probe: false,
+        };
+        early().record(false);
+        early().record(true);
+        assert_eq!(bot.state(), CircuitState::HalfOpen);
+        assert!(breaker.acquire().is_none());
+
+        probe.record(false);
+        assert_eq!(bot.state(), CircuitState::Closed);
+
+        // Non-outage errors reset the counter
+        breaker.acquire().unwrap().record(true);
+        breaker.acquire().unwrap().record(false);
+        breaker.acquire().unwrap().record(true);
+        assert_eq!(bot.state(), CircuitState::Closed);
+    }
+
+    #[test]
+    fn open() {
+        let bot = CircuitBreaker::new(Bot::new("TOKEN"), Settings::default().failure_threshold(1));
+
+        bot.breaker.acquire().unwrap().record(true);
+        assert_eq!(bot.state(), CircuitState::Open);
+        assert!(bot.breaker.acquire().is_none());
+    }
+}
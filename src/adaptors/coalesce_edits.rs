@@ -0,0 +1,712 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use futures::future::BoxFuture;
+use tokio::sync::Notify;
+use url::Url;
+
+use crate::{
+    payloads::{
+        EditMessageCaption, EditMessageCaptionInline, EditMessageLiveLocation,
+        EditMessageLiveLocationInline, EditMessageMedia, EditMessageMediaInline,
+        EditMessageReplyMarkup, EditMessageReplyMarkupInline, EditMessageText,
+        EditMessageTextInline,
+    },
+    requests::{HasPayload, Output, Payload, Request, Requester},
+    types::*,
+};
+
+/// Coalescing of edits of the same message.
+///
+/// Repeatedly editing the same message (e.g. to show a progress bar) quickly
+/// hits flood limits and often fails with [`ApiError::MessageNotModified`].
+/// This bot adaptor sends edits of the same message at most once per
+/// `interval`. Edits which are made while a previous edit is in flight or
+/// waiting for the interval to pass are coalesced: only the latest of them is
+/// sent, the others are _skipped_ and fail with [`CoalesceError::Skipped`]
+/// once the edit which superseded them succeeds.
+///
+/// If the latest edit fails, the error is returned only from it and the most
+/// recent of the superseded edits is sent instead.
+///
+/// Errors of the inner bot are wrapped in [`CoalesceError::Inner`], so
+/// adaptors which require `Err = RequestError` can only be used _below_ this
+/// adaptor.
+///
+/// Edits are coalesced per method and target message (`chat_id` and
+/// `message_id`, or `inline_message_id`), so e.g. [`edit_message_text`]
+/// never supersedes [`edit_message_reply_markup`]. The following methods are
+/// coalesced:
+/// - [`edit_message_text`], [`edit_message_text_inline`]
+/// - [`edit_message_caption`], [`edit_message_caption_inline`]
+/// - [`edit_message_media`], [`edit_message_media_inline`]
+/// - [`edit_message_reply_markup`], [`edit_message_reply_markup_inline`]
+/// - [`edit_message_live_location`], [`edit_message_live_location_inline`]
+///
+/// All other requests are forwarded to the inner bot unchanged.
+///
+/// ## Examples
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use teloxide_core::{adaptors::coalesce_edits::CoalesceError, prelude::*, types::ChatId};
+///
+/// # async {
+/// let bot = Bot::new("TOKEN").coalesce_edits(Duration::from_secs(1));
+/// let chat_id = ChatId(0);
+///
+/// let message = bot.send_message(chat_id, "0%").send().await?;
+/// for percent in 1..=100 {
+///     // Most of these edits are skipped, so this doesn't hit flood limits
+///     let edit = bot
+///         .edit_message_text(chat_id, message.id, format!("{}%", percent))
+///         .send();
+///     tokio::spawn(async move {
+///         match edit.await {
+///             Ok(_) | Err(CoalesceError::Skipped) => {}
+///             Err(CoalesceError::Inner(err)) => log::error!("{}", err),
+///         }
+///     });
+/// }
+/// # Ok::<_, CoalesceError<teloxide_core::RequestError>>(()) };
+/// ```
+///
+/// [`ApiError::MessageNotModified`]: crate::ApiError::MessageNotModified
+/// [`edit_message_text`]: crate::requests::Requester::edit_message_text
+/// [`edit_message_text_inline`]: crate::requests::Requester::edit_message_text_inline
+/// [`edit_message_caption`]: crate::requests::Requester::edit_message_caption
+/// [`edit_message_caption_inline`]: crate::requests::Requester::edit_message_caption_inline
+/// [`edit_message_media`]: crate::requests::Requester::edit_message_media
+/// [`edit_message_media_inline`]: crate::requests::Requester::edit_message_media_inline
+/// [`edit_message_reply_markup`]: crate::requests::Requester::edit_message_reply_markup
+/// [`edit_message_reply_markup_inline`]: crate::requests::Requester::edit_message_reply_markup_inline
+/// [`edit_message_live_location`]: crate::requests::Requester::edit_message_live_location
+/// [`edit_message_live_location_inline`]: crate::requests::Requester::edit_message_live_location_inline
+#[derive(Clone, Debug)]
+pub struct CoalesceEdits<B> {
+    bot: B,
+    coalescer: Arc<Coalescer>,
+}
+
+impl<B> CoalesceEdits<B> {
+    /// Creates new [`CoalesceEdits`] which sends edits of the same message at
+    /// most once per `interval`.
+    ///
+    /// Note: it's recommended to use [`RequesterExt::coalesce_edits`] instead.
+    ///
+    /// [`RequesterExt::coalesce_edits`]: crate::requests::RequesterExt::coalesce_edits
+    pub fn new(bot: B, interval: Duration) -> Self {
+        Self {
+            bot,
+            coalescer: Arc::new(Coalescer {
+                interval,
+                slots: <_>::default(),
+            }),
+        }
+    }
+
+    /// Allows to access the inner bot.
+    pub fn inner(&self) -> &B {
+        &self.bot
+    }
+
+    /// Unwraps the inner bot.
+    pub fn into_inner(self) -> B {
+        self.bot
+    }
+
+    /// Returns the minimal interval between edits of the same message.
+    pub fn interval(&self) -> Duration {
+        self.coalescer.interval
+    }
+}
+
+/// Message edited by a request.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum EditTarget {
+    /// Message sent by the bot, identified by its chat and id.
+    Message { chat_id: Recipient, message_id: i32 },
+
+    /// Message sent via the bot (in inline mode).
+    Inline { inline_message_id: String },
+}
+
+/// An error returned from requests of [`CoalesceEdits`].
+#[derive(Debug, thiserror::Error)]
+pub enum CoalesceError<E> {
+    /// The edit wasn't sent because a newer edit of the same message
+    /// superseded it.
+    #[error("The edit was skipped because a newer edit of the same message superseded it")]
+    Skipped,
+
+    /// An error returned by the inner bot.
+    #[error(transparent)]
+    Inner(E),
+}
+
+/// Payloads of requests coalesced by [`CoalesceEdits`].
+pub trait EditPayload: Payload {
+    /// Returns the message edited by this request.
+    fn target(&self) -> EditTarget;
+}
+
+macro_rules! impl_edit_payload {
+    (chat: $($P:ident),*; inline: $($I:ident),*;) => {
+        $(
+            impl EditPayload for $P {
+                fn target(&self) -> EditTarget {
+                    EditTarget::Message {
+                        chat_id: self.chat_id.clone(),
+                        message_id: self.message_id,
+                    }
+                }
+            }
+        )*
+
+        $(
+            impl EditPayload for $I {
+                fn target(&self) -> EditTarget {
+                    EditTarget::Inline {
+                        inline_message_id: self.inline_message_id.clone(),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_edit_payload! {
+    chat:
+        EditMessageText,
+        EditMessageCaption,
+        EditMessageMedia,
+        EditMessageReplyMarkup,
+        EditMessageLiveLocation;
+    inline:
+        EditMessageTextInline,
+        EditMessageCaptionInline,
+        EditMessageMediaInline,
+        EditMessageReplyMarkupInline,
+        EditMessageLiveLocationInline;
+}
+
+/// State shared between [`CoalesceEdits`] and its requests.
+#[derive(Debug)]
+struct Coalescer {
+    interval: Duration,
+    slots: Mutex<HashMap<Key, Arc<Slot>>>,
+}
+
+type Key = (&'static str, EditTarget);
+
+/// Edits of a single message with a single method.
+#[derive(Debug)]
+struct Slot {
+    state: Mutex<SlotState>,
+    /// Notified whenever an edit is done, cancelled or stops being sent.
+    notify: Notify,
+}
+
+#[derive(Debug)]
+struct SlotState {
+    next_id: u64,
+    /// Ids of edits which are not yet resolved, the greatest one is the latest.
+    pending: BTreeSet<u64>,
+    /// `true` if an edit is being sent (or waits for the interval to pass).
+    sending: bool,
+    last_sent: Option<Instant>,
+    /// Id of the latest successful edit.
+    published: Option<u64>,
+}
+
+impl Slot {
+    /// Returns `true` if the slot can be removed without breaking the interval
+    /// between edits.
+    fn is_idle(&self, now: Instant, interval: Duration) -> bool {
+        let state = self.state.lock().unwrap();
+        let cooled_down = match state.last_sent {
+            Some(last) => last + interval <= now,
+            None => true,
+        };
+
+        state.pending.is_empty() && cooled_down
+    }
+}
+
+impl Coalescer {
+    /// Registers a new edit of `key`.
+    fn register(&self, key: Key) -> Pending {
+        let mut slots = self.slots.lock().unwrap();
+
+        let now = Instant::now();
+        slots.retain(|_, slot| !slot.is_idle(now, self.interval));
+
+        let slot = slots
+            .entry(key)
+            .or_insert_with(|| {
+                Arc::new(Slot {
+                    state: Mutex::new(SlotState {
+                        next_id: 0,
+                        pending: BTreeSet::new(),
+                        sending: false,
+                        last_sent: None,
+                        published: None,
+                    }),
+                    notify: Notify::new(),
+                })
+            })
+            .clone();
+
+        let id = {
+            let mut state = slot.state.lock().unwrap();
+            let id = state.next_id;
+            state.next_id += 1;
+            state.pending.insert(id);
+            id
+        };
+
+        Pending { slot, id }
+    }
+}
+
+enum Turn {
+    /// The edit was superseded by a successful edit.
+    Skipped,
+    /// The edit should be sent, contains the time of the previous edit.
+    Send(Option<Instant>),
+}
+
+impl Slot {
+    /// Waits until the edit `id` is either superseded or should be sent.
+    async fn turn(&self, id: u64) -> Turn {
+        loop {
+            let notified = self.notify.notified();
+
+            {
+                let mut state = self.state.lock().unwrap();
+                if matches!(state.published, Some(published) if published > id) {
+                    return Turn::Skipped;
+                }
+
+                if !state.sending && state.pending.iter().next_back() == Some(&id) {
+                    state.sending = true;
+                    return Turn::Send(state.last_sent);
+                }
+            }
+
+            notified.await;
+        }
+    }
+
+    fn is_latest(&self, id: u64) -> bool {
+        self.state.lock().unwrap().pending.iter().next_back() == Some(&id)
+    }
+}
+
+/// Edit registered in a [`Slot`], unregisters itself on drop.
+struct Pending {
+    slot: Arc<Slot>,
+    id: u64,
+}
+
+impl Drop for Pending {
+    fn drop(&mut self) {
+        self.slot.state.lock().unwrap().pending.remove(&self.id);
+        self.slot.notify.notify_waiters();
+    }
+}
+
+/// Marks a [`Slot`] as not sending on drop.
+struct Sending<'a>(&'a Slot);
+
+impl Drop for Sending<'_> {
+    fn drop(&mut self) {
+        self.0.state.lock().unwrap().sending = false;
+        self.0.notify.notify_waiters();
+    }
+}
+
+macro_rules! f {
+    ($m:ident $this:ident ($($arg:ident : $T:ty),*)) => {
+        ForwardedRequest($this.inner().$m($($arg),*))
+    };
+}
+
+macro_rules! fty {
+    ($T:ident) => {
+        ForwardedRequest<B::$T>
+    };
+}
+
+macro_rules! fcoalesced {
+    ($m:ident $this:ident ($($arg:ident : $T:ty),*)) => {
+        CoalescingRequest {
+            inner: $this.inner().$m($($arg),*),
+            coalescer: Arc::clone(&$this.coalescer),
+        }
+    };
+}
+
+macro_rules! ftycoalesced {
+    ($T:ident) => {
+        CoalescingRequest<B::$T>
+    };
+}
+
+impl<B> Requester for CoalesceEdits<B>
+where
+    B: Requester,
+    B::Err: std::marker::Send,
+    B::EditMessageText: 'static,
+    B::EditMessageTextInline: 'static,
+    B::EditMessageCaption: 'static,
+    B::EditMessageCaptionInline: 'static,
+    B::EditMessageMedia: 'static,
+    B::EditMessageMediaInline: 'static,
+    B::EditMessageReplyMarkup: 'static,
+    B::EditMessageReplyMarkupInline: 'static,
+    B::EditMessageLiveLocation: 'static,
+    B::EditMessageLiveLocationInline: 'static,
+{
+    type Err = CoalesceError<B::Err>;
+
+    requester_forward! {
+        edit_message_text,
+        edit_message_text_inline,
+        edit_message_caption,
+        edit_message_caption_inline,
+        edit_message_media,
+        edit_message_media_inline,
+        edit_message_reply_markup,
+        edit_message_reply_markup_inline,
+        edit_message_live_location,
+        edit_message_live_location_inline
+        => fcoalesced, ftycoalesced
+    }
+
+    requester_forward! {
+        get_me,
+        log_out,
+        close,
+        get_updates,
+        set_webhook,
+        delete_webhook,
+        get_webhook_info,
+        forward_message,
+        copy_message,
+        send_message,
+        send_photo,
+        send_audio,
+        send_document,
+        send_video,
+        send_animation,
+        send_voice,
+        send_video_note,
+        send_media_group,
+        send_location,
+        stop_message_live_location,
+        stop_message_live_location_inline,
+        send_venue,
+        send_contact,
+        send_poll,
+        send_dice,
+        send_chat_action,
+        get_user_profile_photos,
+        get_file,
+        kick_chat_member,
+        ban_chat_member,
+        unban_chat_member,
+        restrict_chat_member,
+        promote_chat_member,
+        set_chat_administrator_custom_title,
+        ban_chat_sender_chat,
+        unban_chat_sender_chat,
+        set_chat_permissions,
+        export_chat_invite_link,
+        create_chat_invite_link,
+        edit_chat_invite_link,
+        revoke_chat_invite_link,
+        set_chat_photo,
+        delete_chat_photo,
+        set_chat_title,
+        set_chat_description,
+        pin_chat_message,
+        unpin_chat_message,
+        unpin_all_chat_messages,
+        leave_chat,
+        get_chat,
+        get_chat_administrators,
+        get_chat_members_count,
+        get_chat_member_count,
+        get_chat_member,
+        set_chat_sticker_set,
+        delete_chat_sticker_set,
+        answer_callback_query,
+        set_my_commands,
+        get_my_commands,
+        set_chat_menu_button,
+        get_chat_menu_button,
+        set_my_default_administrator_rights,
+        get_my_default_administrator_rights,
+        delete_my_commands,
+        answer_inline_query,
+        answer_web_app_query,
+        stop_poll,
+        delete_message,
+        send_sticker,
+        get_sticker_set,
+        upload_sticker_file,
+        create_new_sticker_set,
+        add_sticker_to_set,
+        set_sticker_position_in_set,
+        delete_sticker_from_set,
+        set_sticker_set_thumb,
+        send_invoice,
+        create_invoice_link,
+        answer_shipping_query,
+        answer_pre_checkout_query,
+        set_passport_data_errors,
+        send_game,
+        set_game_score,
+        set_game_score_inline,
+        get_game_high_scores,
+        approve_chat_join_request,
+        decline_chat_join_request
+        => f, fty
+    }
+}
+
+download_forward! {
+    'w
+    B
+    CoalesceEdits<B>
+    { this => this.inner() }
+}
+
+/// Request returned by [`CoalesceEdits`] methods.
+#[must_use = "Requests are lazy and do nothing unless sent"]
+pub struct CoalescingRequest<R> {
+    inner: R,
+    coalescer: Arc<Coalescer>,
+}
+
+/// Future returned by [`CoalescingRequest`]s.
+#[pin_project::pin_project]
+pub struct CoalescingSend<R: Request>(
+    #[pin] BoxFuture<'static, Result<Output<R>, CoalesceError<R::Err>>>,
+);
+
+impl<R: HasPayload> HasPayload for CoalescingRequest<R> {
+    type Payload = R::Payload;
+
+    fn payload_mut(&mut self) -> &mut Self::Payload {
+        self.inner.payload_mut()
+    }
+
+    fn payload_ref(&self) -> &Self::Payload {
+        self.inner.payload_ref()
+    }
+}
+
+impl<R> Request for CoalescingRequest<R>
+where
+    R: Request + 'static,
+    R::Payload: EditPayload,
+    R::Err: std::marker::Send,
+    Output<R>: std::marker::Send,
+{
+    type Err = CoalesceError<R::Err>;
+    type Send = CoalescingSend<R>;
+    type SendRef = CoalescingSend<R>;
+
+    fn send(self) -> Self::Send {
+        let pending = self.register();
+        CoalescingSend(Box::pin(send(
+            pending,
+            self.coalescer.interval,
+            self.inner.send(),
+        )))
+    }
+
+    fn send_ref(&self) -> Self::SendRef {
+        CoalescingSend(Box::pin(send(
+            self.register(),
+            self.coalescer.interval,
+            self.inner.send_ref(),
+        )))
+    }
+}
+
+impl<R> CoalescingRequest<R>
+where
+    R: Request,
+    R::Payload: EditPayload,
+{
+    fn register(&self) -> Pending {
+        let key = (R::Payload::NAME, self.payload_ref().target());
+        self.coalescer.register(key)
+    }
+}
+
+impl<R: Request> Future for CoalescingSend<R> {
+    type Output = Result<Output<R>, CoalesceError<R::Err>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.project().0.poll(cx)
+    }
+}
+
+/// Request returned by [`CoalesceEdits`] methods which are not coalesced.
+#[must_use = "Requests are lazy and do nothing unless sent"]
+pub struct ForwardedRequest<R>(R);
+
+impl<R: HasPayload> HasPayload for ForwardedRequest<R> {
+    type Payload = R::Payload;
+
+    fn payload_mut(&mut self) -> &mut Self::Payload {
+        self.0.payload_mut()
+    }
+
+    fn payload_ref(&self) -> &Self::Payload {
+        self.0.payload_ref()
+    }
+}
+
+impl<R: Request> Request for ForwardedRequest<R> {
+    type Err = CoalesceError<R::Err>;
+    type Send = ForwardedSend<R::Send>;
+    type SendRef = ForwardedSend<R::SendRef>;
+
+    fn send(self) -> Self::Send {
+        ForwardedSend(self.0.send())
+    }
+
+    fn send_ref(&self) -> Self::SendRef {
+        ForwardedSend(self.0.send_ref())
+    }
+}
+
+/// Future returned by [`ForwardedRequest`]s.
+#[pin_project::pin_project]
+pub struct ForwardedSend<F>(#[pin] F);
+
+impl<F, T, E> Future for ForwardedSend<F>
+where
+    F: Future<Output = Result<T, E>>,
+{
+    type Output = Result<T, CoalesceError<E>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.project().0.poll(cx).map_err(CoalesceError::Inner)
+    }
+}
+
+/// Actual implementation of the `CoalescingSend` future
+async fn send<F, T, E>(pending: Pending, interval: Duration, fut: F) -> Result<T, CoalesceError<E>>
+where
+    F: Future<Output = Result<T, E>>,
+{
+    let slot = &*pending.slot;
+
+    loop {
+        let last_sent = match slot.turn(pending.id).await {
+            Turn::Skipped => return Err(CoalesceError::Skipped),
+            Turn::Send(last_sent) => last_sent,
+        };
+        let _sending = Sending(slot);
+
+        if let Some(last_sent) = last_sent {
+            tokio::time::sleep_until((last_sent + interval).into()).await;
+        }
+
+        // A newer edit could be made while we were waiting for the interval
+        if !slot.is_latest(pending.id) {
+            continue;
+        }
+
+        let res = fut.await;
+
+        let mut state = slot.state.lock().unwrap();
+        state.last_sent = Some(Instant::now());
+        if res.is_ok() {
+            state.published = Some(pending.id);
+        }
+
+        return res.map_err(CoalesceError::Inner);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use crate::{
+        adaptors::{
+            coalesce_edits::{CoalesceError, EditTarget},
+            CoalesceEdits,
+        },
+        Bot,
+    };
+
+    use super::send;
+
+    #[tokio::test]
+    async fn coalesce() {
+        let bot = CoalesceEdits::new(Bot::new("TOKEN"), Duration::from_millis(20));
+        let sent = Arc::new(Mutex::new(Vec::new()));
+
+        let key = || {
+            (
+                "editMessageText",
+                EditTarget::Inline {
+                    inline_message_id: "id".to_owned(),
+                },
+            )
+        };
+        let edit = |n: u32| {
+            let sent = Arc::clone(&sent);
+            async move {
+                tokio::task::yield_now().await;
+                sent.lock().unwrap().push(n);
+                if n == 4 {
+                    Err(())
+                } else {
+                    Ok(n)
+                }
+            }
+        };
+        let interval = bot.interval();
+
+        // Edits 2 and 3 are made while edit 1 is in flight
+        let (coalescer, edit_ref) = (&bot.coalescer, &edit);
+        let later = |n| async move {
+            tokio::task::yield_now().await;
+            send(coalescer.register(key()), interval, edit_ref(n)).await
+        };
+        let res = tokio::join!(
+            send(bot.coalescer.register(key()), interval, edit(1)),
+            later(2),
+            later(3),
+        );
+        assert!(matches!(res, (Ok(1), Err(CoalesceError::Skipped), Ok(3))));
+        assert_eq!(*sent.lock().unwrap(), [1, 3]);
+
+        // If the latest edit fails, the superseded one is sent
+        let res = tokio::join!(
+            send(bot.coalescer.register(key()), interval, edit(5)),
+            send(bot.coalescer.register(key()), interval, edit(4)),
+        );
+        assert!(matches!(res, (Ok(5), Err(CoalesceError::Inner(())))));
+        assert_eq!(*sent.lock().unwrap(), [1, 3, 4, 5]);
+    }
+}
@@ -0,0 +1,400 @@
+use url::Url;
+
+use crate::{
+    payloads::*,
+    prelude::Requester,
+    requests::HasPayload,
+    types::{InputFile, ParseMode, Recipient, ReplyMarkup, *},
+};
+
+/// Default values adaptor, see
+/// [`RequesterExt::defaults`](crate::requests::RequesterExt::defaults).
+///
+/// Sets [`Defaults`] to every payload which has corresponding fields, when a
+/// request is created. So the defaults can still be overridden for a particular
+/// request, e.g. `bot.send_message(chat, text).disable_notification(false)`.
+///
+/// This is a generalization of [`DefaultParseMode`].
+///
+/// ## Examples
+///
+/// ```
+/// use teloxide_core::{
+///     adaptors::Defaults,
+///     requests::{Requester, RequesterExt},
+///     types::{ChatId, ParseMode},
+///     Bot,
+/// };
+///
+/// let bot = Bot::new("TOKEN").defaults(
+///     Defaults::new()
+///         .disable_notification(true)
+///         .protect_content(true)
+///         .parse_mode(ParseMode::Html),
+/// );
+///
+/// let request = bot.send_message(ChatId(-1), "<b>Hi!</b>");
+/// assert_eq!(request.disable_notification, Some(true));
+/// assert_eq!(request.protect_content, Some(true));
+/// assert_eq!(request.parse_mode, Some(ParseMode::Html));
+/// ```
+///
+/// [`DefaultParseMode`]: crate::adaptors::DefaultParseMode
+#[derive(Clone, Debug)]
+pub struct DefaultValues<B> {
+    bot: B,
+    defaults: Defaults,
+}
+
+/// Default values of payload fields used by [`DefaultValues`].
+///
+/// `None` means that the corresponding field is left as is.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[non_exhaustive]
+pub struct Defaults {
+    /// Default `parse_mode` of texts and captions (and `explanation_parse_mode`
+    /// of polls).
+    pub parse_mode: Option<ParseMode>,
+
+    /// Default `disable_notification` of requests which send (or pin)
+    /// messages.
+    pub disable_notification: Option<bool>,
+
+    /// Default `protect_content` of requests which send messages.
+    pub protect_content: Option<bool>,
+
+    /// Default `disable_web_page_preview` of requests which send (or edit)
+    /// text messages.
+    pub disable_web_page_preview: Option<bool>,
+
+    /// Default `allow_sending_without_reply` of requests which send messages.
+    pub allow_sending_without_reply: Option<bool>,
+
+    /// Default `reply_markup` of requests which send messages.
+    ///
+    /// Note that it's only set to the requests which accept any
+    /// [`ReplyMarkup`], i.e. it's not set to edits, `send_invoice`, etc.
+    pub reply_markup: Option<ReplyMarkup>,
+}
+
+impl<B> DefaultValues<B> {
+    /// Creates new [`DefaultValues`].
+    ///
+    /// Note: it's recommended to use [`RequesterExt::defaults`] instead.
+    ///
+    /// [`RequesterExt::defaults`]: crate::requests::RequesterExt::defaults
+    pub fn new(bot: B, defaults: Defaults) -> Self {
+        Self { bot, defaults }
+    }
+
+    /// Allows to access the inner bot.
+    pub fn inner(&self) -> &B {
+        &self.bot
+    }
+
+    /// Unwraps the inner bot.
+    pub fn into_inner(self) -> B {
+        self.bot
+    }
+
+    /// Returns currently used [`Defaults`].
+    pub fn defaults(&self) -> &Defaults {
+        &self.defaults
+    }
+}
+
+impl Defaults {
+    /// Creates defaults which don't change anything.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn parse_mode(mut self, val: ParseMode) -> Self {
+        self.parse_mode = Some(val);
+        self
+    }
+
+    pub fn disable_notification(mut self, val: bool) -> Self {
+        self.disable_notification = Some(val);
+        self
+    }
+
+    pub fn protect_content(mut self, val: bool) -> Self {
+        self.protect_content = Some(val);
+        self
+    }
+
+    pub fn disable_web_page_preview(mut self, val: bool) -> Self {
+        self.disable_web_page_preview = Some(val);
+        self
+    }
+
+    pub fn allow_sending_without_reply(mut self, val: bool) -> Self {
+        self.allow_sending_without_reply = Some(val);
+        self
+    }
+
+    pub fn reply_markup<T>(mut self, val: T) -> Self
+    where
+        T: Into<ReplyMarkup>,
+    {
+        self.reply_markup = Some(val.into());
+        self
+    }
+}
+
+/// Payloads which have fields with [`Defaults`].
+trait SetDefaults {
+    fn set_defaults(&mut self, defaults: &Defaults);
+}
+
+macro_rules! impl_set_defaults {
+    ($($P:ident { $($field:ident $(= $default:ident)?),* })*) => {
+        $(
+            impl SetDefaults for $P {
+                fn set_defaults(&mut self, defaults: &Defaults) {
+                    $(
+                        if let Some(val) = &impl_set_defaults!(@default defaults $field $($default)?) {
+                            self.$field = Some(val.clone());
+                        }
+                    )*
+                }
+            }
+        )*
+    };
+    (@default $defaults:ident $field:ident) => {
+        $defaults.$field
+    };
+    (@default $defaults:ident $field:ident $default:ident) => {
+        $defaults.$default
+    };
+}
+
+impl_set_defaults! {
+    ForwardMessage { disable_notification, protect_content }
+    CopyMessage { parse_mode, disable_notification, protect_content, allow_sending_without_reply, reply_markup }
+    SendMessage { parse_mode, disable_notification, protect_content, disable_web_page_preview, allow_sending_without_reply, reply_markup }
+    SendPhoto { parse_mode, disable_notification, protect_content, allow_sending_without_reply, reply_markup }
+    SendAudio { parse_mode, disable_notification, protect_content, allow_sending_without_reply, reply_markup }
+    SendDocument { parse_mode, disable_notification, protect_content, allow_sending_without_reply, reply_markup }
+    SendVideo { parse_mode, disable_notification, protect_content, allow_sending_without_reply, reply_markup }
+    SendAnimation { parse_mode, disable_notification, protect_content, allow_sending_without_reply, reply_markup }
+    SendVoice { parse_mode, disable_notification, allow_sending_without_reply, reply_markup }
+    SendVideoNote { disable_notification, protect_content, allow_sending_without_reply, reply_markup }
+    SendMediaGroup { disable_notification, protect_content, allow_sending_without_reply }
+    SendLocation { disable_notification, protect_content, allow_sending_without_reply, reply_markup }
+    SendVenue { disable_notification, protect_content, allow_sending_without_reply, reply_markup }
+    SendContact { disable_notification, protect_content, allow_sending_without_reply, reply_markup }
+    SendPoll { explanation_parse_mode = parse_mode, disable_notification, protect_content, allow_sending_without_reply, reply_markup }
+    SendDice { disable_notification, protect_content, allow_sending_without_reply, reply_markup }
+    PinChatMessage { disable_notification }
+    EditMessageText { parse_mode, disable_web_page_preview }
+    EditMessageTextInline { parse_mode, disable_web_page_preview }
+    EditMessageCaption { parse_mode }
+    EditMessageCaptionInline { parse_mode }
+    SendSticker { disable_notification, protect_content, allow_sending_without_reply, reply_markup }
+    SendInvoice { disable_notification, protect_content, allow_sending_without_reply }
+    SendGame { disable_notification, protect_content, allow_sending_without_reply }
+}
+
+macro_rules! f {
+    ($m:ident $this:ident ($($arg:ident : $T:ty),*)) => {
+        {
+            let mut req = $this.inner().$m($($arg),*);
+            req.payload_mut().set_defaults(&$this.defaults);
+            req
+        }
+    };
+}
+
+macro_rules! fty {
+    ($T:ident) => {
+        B::$T
+    };
+}
+
+macro_rules! fid {
+    ($m:ident $this:ident ($($arg:ident : $T:ty),*)) => {
+        $this.inner().$m($($arg),*)
+    };
+}
+
+impl<B: Requester> Requester for DefaultValues<B> {
+    type Err = B::Err;
+
+    requester_forward! {
+        forward_message,
+        copy_message,
+        send_message,
+        send_photo,
+        send_audio,
+        send_document,
+        send_video,
+        send_animation,
+        send_voice,
+        send_video_note,
+        send_media_group,
+        send_location,
+        send_venue,
+        send_contact,
+        send_poll,
+        send_dice,
+        pin_chat_message,
+        edit_message_text,
+        edit_message_text_inline,
+        edit_message_caption,
+        edit_message_caption_inline,
+        send_sticker,
+        send_invoice,
+        send_game
+        => f, fty
+    }
+
+    requester_forward! {
+        get_me,
+        log_out,
+        close,
+        get_updates,
+        set_webhook,
+        delete_webhook,
+        get_webhook_info,
+        edit_message_live_location,
+        edit_message_live_location_inline,
+        stop_message_live_location,
+        stop_message_live_location_inline,
+        send_chat_action,
+        get_user_profile_photos,
+        get_file,
+        kick_chat_member,
+        ban_chat_member,
+        unban_chat_member,
+        restrict_chat_member,
+        promote_chat_member,
+        set_chat_administrator_custom_title,
+        ban_chat_sender_chat,
+        unban_chat_sender_chat,
+        set_chat_permissions,
+        export_chat_invite_link,
+        create_chat_invite_link,
+        edit_chat_invite_link,
+        revoke_chat_invite_link,
+        set_chat_photo,
+        delete_chat_photo,
+        set_chat_title,
+        set_chat_description,
+        unpin_chat_message,
+        unpin_all_chat_messages,
+        leave_chat,
+        get_chat,
+        get_chat_administrators,
+        get_chat_members_count,
+        get_chat_member_count,
+        get_chat_member,
+        set_chat_sticker_set,
+        delete_chat_sticker_set,
+        answer_callback_query,
+        set_my_commands,
+        get_my_commands,
+        set_chat_menu_button,
+        get_chat_menu_button,
+        set_my_default_administrator_rights,
+        get_my_default_administrator_rights,
+        delete_my_commands,
+        answer_inline_query,
+        answer_web_app_query,
+        edit_message_media,
+        edit_message_media_inline,
+        edit_message_reply_markup,
+        edit_message_reply_markup_inline,
+        stop_poll,
+        delete_message,
+        get_sticker_set,
+        upload_sticker_file,
+        create_new_sticker_set,
+        add_sticker_to_set,
+        set_sticker_position_in_set,
+        delete_sticker_from_set,
+        set_sticker_set_thumb,
+        create_invoice_link,
+        answer_shipping_query,
+        answer_pre_checkout_query,
+        set_passport_data_errors,
+        set_game_score,
+        set_game_score_inline,
+        get_game_high_scores,
+        approve_chat_join_request,
+        decline_chat_join_request
+        => fid, fty
+    }
+}
+
+download_forward! {
+    'w
+    B
+    DefaultValues<B>
+    { this => this.inner() }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        adaptors::Defaults,
+        payloads::setters::*,
+        requests::{HasPayload, Requester, RequesterExt},
+        types::{ChatId, InputFile, KeyboardRemove, ParseMode, ReplyMarkup},
+        Bot,
+    };
+
+    #[test]
+    fn defaults() {
+        let bot = Bot::new("TOKEN").defaults(
+            Defaults::new()
+                .parse_mode(ParseMode::Html)
+                .disable_notification(true)
+                .disable_web_page_preview(true)
+                .reply_markup(KeyboardRemove::new()),
+        );
+        let remove = || Some(ReplyMarkup::KeyboardRemove(KeyboardRemove::new()));
+
+        let request = bot.send_message(ChatId(1), "text");
+        let payload = request.payload_ref();
+        assert_eq!(payload.parse_mode, Some(ParseMode::Html));
+        assert_eq!(payload.disable_notification, Some(true));
+        assert_eq!(payload.disable_web_page_preview, Some(true));
+        assert_eq!(payload.reply_markup, remove());
+        // Not set in `Defaults`
+        assert_eq!(payload.protect_content, None);
+
+        let request = bot.send_poll(ChatId(1), "?", ["a".to_owned(), "b".to_owned()]);
+        let payload = request.payload_ref();
+        assert_eq!(payload.explanation_parse_mode, Some(ParseMode::Html));
+        assert_eq!(payload.reply_markup, remove());
+
+        // Edits don't get notification settings and reply markup
+        let request = bot.edit_message_caption(ChatId(1), 1);
+        let payload = request.payload_ref();
+        assert_eq!(payload.parse_mode, Some(ParseMode::Html));
+        assert_eq!(payload.reply_markup, None);
+
+        let request = bot.pin_chat_message(ChatId(1), 1);
+        assert_eq!(request.payload_ref().disable_notification, Some(true));
+    }
+
+    #[test]
+    fn explicit_values() {
+        let bot = Bot::new("TOKEN").defaults(
+            Defaults::new()
+                .parse_mode(ParseMode::Html)
+                .disable_notification(true),
+        );
+
+        let request = bot
+            .send_photo(ChatId(1), InputFile::file_id("id"))
+            .parse_mode(ParseMode::MarkdownV2)
+            .disable_notification(false);
+
+        let payload = request.payload_ref();
+        assert_eq!(payload.parse_mode, Some(ParseMode::MarkdownV2));
+        assert_eq!(payload.disable_notification, Some(false));
+    }
+}
@@ -0,0 +1,529 @@
+use std::{
+    any::Any,
+    collections::HashMap,
+    fmt::{self, Debug},
+    sync::{Arc, RwLock},
+};
+
+use futures::future::{ready, Ready};
+use serde::{de::DeserializeOwned, Serialize};
+use url::Url;
+
+use crate::{
+    errors::RequestError,
+    requests::{HasPayload, Output, Payload, Request, Requester},
+    types::*,
+};
+
+mod fabricate;
+
+/// Bot adaptor which never sends requests.
+///
+/// Instead, every request is logged (with the `info` level) and a plausible
+/// response is fabricated: e.g. [`send_message`] returns a [`Message`] with the
+/// text, the chat and the reply markup from the request, boolean methods
+/// return [`True`], [`get_chat_member`] returns a member with the requested
+/// user, etc. Fabricated messages have unique (increasing) ids.
+///
+/// The fabricated output of any method can be overridden with
+/// [`DryRun::with_output`]. If a response can't be fabricated (e.g. the payload
+/// lacks data required by the response), the request fails with
+/// [`RequestError::InvalidJson`] and the output should be overridden.
+///
+/// This is useful for staging environments and load tests, when a bot should
+/// run end-to-end without spamming real chats. Note that [`get_updates`]
+/// always returns no updates (immediately), so updates should be fed to the
+/// bot by other means. Also note that file downloads are forwarded to the
+/// inner bot.
+///
+/// ## Examples
+///
+/// ```
+/// use teloxide_core::{
+///     payloads::GetChatMemberCount,
+///     requests::{Request, Requester, RequesterExt},
+///     types::ChatId,
+///     Bot,
+/// };
+///
+/// # async {
+/// let bot = Bot::new("TOKEN")
+///     .dry_run()
+///     .with_output::<GetChatMemberCount, _>(|_| 42);
+///
+/// let count = bot.get_chat_member_count(ChatId(-1)).send().await?;
+/// assert_eq!(count, 42);
+///
+/// let message = bot.send_message(ChatId(1), "Hi!").send().await?;
+/// assert_eq!(message.text(), Some("Hi!"));
+/// # Ok::<_, teloxide_core::RequestError>(())
+/// # };
+/// ```
+///
+/// [`send_message`]: crate::requests::Requester::send_message
+/// [`get_chat_member`]: crate::requests::Requester::get_chat_member
+/// [`get_updates`]: crate::requests::Requester::get_updates
+/// [`RequestError::InvalidJson`]: crate::RequestError::InvalidJson
+#[derive(Clone, Debug)]
+pub struct DryRun<B> {
+    bot: B,
+    state: Arc<State>,
+}
+
+impl<B> DryRun<B> {
+    /// Creates new [`DryRun`].
+    ///
+    /// Note: it's recommended to use [`RequesterExt::dry_run`] instead.
+    ///
+    /// [`RequesterExt::dry_run`]: crate::requests::RequesterExt::dry_run
+    pub fn new(bot: B) -> Self {
+        Self {
+            bot,
+            state: <_>::default(),
+        }
+    }
+
+    /// Allows to access the inner bot.
+    pub fn inner(&self) -> &B {
+        &self.bot
+    }
+
+    /// Unwraps the inner bot.
+    pub fn into_inner(self) -> B {
+        self.bot
+    }
+
+    /// Overrides the output of requests with payload `P`.
+    ///
+    /// Note that overrides are shared between clones of self.
+    pub fn with_output<P, F>(self, f: F) -> Self
+    where
+        P: Payload + 'static,
+        F: Fn(&P) -> P::Output + std::marker::Send + Sync + 'static,
+    {
+        let f: OutputFn<P> = Arc::new(f);
+        self.state
+            .outputs
+            .write()
+            .unwrap()
+            .insert(P::NAME, Box::new(f));
+
+        self
+    }
+}
+
+type OutputFn<P> = Arc<dyn Fn(&P) -> <P as Payload>::Output + std::marker::Send + Sync>;
+
+/// State shared between [`DryRun`] and its requests.
+#[derive(Default)]
+struct State {
+    ids: fabricate::Ids,
+    /// `OutputFn<P>`s by `P::NAME`.
+    outputs: RwLock<HashMap<&'static str, Box<dyn Any + std::marker::Send + Sync>>>,
+}
+
+impl Debug for State {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("State")
+            .field("ids", &self.ids)
+            .finish_non_exhaustive()
+    }
+}
+
+impl State {
+    fn output<P>(&self, payload: &P) -> Result<P::Output, RequestError>
+    where
+        P: Payload + Debug + Serialize + 'static,
+        P::Output: DeserializeOwned,
+    {
+        log::info!("Dry run of `{}` request: {:?}", P::NAME, payload);
+
+        let f = self
+            .outputs
+            .read()
+            .unwrap()
+            .get(P::NAME)
+            .and_then(|f| f.downcast_ref::<OutputFn<P>>())
+            .cloned();
+        if let Some(f) = f {
+            return Ok(f(payload));
+        }
+
+        let payload = serde_json::to_value(payload).unwrap_or_default();
+        let output = fabricate::output(P::NAME, &payload, &self.ids);
+
+        serde_json::from_value(output.clone()).map_err(|source| {
+            log::error!(
+                "Couldn't fabricate the output of `{}` ({}), use `DryRun::with_output` to set it",
+                P::NAME,
+                source
+            );
+
+            RequestError::InvalidJson {
+                source,
+                raw: output.to_string().into(),
+            }
+        })
+    }
+}
+
+macro_rules! f {
+    ($m:ident $this:ident ($($arg:ident : $T:ty),*)) => {
+        DryRunRequest {
+            inner: $this.inner().$m($($arg),*),
+            state: Arc::clone(&$this.state),
+        }
+    };
+}
+
+macro_rules! fty {
+    ($T:ident) => {
+        DryRunRequest<B::$T>
+    };
+}
+
+impl<B> Requester for DryRun<B>
+where
+    B: Requester,
+    B::Err: From<RequestError> + std::marker::Send,
+{
+    type Err = B::Err;
+
+    requester_forward! {
+        get_me,
+        log_out,
+        close,
+        get_updates,
+        set_webhook,
+        delete_webhook,
+        get_webhook_info,
+        forward_message,
+        copy_message,
+        send_message,
+        send_photo,
+        send_audio,
+        send_document,
+        send_video,
+        send_animation,
+        send_voice,
+        send_video_note,
+        send_media_group,
+        send_location,
+        edit_message_live_location,
+        edit_message_live_location_inline,
+        stop_message_live_location,
+        stop_message_live_location_inline,
+        send_venue,
+        send_contact,
+        send_poll,
+        send_dice,
+        send_chat_action,
+        get_user_profile_photos,
+        get_file,
+        kick_chat_member,
+        ban_chat_member,
+        unban_chat_member,
+        restrict_chat_member,
+        promote_chat_member,
+        set_chat_administrator_custom_title,
+        ban_chat_sender_chat,
+        unban_chat_sender_chat,
+        set_chat_permissions,
+        export_chat_invite_link,
+        create_chat_invite_link,
+        edit_chat_invite_link,
+        revoke_chat_invite_link,
+        set_chat_photo,
+        delete_chat_photo,
+        set_chat_title,
+        set_chat_description,
+        pin_chat_message,
+        unpin_chat_message,
+        unpin_all_chat_messages,
+        leave_chat,
+        get_chat,
+        get_chat_administrators,
+        get_chat_members_count,
+        get_chat_member_count,
+        get_chat_member,
+        set_chat_sticker_set,
+        delete_chat_sticker_set,
+        answer_callback_query,
+        set_my_commands,
+        get_my_commands,
+        set_chat_menu_button,
+        get_chat_menu_button,
+        set_my_default_administrator_rights,
+        get_my_default_administrator_rights,
+        delete_my_commands,
+        answer_inline_query,
+        answer_web_app_query,
+        edit_message_text,
+        edit_message_text_inline,
+        edit_message_caption,
+        edit_message_caption_inline,
+        edit_message_media,
+        edit_message_media_inline,
+        edit_message_reply_markup,
+        edit_message_reply_markup_inline,
+        stop_poll,
+        delete_message,
+        send_sticker,
+        get_sticker_set,
+        upload_sticker_file,
+        create_new_sticker_set,
+        add_sticker_to_set,
+        set_sticker_position_in_set,
+        delete_sticker_from_set,
+        set_sticker_set_thumb,
+        send_invoice,
+        create_invoice_link,
+        answer_shipping_query,
+        answer_pre_checkout_query,
+        set_passport_data_errors,
+        send_game,
+        set_game_score,
+        set_game_score_inline,
+        get_game_high_scores,
+        approve_chat_join_request,
+        decline_chat_join_request
+        => f, fty
+    }
+}
+
+download_forward! {
+    'w
+    B
+    DryRun<B>
+    { this => this.inner() }
+}
+
+/// Request returned by [`DryRun`] methods.
+#[must_use = "Requests are lazy and do nothing unless sent"]
+pub struct DryRunRequest<R> {
+    inner: R,
+    state: Arc<State>,
+}
+
+impl<R: HasPayload> HasPayload for DryRunRequest<R> {
+    type Payload = R::Payload;
+
+    fn payload_mut(&mut self) -> &mut Self::Payload {
+        self.inner.payload_mut()
+    }
+
+    fn payload_ref(&self) -> &Self::Payload {
+        self.inner.payload_ref()
+    }
+}
+
+impl<R> Request for DryRunRequest<R>
+where
+    R: Request,
+    R::Payload: Debug + Serialize + 'static,
+    R::Err: From<RequestError> + std::marker::Send,
+    Output<R>: DeserializeOwned + std::marker::Send,
+{
+    type Err = R::Err;
+    type Send = Ready<Result<Output<R>, R::Err>>;
+    type SendRef = Ready<Result<Output<R>, R::Err>>;
+
+    fn send(self) -> Self::Send {
+        self.send_ref()
+    }
+
+    fn send_ref(&self) -> Self::SendRef {
+        ready(
+            self.state
+                .output(self.inner.payload_ref())
+                .map_err(Into::into),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::de::DeserializeOwned;
+    use serde_json::json;
+
+    use crate::{
+        adaptors::dry_run::{
+            fabricate::{self, Ids},
+            State,
+        },
+        payloads::*,
+        requests::Payload,
+        RequestError,
+    };
+
+    fn check<P>(payload: &serde_json::Value, ids: &Ids)
+    where
+        P: Payload,
+        P::Output: DeserializeOwned,
+    {
+        let output = fabricate::output(P::NAME, payload, ids);
+        if let Err(err) = serde_json::from_value::<P::Output>(output.clone()) {
+            panic!("Couldn't fabricate `{}`: {} ({})", P::NAME, err, output);
+        }
+    }
+
+    #[test]
+    fn fabricate_all() {
+        let payload = json!({
+            "chat_id": -1001,
+            "user_id": 2,
+            "message_id": 3,
+            "text": "text",
+            "caption": "caption",
+            "file_id": "file",
+            "name": "name",
+            "latitude": 1.0,
+            "longitude": 2.0,
+            "title": "title",
+            "address": "address",
+            "description": "description",
+            "phone_number": "+1",
+            "first_name": "Ann",
+            "question": "?",
+            "options": ["a", "b"],
+            "emoji": "🎯",
+            "media": [{ "type": "photo", "media": "a" }, { "type": "video", "media": "b" }],
+            "currency": "USD",
+            "prices": [{ "label": "a", "amount": 100 }],
+            "game_short_name": "game",
+            "reply_markup": { "inline_keyboard": [] },
+        });
+        let ids = Ids::default();
+
+        macro_rules! check_all {
+            ($($P:ident,)*) => {
+                $( check::<$P>(&payload, &ids); )*
+            };
+        }
+
+        check_all! {
+            AddStickerToSet,
+            AnswerCallbackQuery,
+            AnswerInlineQuery,
+            AnswerPreCheckoutQuery,
+            AnswerShippingQuery,
+            AnswerWebAppQuery,
+            ApproveChatJoinRequest,
+            BanChatMember,
+            BanChatSenderChat,
+            Close,
+            CopyMessage,
+            CreateChatInviteLink,
+            CreateInvoiceLink,
+            CreateNewStickerSet,
+            DeclineChatJoinRequest,
+            DeleteChatPhoto,
+            DeleteChatStickerSet,
+            DeleteMessage,
+            DeleteMyCommands,
+            DeleteStickerFromSet,
+            DeleteWebhook,
+            EditChatInviteLink,
+            EditMessageCaption,
+            EditMessageCaptionInline,
+            EditMessageLiveLocation,
+            EditMessageLiveLocationInline,
+            EditMessageMedia,
+            EditMessageMediaInline,
+            EditMessageReplyMarkup,
+            EditMessageReplyMarkupInline,
+            EditMessageText,
+            EditMessageTextInline,
+            ExportChatInviteLink,
+            ForwardMessage,
+            GetChat,
+            GetChatAdministrators,
+            GetChatMember,
+            GetChatMemberCount,
+            GetChatMembersCount,
+            GetChatMenuButton,
+            GetFile,
+            GetGameHighScores,
+            GetMe,
+            GetMyCommands,
+            GetMyDefaultAdministratorRights,
+            GetStickerSet,
+            GetUpdates,
+            GetUserProfilePhotos,
+            GetWebhookInfo,
+            KickChatMember,
+            LeaveChat,
+            LogOut,
+            PinChatMessage,
+            PromoteChatMember,
+            RestrictChatMember,
+            RevokeChatInviteLink,
+            SendAnimation,
+            SendAudio,
+            SendChatAction,
+            SendContact,
+            SendDice,
+            SendDocument,
+            SendGame,
+            SendInvoice,
+            SendLocation,
+            SendMediaGroup,
+            SendMessage,
+            SendPhoto,
+            SendPoll,
+            SendSticker,
+            SendVenue,
+            SendVideo,
+            SendVideoNote,
+            SendVoice,
+            SetChatAdministratorCustomTitle,
+            SetChatDescription,
+            SetChatMenuButton,
+            SetChatPermissions,
+            SetChatPhoto,
+            SetChatStickerSet,
+            SetChatTitle,
+            SetGameScore,
+            SetGameScoreInline,
+            SetMyCommands,
+            SetMyDefaultAdministratorRights,
+            SetPassportDataErrors,
+            SetStickerPositionInSet,
+            SetStickerSetThumb,
+            SetWebhook,
+            StopMessageLiveLocation,
+            StopMessageLiveLocationInline,
+            StopPoll,
+            UnbanChatMember,
+            UnbanChatSenderChat,
+            UnpinAllChatMessages,
+            UnpinChatMessage,
+            UploadStickerFile,
+        }
+
+        for chat_id in [
+            json!(1),
+            json!(-1),
+            json!(-1001234567890i64),
+            json!("@channel"),
+        ] {
+            check::<SendMessage>(&json!({ "chat_id": chat_id, "text": "text" }), &ids);
+        }
+    }
+
+    #[test]
+    fn unfabricatable() {
+        #[derive(Debug, serde::Serialize)]
+        struct Unknown;
+
+        impl Payload for Unknown {
+            type Output = String;
+
+            const NAME: &'static str = "Unknown";
+        }
+
+        // Unknown methods return `true`, which is not a string
+        let res = State::default().output(&Unknown);
+        assert!(matches!(res, Err(RequestError::InvalidJson { .. })));
+    }
+}
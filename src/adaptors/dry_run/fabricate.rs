@@ -0,0 +1,401 @@
+//! Fabrication of plausible responses for [`DryRun`].
+//!
+//! Responses are built as JSON (the same way telegram would send them) and then
+//! deserialized into the output type of a method.
+//!
+//! [`DryRun`]: crate::adaptors::DryRun
+
+use std::{
+    sync::atomic::{AtomicI32, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde_json::{json, Map, Value};
+
+/// Id of the fabricated bot user.
+const BOT_ID: u64 = 1;
+
+/// Source of ids of fabricated messages and files.
+#[derive(Debug)]
+pub(super) struct Ids(AtomicI32);
+
+impl Default for Ids {
+    fn default() -> Self {
+        Self(AtomicI32::new(1))
+    }
+}
+
+impl Ids {
+    fn next(&self) -> i32 {
+        self.0.fetch_add(1, Ordering::Relaxed)
+    }
+
+    fn file(&self) -> Value {
+        let id = self.next();
+
+        json!({
+            "file_id": format!("dry_run_file_{}", id),
+            "file_unique_id": format!("dry_run_unique_{}", id),
+            "file_size": 0,
+        })
+    }
+}
+
+/// Returns a fabricated response of method `name` to a request with `payload`.
+pub(super) fn output(name: &str, payload: &Value, ids: &Ids) -> Value {
+    match name {
+        "GetMe" => me(),
+        "GetUpdates" | "GetChatAdministrators" | "GetMyCommands" => json!([]),
+        "GetWebhookInfo" => json!({
+            "url": "",
+            "has_custom_certificate": false,
+            "pending_update_count": 0,
+        }),
+        "GetChat" => chat(payload.get("chat_id")),
+        "GetChatMember" => json!({
+            "user": user(payload.get("user_id")),
+            "status": "member",
+        }),
+        "GetChatMemberCount" | "GetChatMembersCount" => json!(1),
+        "GetChatMenuButton" => json!({ "type": "default" }),
+        "GetMyDefaultAdministratorRights" => json!({
+            "is_anonymous": false,
+            "can_manage_chat": false,
+            "can_delete_messages": false,
+            "can_manage_video_chats": false,
+            "can_restrict_members": false,
+            "can_promote_members": false,
+            "can_change_info": false,
+            "can_invite_users": false,
+        }),
+        "GetUserProfilePhotos" => json!({ "total_count": 0, "photos": [] }),
+        "GetFile" => {
+            let file_id = payload.get("file_id").cloned().unwrap_or_default();
+            json!({
+                "file_id": file_id,
+                "file_unique_id": file_id,
+                "file_size": 0,
+                "file_path": format!("dry_run/{}", file_id.as_str().unwrap_or_default()),
+            })
+        }
+        "UploadStickerFile" => ids.file(),
+        "GetStickerSet" => {
+            let name = payload.get("name").cloned().unwrap_or_default();
+            json!({
+                "name": name,
+                "title": name,
+                "kind": { "is_animated": false, "is_video": false },
+                "contains_masks": false,
+                "stickers": [],
+            })
+        }
+        "AnswerWebAppQuery" => json!({}),
+        "CreateChatInviteLink" => json!({
+            "invite_link": "https://t.me/+dry_run",
+            "creator": me(),
+            "creates_join_request": payload.get("creates_join_request").cloned().unwrap_or(json!(false)),
+            "is_primary": false,
+            "is_revoked": false,
+            "name": payload.get("name"),
+        }),
+        "CreateInvoiceLink" => json!("https://t.me/$dry_run"),
+        "ExportChatInviteLink"
+        | "EditChatInviteLink"
+        | "RevokeChatInviteLink"
+        | "DeleteChatPhoto" => json!("https://t.me/+dry_run"),
+        "CopyMessage" => json!({ "message_id": ids.next() }),
+        "StopPoll" => {
+            let mut poll = poll(payload, ids);
+            poll["is_closed"] = json!(true);
+            poll
+        }
+        "SendMediaGroup" => {
+            let media = payload.get("media").and_then(Value::as_array);
+            let messages = media
+                .into_iter()
+                .flatten()
+                .map(|item| {
+                    let content = match item.get("type").and_then(Value::as_str) {
+                        Some(kind @ ("video" | "audio" | "document")) => file_content(kind, ids),
+                        _ => json!({ "photo": [photo(ids)] }),
+                    };
+                    message(payload, with_caption(content, item), false, ids)
+                })
+                .collect();
+
+            Value::Array(messages)
+        }
+        "ForwardMessage"
+        | "SendMessage"
+        | "EditMessageText"
+        | "EditMessageCaption"
+        | "EditMessageMedia"
+        | "EditMessageReplyMarkup"
+        | "SendPhoto"
+        | "SendAudio"
+        | "SendDocument"
+        | "SendVideo"
+        | "SendAnimation"
+        | "SendVoice"
+        | "SendVideoNote"
+        | "SendLocation"
+        | "EditMessageLiveLocation"
+        | "EditMessageLiveLocationInline"
+        | "StopMessageLiveLocation"
+        | "StopMessageLiveLocationInline"
+        | "SendVenue"
+        | "SendContact"
+        | "SendPoll"
+        | "SendDice"
+        | "SendSticker"
+        | "SendGame"
+        | "SendInvoice"
+        | "SetGameScore"
+        | "SetGameScoreInline" => {
+            let edit = ["Edit", "Stop", "SetGameScore"]
+                .iter()
+                .any(|prefix| name.starts_with(prefix));
+
+            message(payload, content(name, payload, ids), edit, ids)
+        }
+        // All other methods return `True`
+        _ => json!(true),
+    }
+}
+
+/// Returns content (i.e. media related fields) of a message sent by method
+/// `name`.
+fn content(name: &str, payload: &Value, ids: &Ids) -> Value {
+    let field = |name| payload.get(name).cloned().unwrap_or_default();
+    let location = || {
+        json!({
+            "latitude": field("latitude"),
+            "longitude": field("longitude"),
+        })
+    };
+
+    match name {
+        "SendPhoto" => with_caption(json!({ "photo": [photo(ids)] }), payload),
+        "SendAudio" => with_caption(file_content("audio", ids), payload),
+        "SendDocument" => with_caption(file_content("document", ids), payload),
+        "SendVideo" => with_caption(file_content("video", ids), payload),
+        "SendAnimation" => with_caption(file_content("animation", ids), payload),
+        "SendVoice" => with_caption(file_content("voice", ids), payload),
+        "SendVideoNote" => file_content("video_note", ids),
+        "SendLocation" | "EditMessageLiveLocation" | "EditMessageLiveLocationInline" => {
+            json!({ "location": location() })
+        }
+        "SendVenue" => json!({
+            "location": location(),
+            "venue": {
+                "location": location(),
+                "title": field("title"),
+                "address": field("address"),
+            },
+        }),
+        "SendContact" => json!({
+            "contact": {
+                "phone_number": field("phone_number"),
+                "first_name": field("first_name"),
+                "last_name": payload.get("last_name"),
+            },
+        }),
+        "SendPoll" => json!({ "poll": poll(payload, ids) }),
+        "SendDice" => json!({
+            "dice": {
+                "emoji": payload.get("emoji").cloned().unwrap_or_else(|| json!("🎲")),
+                "value": 1,
+            },
+        }),
+        "SendSticker" => {
+            let mut sticker = ids.file();
+            sticker["width"] = json!(512);
+            sticker["height"] = json!(512);
+            sticker["is_animated"] = json!(false);
+            sticker["is_video"] = json!(false);
+            json!({ "sticker": sticker })
+        }
+        "SendGame" => {
+            let name = field("game_short_name");
+            json!({ "game": { "title": name, "description": name, "photo": [] } })
+        }
+        "SendInvoice" => {
+            let total: i64 = payload
+                .get("prices")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(|price| price.get("amount").and_then(Value::as_i64))
+                .sum();
+
+            json!({
+                "invoice": {
+                    "title": field("title"),
+                    "description": field("description"),
+                    "start_parameter": payload.get("start_parameter").cloned().unwrap_or(json!("")),
+                    "currency": field("currency"),
+                    "total_amount": total,
+                },
+            })
+        }
+        "SendMessage" | "EditMessageText" => json!({
+            "text": field("text"),
+            "entities": payload.get("entities").cloned().unwrap_or(json!([])),
+        }),
+        // The content is unknown, e.g. for forwarded messages or edits of the
+        // reply markup
+        _ => json!({
+            "text": payload.get("caption").cloned().unwrap_or(json!("")),
+            "entities": payload.get("caption_entities").cloned().unwrap_or(json!([])),
+        }),
+    }
+}
+
+/// Returns a message sent by the bot (or edited, if `edit` is `true`).
+fn message(payload: &Value, content: Value, edit: bool, ids: &Ids) -> Value {
+    let date = now();
+    let id = match payload.get("message_id") {
+        Some(id) if edit => id.clone(),
+        _ => json!(ids.next()),
+    };
+
+    let mut message = json!({
+        "message_id": id,
+        "date": date,
+        "chat": chat(payload.get("chat_id")),
+        "from": me(),
+    });
+
+    if edit {
+        message["edit_date"] = json!(date);
+    }
+
+    if let Some(markup) = payload
+        .get("reply_markup")
+        .filter(|m| m.get("inline_keyboard").is_some())
+    {
+        message["reply_markup"] = markup.clone();
+    }
+
+    merge(&mut message, content);
+    message
+}
+
+fn with_caption(mut content: Value, payload: &Value) -> Value {
+    if let Some(caption) = payload.get("caption") {
+        content["caption"] = caption.clone();
+    }
+    if let Some(entities) = payload.get("caption_entities") {
+        content["caption_entities"] = entities.clone();
+    }
+
+    content
+}
+
+/// Returns content of a message with a file of type `kind` (e.g. `"audio"`).
+fn file_content(kind: &str, ids: &Ids) -> Value {
+    let mut file = ids.file();
+    match kind {
+        "video" | "animation" => {
+            file["width"] = json!(0);
+            file["height"] = json!(0);
+            file["duration"] = json!(0);
+            file["mime_type"] = Value::Null;
+        }
+        "audio" | "voice" => {
+            file["duration"] = json!(0);
+            file["mime_type"] = Value::Null;
+        }
+        "video_note" => {
+            file["length"] = json!(0);
+            file["duration"] = json!(0);
+        }
+        _ => {}
+    }
+
+    let mut content = Map::new();
+    content.insert(kind.to_owned(), file);
+    Value::Object(content)
+}
+
+fn photo(ids: &Ids) -> Value {
+    let mut photo = ids.file();
+    photo["width"] = json!(0);
+    photo["height"] = json!(0);
+    photo
+}
+
+fn poll(payload: &Value, ids: &Ids) -> Value {
+    let options = payload.get("options").and_then(Value::as_array);
+
+    json!({
+        "id": format!("dry_run_poll_{}", ids.next()),
+        "question": payload.get("question").cloned().unwrap_or(json!("")),
+        "options": options
+            .into_iter()
+            .flatten()
+            .map(|text| json!({ "text": text, "voter_count": 0 }))
+            .collect::<Vec<_>>(),
+        "is_closed": payload.get("is_closed").cloned().unwrap_or(json!(false)),
+        "total_voter_count": 0,
+        "is_anonymous": payload.get("is_anonymous").cloned().unwrap_or(json!(true)),
+        "type": payload.get("type").cloned().unwrap_or(json!("regular")),
+        "allows_multiple_answers": payload.get("allows_multiple_answers").cloned().unwrap_or(json!(false)),
+    })
+}
+
+/// Returns the chat identified by `chat_id` (a number or a `@username`).
+fn chat(chat_id: Option<&Value>) -> Value {
+    match chat_id {
+        Some(Value::String(username)) => json!({
+            "id": -1001000000000i64,
+            "type": "channel",
+            "title": username,
+            "username": username.trim_start_matches('@'),
+        }),
+        Some(Value::Number(id)) => match id.as_i64().unwrap_or_default() {
+            id if id <= -1_000_000_000_000 => json!({
+                "id": id,
+                "type": "supergroup",
+                "title": "Dry run",
+            }),
+            id if id < 0 => json!({ "id": id, "type": "group", "title": "Dry run" }),
+            id => json!({ "id": id, "type": "private", "first_name": "User" }),
+        },
+        // e.g. inline messages
+        _ => json!({ "id": BOT_ID, "type": "private", "first_name": "Dry run" }),
+    }
+}
+
+fn user(user_id: Option<&Value>) -> Value {
+    json!({
+        "id": user_id.cloned().unwrap_or(json!(BOT_ID)),
+        "is_bot": false,
+        "first_name": "User",
+    })
+}
+
+fn me() -> Value {
+    json!({
+        "id": BOT_ID,
+        "is_bot": true,
+        "first_name": "Dry run",
+        "username": "dry_run_bot",
+        "can_join_groups": true,
+        "can_read_all_group_messages": false,
+        "supports_inline_queries": false,
+    })
+}
+
+fn merge(value: &mut Value, other: Value) {
+    if let (Value::Object(value), Value::Object(other)) = (value, other) {
+        value.extend(other);
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
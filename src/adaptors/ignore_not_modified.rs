@@ -0,0 +1,636 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+use futures::future::BoxFuture;
+use url::Url;
+
+use crate::{
+    errors::{ApiError, RequestError},
+    payloads::{
+        EditMessageCaption, EditMessageCaptionInline, EditMessageMedia, EditMessageMediaInline,
+        EditMessageReplyMarkup, EditMessageReplyMarkupInline, EditMessageText,
+        EditMessageTextInline,
+    },
+    requests::{HasPayload, Output, Payload, Request, Requester},
+    types::*,
+};
+
+/// Treats [`ApiError::MessageNotModified`] as a success.
+///
+/// Telegram returns [`ApiError::MessageNotModified`] when an edit doesn't
+/// change anything, e.g. when a user double-clicks an inline button which
+/// re-renders the same keyboard. This bot adaptor makes the following methods
+/// succeed in this case:
+/// - [`edit_message_text`], [`edit_message_text_inline`]
+/// - [`edit_message_caption`], [`edit_message_caption_inline`]
+/// - [`edit_message_media`], [`edit_message_media_inline`]
+/// - [`edit_message_reply_markup`], [`edit_message_reply_markup_inline`]
+///
+/// Inline variants just return [`True`]. Other variants return the _known_
+/// message: the one returned by the previous successful edit of the same
+/// message or registered with [`IgnoreNotModified::remember`] (e.g. the
+/// message of a [`CallbackQuery`]). If the message is unknown, the error is
+/// returned as is. To get a typed marker instead, use
+/// [`IgnoringRequest::send_edit`] which never fails with
+/// [`ApiError::MessageNotModified`].
+///
+/// Known messages are identified by the numeric id of their chat, so edits
+/// which address a channel by its `@username`
+/// ([`Recipient::ChannelUsername`]) never match a known message and behave
+/// as if the message is unknown.
+///
+/// At most [`IgnoreNotModified::CAPACITY`] known messages are stored, the
+/// oldest ones are forgotten first.
+///
+/// All other requests are forwarded to the inner bot unchanged.
+///
+/// ## Examples
+///
+/// ```no_run
+/// use teloxide_core::{
+///     adaptors::ignore_not_modified::Edit,
+///     prelude::*,
+///     types::{CallbackQuery, InlineKeyboardMarkup},
+/// };
+///
+/// # async fn on_callback(query: CallbackQuery, keyboard: InlineKeyboardMarkup) -> Result<(), teloxide_core::RequestError> {
+/// let bot = Bot::new("TOKEN").ignore_not_modified();
+///
+/// if let Some(message) = &query.message {
+///     bot.remember(message);
+///
+///     // Doesn't fail if the keyboard is the same
+///     let message = bot
+///         .edit_message_reply_markup(message.chat.id, message.id)
+///         .reply_markup(keyboard)
+///         .send()
+///         .await?;
+/// }
+/// # Ok(()) }
+///
+/// # async fn on_callback2(bot: teloxide_core::adaptors::IgnoreNotModified<Bot>, query: CallbackQuery) -> Result<(), teloxide_core::RequestError> {
+/// if let Some(message) = &query.message {
+///     match bot.edit_message_text(message.chat.id, message.id, "text").send_edit().await? {
+///         Edit::Modified(_) => {}
+///         Edit::Unchanged(_) => log::debug!("the text is the same"),
+///     }
+/// }
+/// # Ok(()) }
+/// ```
+///
+/// [`ApiError::MessageNotModified`]: crate::ApiError::MessageNotModified
+/// [`edit_message_text`]: crate::requests::Requester::edit_message_text
+/// [`edit_message_text_inline`]: crate::requests::Requester::edit_message_text_inline
+/// [`edit_message_caption`]: crate::requests::Requester::edit_message_caption
+/// [`edit_message_caption_inline`]: crate::requests::Requester::edit_message_caption_inline
+/// [`edit_message_media`]: crate::requests::Requester::edit_message_media
+/// [`edit_message_media_inline`]: crate::requests::Requester::edit_message_media_inline
+/// [`edit_message_reply_markup`]: crate::requests::Requester::edit_message_reply_markup
+/// [`edit_message_reply_markup_inline`]: crate::requests::Requester::edit_message_reply_markup_inline
+#[derive(Clone, Debug)]
+pub struct IgnoreNotModified<B> {
+    bot: B,
+    known: Arc<Known>,
+}
+
+impl<B> IgnoreNotModified<B> {
+    /// Maximum number of known messages.
+    pub const CAPACITY: usize = 1024;
+
+    /// Creates new [`IgnoreNotModified`].
+    ///
+    /// Note: it's recommended to use [`RequesterExt::ignore_not_modified`]
+    /// instead.
+    ///
+    /// [`RequesterExt::ignore_not_modified`]: crate::requests::RequesterExt::ignore_not_modified
+    pub fn new(bot: B) -> Self {
+        Self {
+            bot,
+            known: Arc::new(Known {
+                capacity: Self::CAPACITY,
+                messages: <_>::default(),
+            }),
+        }
+    }
+
+    /// Allows to access the inner bot.
+    pub fn inner(&self) -> &B {
+        &self.bot
+    }
+
+    /// Unwraps the inner bot.
+    pub fn into_inner(self) -> B {
+        self.bot
+    }
+
+    /// Remembers the current state of `message`, so it can be returned by
+    /// edits which don't modify it.
+    ///
+    /// Note that known messages are shared between clones of self.
+    pub fn remember(&self, message: &Message) {
+        self.known.insert(message.clone());
+    }
+}
+
+/// Result of an edit returned by [`IgnoringRequest::send_edit`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum Edit<T> {
+    /// The message was modified, contains the response.
+    Modified(T),
+
+    /// The message was not modified, contains the known message (or [`True`]
+    /// for inline messages) if any.
+    Unchanged(Option<T>),
+}
+
+/// Errors which can be classified by [`IgnoreNotModified`].
+pub trait NotModifiedError {
+    /// Returns `true` if the error means that the message was not modified.
+    fn is_not_modified(&self) -> bool;
+}
+
+impl NotModifiedError for RequestError {
+    fn is_not_modified(&self) -> bool {
+        matches!(self, RequestError::Api(ApiError::MessageNotModified))
+    }
+}
+
+/// Payloads of requests handled by [`IgnoreNotModified`].
+pub trait NotModifiedPayload: Payload {
+    /// Returns chat and id of the edited message, or `None` for inline
+    /// messages.
+    ///
+    /// Only [`Recipient::Id`] can match a known message.
+    fn message_id(&self) -> Option<(Recipient, i32)>;
+
+    /// Returns the message contained in the output, if any.
+    fn output_message(output: &Self::Output) -> Option<&Message>;
+
+    /// Returns the output of an edit which didn't modify the message, given
+    /// the known message.
+    fn unchanged_output(known: Option<Message>) -> Option<Self::Output>;
+}
+
+macro_rules! impl_not_modified_payload {
+    (chat: $($P:ident),*; inline: $($I:ident),*;) => {
+        $(
+            impl NotModifiedPayload for $P {
+                fn message_id(&self) -> Option<(Recipient, i32)> {
+                    Some((self.chat_id.clone(), self.message_id))
+                }
+
+                fn output_message(output: &Message) -> Option<&Message> {
+                    Some(output)
+                }
+
+                fn unchanged_output(known: Option<Message>) -> Option<Message> {
+                    known
+                }
+            }
+        )*
+
+        $(
+            impl NotModifiedPayload for $I {
+                fn message_id(&self) -> Option<(Recipient, i32)> {
+                    None
+                }
+
+                fn output_message(_: &True) -> Option<&Message> {
+                    None
+                }
+
+                fn unchanged_output(_: Option<Message>) -> Option<True> {
+                    Some(True)
+                }
+            }
+        )*
+    };
+}
+
+impl_not_modified_payload! {
+    chat:
+        EditMessageText,
+        EditMessageCaption,
+        EditMessageMedia,
+        EditMessageReplyMarkup;
+    inline:
+        EditMessageTextInline,
+        EditMessageCaptionInline,
+        EditMessageMediaInline,
+        EditMessageReplyMarkupInline;
+}
+
+/// Known messages shared between [`IgnoreNotModified`] and its requests.
+#[derive(Debug)]
+struct Known {
+    capacity: usize,
+    messages: Mutex<Messages>,
+}
+
+#[derive(Debug, Default)]
+struct Messages {
+    map: HashMap<(Recipient, i32), Message>,
+    /// Keys of `map` from the oldest to the newest.
+    order: VecDeque<(Recipient, i32)>,
+}
+
+impl Known {
+    fn insert(&self, message: Message) {
+        let key = (Recipient::Id(message.chat.id), message.id);
+        let mut messages = self.messages.lock().unwrap();
+
+        if messages.map.insert(key.clone(), message).is_none() {
+            messages.order.push_back(key);
+        }
+
+        while messages.order.len() > self.capacity {
+            let oldest = messages.order.pop_front().unwrap();
+            messages.map.remove(&oldest);
+        }
+    }
+
+    fn get(&self, key: &(Recipient, i32)) -> Option<Message> {
+        self.messages.lock().unwrap().map.get(key).cloned()
+    }
+}
+
+macro_rules! f {
+    ($m:ident $this:ident ($($arg:ident : $T:ty),*)) => {
+        $this.inner().$m($($arg),*)
+    };
+}
+
+macro_rules! fty {
+    ($T:ident) => {
+        B::$T
+    };
+}
+
+macro_rules! fignoring {
+    ($m:ident $this:ident ($($arg:ident : $T:ty),*)) => {
+        IgnoringRequest {
+            inner: $this.inner().$m($($arg),*),
+            known: Arc::clone(&$this.known),
+        }
+    };
+}
+
+macro_rules! ftyignoring {
+    ($T:ident) => {
+        IgnoringRequest<B::$T>
+    };
+}
+
+impl<B> Requester for IgnoreNotModified<B>
+where
+    B: Requester,
+    B::Err: NotModifiedError + std::marker::Send,
+    B::EditMessageText: 'static,
+    B::EditMessageTextInline: 'static,
+    B::EditMessageCaption: 'static,
+    B::EditMessageCaptionInline: 'static,
+    B::EditMessageMedia: 'static,
+    B::EditMessageMediaInline: 'static,
+    B::EditMessageReplyMarkup: 'static,
+    B::EditMessageReplyMarkupInline: 'static,
+{
+    type Err = B::Err;
+
+    requester_forward! {
+        edit_message_text,
+        edit_message_text_inline,
+        edit_message_caption,
+        edit_message_caption_inline,
+        edit_message_media,
+        edit_message_media_inline,
+        edit_message_reply_markup,
+        edit_message_reply_markup_inline
+        => fignoring, ftyignoring
+    }
+
+    requester_forward! {
+        get_me,
+        log_out,
+        close,
+        get_updates,
+        set_webhook,
+        delete_webhook,
+        get_webhook_info,
+        forward_message,
+        copy_message,
+        send_message,
+        send_photo,
+        send_audio,
+        send_document,
+        send_video,
+        send_animation,
+        send_voice,
+        send_video_note,
+        send_media_group,
+        send_location,
+        edit_message_live_location,
+        edit_message_live_location_inline,
+        stop_message_live_location,
+        stop_message_live_location_inline,
+        send_venue,
+        send_contact,
+        send_poll,
+        send_dice,
+        send_chat_action,
+        get_user_profile_photos,
+        get_file,
+        kick_chat_member,
+        ban_chat_member,
+        unban_chat_member,
+        restrict_chat_member,
+        promote_chat_member,
+        set_chat_administrator_custom_title,
+        ban_chat_sender_chat,
+        unban_chat_sender_chat,
+        set_chat_permissions,
+        export_chat_invite_link,
+        create_chat_invite_link,
+        edit_chat_invite_link,
+        revoke_chat_invite_link,
+        set_chat_photo,
+        delete_chat_photo,
+        set_chat_title,
+        set_chat_description,
+        pin_chat_message,
+        unpin_chat_message,
+        unpin_all_chat_messages,
+        leave_chat,
+        get_chat,
+        get_chat_administrators,
+        get_chat_members_count,
+        get_chat_member_count,
+        get_chat_member,
+        set_chat_sticker_set,
+        delete_chat_sticker_set,
+        answer_callback_query,
+        set_my_commands,
+        get_my_commands,
+        set_chat_menu_button,
+        get_chat_menu_button,
+        set_my_default_administrator_rights,
+        get_my_default_administrator_rights,
+        delete_my_commands,
+        answer_inline_query,
+        answer_web_app_query,
+        stop_poll,
+        delete_message,
+        send_sticker,
+        get_sticker_set,
+        upload_sticker_file,
+        create_new_sticker_set,
+        add_sticker_to_set,
+        set_sticker_position_in_set,
+        delete_sticker_from_set,
+        set_sticker_set_thumb,
+        send_invoice,
+        create_invoice_link,
+        answer_shipping_query,
+        answer_pre_checkout_query,
+        set_passport_data_errors,
+        send_game,
+        set_game_score,
+        set_game_score_inline,
+        get_game_high_scores,
+        approve_chat_join_request,
+        decline_chat_join_request
+        => f, fty
+    }
+}
+
+download_forward! {
+    'w
+    B
+    IgnoreNotModified<B>
+    { this => this.inner() }
+}
+
+/// Request returned by [`IgnoreNotModified`] methods.
+#[must_use = "Requests are lazy and do nothing unless sent"]
+pub struct IgnoringRequest<R> {
+    inner: R,
+    known: Arc<Known>,
+}
+
+/// Future returned by [`IgnoringRequest`]s.
+#[pin_project::pin_project]
+pub struct IgnoringSend<R: Request>(#[pin] BoxFuture<'static, Result<Output<R>, R::Err>>);
+
+/// Future returned by [`IgnoringRequest::send_edit`].
+#[pin_project::pin_project]
+pub struct SendEdit<R: Request>(#[pin] BoxFuture<'static, Result<Edit<Output<R>>, R::Err>>);
+
+impl<R: HasPayload> HasPayload for IgnoringRequest<R> {
+    type Payload = R::Payload;
+
+    fn payload_mut(&mut self) -> &mut Self::Payload {
+        self.inner.payload_mut()
+    }
+
+    fn payload_ref(&self) -> &Self::Payload {
+        self.inner.payload_ref()
+    }
+}
+
+impl<R> Request for IgnoringRequest<R>
+where
+    R: Request + 'static,
+    R::Payload: NotModifiedPayload,
+    R::Err: NotModifiedError + std::marker::Send,
+{
+    type Err = R::Err;
+    type Send = IgnoringSend<R>;
+    type SendRef = IgnoringSend<R>;
+
+    fn send(self) -> Self::Send {
+        let id = self.payload_ref().message_id();
+        IgnoringSend(Box::pin(send::<R::Payload, _, _>(
+            self.known,
+            id,
+            self.inner.send(),
+        )))
+    }
+
+    fn send_ref(&self) -> Self::SendRef {
+        IgnoringSend(Box::pin(send::<R::Payload, _, _>(
+            Arc::clone(&self.known),
+            self.payload_ref().message_id(),
+            self.inner.send_ref(),
+        )))
+    }
+}
+
+impl<R> IgnoringRequest<R>
+where
+    R: Request + 'static,
+    R::Payload: NotModifiedPayload,
+    R::Err: NotModifiedError + std::marker::Send,
+{
+    /// Sends the request and returns whether the message was modified.
+    ///
+    /// Unlike [`Request::send`] this never fails with
+    /// [`ApiError::MessageNotModified`], even if the message is unknown.
+    ///
+    /// [`ApiError::MessageNotModified`]: crate::ApiError::MessageNotModified
+    pub fn send_edit(self) -> SendEdit<R> {
+        let id = self.payload_ref().message_id();
+        SendEdit(Box::pin(send_edit::<R::Payload, _, _>(
+            self.known,
+            id,
+            self.inner.send(),
+        )))
+    }
+}
+
+impl<R: Request> Future for IgnoringSend<R> {
+    type Output = Result<Output<R>, R::Err>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.project().0.poll(cx)
+    }
+}
+
+impl<R: Request> Future for SendEdit<R> {
+    type Output = Result<Edit<Output<R>>, R::Err>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.project().0.poll(cx)
+    }
+}
+
+/// Actual implementation of the `IgnoringSend` future
+async fn send<P, F, E>(
+    known: Arc<Known>,
+    id: Option<(Recipient, i32)>,
+    fut: F,
+) -> Result<P::Output, E>
+where
+    P: NotModifiedPayload,
+    F: Future<Output = Result<P::Output, E>>,
+    E: NotModifiedError,
+{
+    handle::<P, E>(&known, id, fut.await)
+}
+
+/// Actual implementation of the `SendEdit` future
+async fn send_edit<P, F, E>(
+    known: Arc<Known>,
+    id: Option<(Recipient, i32)>,
+    fut: F,
+) -> Result<Edit<P::Output>, E>
+where
+    P: NotModifiedPayload,
+    F: Future<Output = Result<P::Output, E>>,
+    E: NotModifiedError,
+{
+    let res = fut.await;
+    let modified = res.is_ok();
+
+    match handle::<P, E>(&known, id, res) {
+        Ok(output) if modified => Ok(Edit::Modified(output)),
+        Ok(output) => Ok(Edit::Unchanged(Some(output))),
+        Err(err) if err.is_not_modified() => Ok(Edit::Unchanged(None)),
+        Err(err) => Err(err),
+    }
+}
+
+/// Remembers the edited message or replaces "message is not modified" error
+/// with the known message.
+fn handle<P, E>(
+    known: &Known,
+    id: Option<(Recipient, i32)>,
+    res: Result<P::Output, E>,
+) -> Result<P::Output, E>
+where
+    P: NotModifiedPayload,
+    E: NotModifiedError,
+{
+    match res {
+        Ok(output) => {
+            if let Some(message) = P::output_message(&output) {
+                known.insert(message.clone());
+            }
+            Ok(output)
+        }
+        Err(err) if err.is_not_modified() => {
+            let message = id.and_then(|id| known.get(&id));
+            P::unchanged_output(message).ok_or(err)
+        }
+        Err(err) => Err(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        adaptors::{ignore_not_modified::Edit, IgnoreNotModified},
+        errors::{ApiError, RequestError},
+        payloads::{EditMessageText, EditMessageTextInline},
+        types::{ChatId, Message, Recipient, True},
+        Bot,
+    };
+
+    use super::{send, send_edit};
+
+    fn message(text: &str) -> Message {
+        serde_json::from_value(serde_json::json!({
+            "message_id": 1,
+            "date": 0,
+            "chat": { "id": 2, "type": "private", "first_name": "A" },
+            "text": text,
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn known_message() {
+        let bot = IgnoreNotModified::new(Bot::new("TOKEN"));
+        let id = || Some((Recipient::Id(ChatId(2)), 1));
+        let not_modified = || async { Err(RequestError::Api(ApiError::MessageNotModified)) };
+
+        // The message is unknown
+        let res = send::<EditMessageText, _, _>(bot.known.clone(), id(), not_modified()).await;
+        assert!(matches!(
+            res,
+            Err(RequestError::Api(ApiError::MessageNotModified))
+        ));
+        let res = send_edit::<EditMessageText, _, _>(bot.known.clone(), id(), not_modified()).await;
+        assert_eq!(res.unwrap(), Edit::Unchanged(None));
+
+        // Successful edits are remembered
+        let res = send::<EditMessageText, _, RequestError>(bot.known.clone(), id(), async {
+            Ok(message("a"))
+        })
+        .await;
+        assert_eq!(res.unwrap().text(), Some("a"));
+
+        let res = send::<EditMessageText, _, _>(bot.known.clone(), id(), not_modified()).await;
+        assert_eq!(res.unwrap().text(), Some("a"));
+
+        bot.remember(&message("b"));
+        let res = send_edit::<EditMessageText, _, _>(bot.known.clone(), id(), not_modified()).await;
+        assert!(matches!(res, Ok(Edit::Unchanged(Some(m))) if m.text() == Some("b")));
+
+        // Usernames never match known messages
+        let username = Some((Recipient::ChannelUsername("@chan".to_owned()), 1));
+        let res =
+            send_edit::<EditMessageText, _, _>(bot.known.clone(), username, not_modified()).await;
+        assert_eq!(res.unwrap(), Edit::Unchanged(None));
+
+        // Inline messages are never known
+        let res = send::<EditMessageTextInline, _, _>(bot.known.clone(), None, async {
+            Err(RequestError::Api(ApiError::MessageNotModified))
+        })
+        .await;
+        assert_eq!(res.unwrap(), True);
+    }
+}
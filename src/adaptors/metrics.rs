@@ -0,0 +1,622 @@
+use std::{
+    cmp,
+    collections::BTreeMap,
+    fmt::{self, Write},
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{self, Poll},
+    time::{Duration, Instant},
+};
+
+use futures::ready;
+use url::Url;
+
+use crate::{
+    errors::{ApiError, RequestError},
+    requests::{HasPayload, Payload, Request, Requester},
+    types::*,
+};
+
+/// Collects metrics of requests.
+///
+/// For every request this adaptor measures how long it took and reports it
+/// (alongside with the method name and the error, if any) to a [`Recorder`].
+/// [`PrometheusRecorder`] can be used to render collected metrics in the
+/// Prometheus text format.
+///
+/// ## Examples
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// use teloxide_core::{adaptors::metrics::PrometheusRecorder, requests::RequesterExt, Bot};
+///
+/// let recorder = Arc::new(PrometheusRecorder::new());
+/// let bot = Bot::new("TOKEN").metrics(recorder.clone());
+///
+/// /* send some requests */
+///
+/// // Serve this on the `/metrics` endpoint
+/// let text = recorder.render();
+/// # let _ = (bot, text);
+/// ```
+#[derive(Clone)]
+pub struct Metrics<B> {
+    inner: B,
+    recorder: Arc<dyn Recorder>,
+}
+
+impl<B> Metrics<B> {
+    /// Creates new [`Metrics`].
+    ///
+    /// Note: it's recommended to use [`RequesterExt::metrics`] instead.
+    ///
+    /// [`RequesterExt::metrics`]: crate::requests::RequesterExt::metrics
+    pub fn new(inner: B, recorder: Arc<dyn Recorder>) -> Self {
+        Self { inner, recorder }
+    }
+
+    /// Allows to access the inner bot.
+    pub fn inner(&self) -> &B {
+        &self.inner
+    }
+
+    /// Unwraps the inner bot.
+    pub fn into_inner(self) -> B {
+        self.inner
+    }
+
+    /// Returns the recorder used by this adaptor.
+    pub fn recorder(&self) -> &Arc<dyn Recorder> {
+        &self.recorder
+    }
+}
+
+impl<B: fmt::Debug> fmt::Debug for Metrics<B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Metrics")
+            .field("inner", &self.inner)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Receiver of metrics collected by [`Metrics`].
+pub trait Recorder: std::marker::Send + Sync {
+    /// Records a finished request.
+    ///
+    /// `method` is the name of the method (see [`Payload::NAME`]), `latency` is
+    /// the time passed since the request was first polled.
+    fn record(&self, method: &'static str, latency: Duration, result: Result<(), &RequestError>);
+}
+
+/// Kind of a [`RequestError`], suitable to be used as a metric label.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ErrorKind {
+    /// Name of the [`RequestError`] variant, e.g. `"Api"` or `"RetryAfter"`.
+    pub variant: &'static str,
+
+    /// Name of the [`ApiError`] variant (e.g. `"MessageNotModified"`) if
+    /// `variant` is `"Api"`.
+    ///
+    /// [`ApiError`]: crate::ApiError
+    pub api_error: Option<&'static str>,
+}
+
+impl ErrorKind {
+    /// Returns kind of the `error`.
+    pub fn of(error: &RequestError) -> Self {
+        let (variant, api_error) = match error {
+            RequestError::Api(api_error) => ("Api", Some(api_error_name(api_error))),
+            RequestError::MigrateToChatId(_) => ("MigrateToChatId", None),
+            RequestError::RetryAfter(_) => ("RetryAfter", None),
+            RequestError::Network(_) => ("Network", None),
+            RequestError::InvalidJson { .. } => ("InvalidJson", None),
+            RequestError::Io(_) => ("Io", None),
+        };
+
+        Self { variant, api_error }
+    }
+}
+
+/// Returns name of the [`ApiError`] variant.
+fn api_error_name(error: &ApiError) -> &'static str {
+    use ApiError::*;
+
+    match error {
+        BotBlocked => "BotBlocked",
+        NotFound => "NotFound",
+        MessageNotModified => "MessageNotModified",
+        MessageIdInvalid => "MessageIdInvalid",
+        MessageToForwardNotFound => "MessageToForwardNotFound",
+        MessageToDeleteNotFound => "MessageToDeleteNotFound",
+        MessageTextIsEmpty => "MessageTextIsEmpty",
+        MessageCantBeEdited => "MessageCantBeEdited",
+        MessageCantBeDeleted => "MessageCantBeDeleted",
+        MessageToEditNotFound => "MessageToEditNotFound",
+        MessageToReplyNotFound => "MessageToReplyNotFound",
+        MessageIdentifierNotSpecified => "MessageIdentifierNotSpecified",
+        MessageIsTooLong => "MessageIsTooLong",
+        EditedMessageIsTooLong => "EditedMessageIsTooLong",
+        ToMuchMessages => "ToMuchMessages",
+        TooMuchInlineQueryResults => "TooMuchInlineQueryResults",
+        PollHasAlreadyClosed => "PollHasAlreadyClosed",
+        PollMustHaveMoreOptions => "PollMustHaveMoreOptions",
+        PollCantHaveMoreOptions => "PollCantHaveMoreOptions",
+        PollOptionsMustBeNonEmpty => "PollOptionsMustBeNonEmpty",
+        PollQuestionMustBeNonEmpty => "PollQuestionMustBeNonEmpty",
+        PollOptionsLengthTooLong => "PollOptionsLengthTooLong",
+        PollQuestionLengthTooLong => "PollQuestionLengthTooLong",
+        MessageWithPollNotFound => "MessageWithPollNotFound",
+        MessageIsNotAPoll => "MessageIsNotAPoll",
+        ChatNotFound => "ChatNotFound",
+        UserNotFound => "UserNotFound",
+        ChatDescriptionIsNotModified => "ChatDescriptionIsNotModified",
+        InvalidQueryId => "InvalidQueryId",
+        ButtonUrlInvalid => "ButtonUrlInvalid",
+        ButtonDataInvalid => "ButtonDataInvalid",
+        TextButtonsAreUnallowed => "TextButtonsAreUnallowed",
+        WrongFileId => "WrongFileId",
+        WrongFileIdOrUrl => "WrongFileIdOrUrl",
+        FailedToGetUrlContent => "FailedToGetUrlContent",
+        GroupDeactivated => "GroupDeactivated",
+        PhotoAsInputFileRequired => "PhotoAsInputFileRequired",
+        InvalidStickersSet => "InvalidStickersSet",
+        StickerSetNameOccupied => "StickerSetNameOccupied",
+        StickerSetOwnerIsBot => "StickerSetOwnerIsBot",
+        InvalidStickerName => "InvalidStickerName",
+        NotEnoughRightsToPinMessage => "NotEnoughRightsToPinMessage",
+        NotEnoughRightsToManagePins => "NotEnoughRightsToManagePins",
+        NotEnoughRightsToChangeChatPermissions => "NotEnoughRightsToChangeChatPermissions",
+        MethodNotAvailableInPrivateChats => "MethodNotAvailableInPrivateChats",
+        CantDemoteChatCreator => "CantDemoteChatCreator",
+        CantRestrictSelf => "CantRestrictSelf",
+        NotEnoughRightsToRestrict => "NotEnoughRightsToRestrict",
+        NotEnoughRightsToPostMessages => "NotEnoughRightsToPostMessages",
+        WebhookRequireHttps => "WebhookRequireHttps",
+        BadWebhookPort => "BadWebhookPort",
+        UnknownHost => "UnknownHost",
+        CantParseUrl => "CantParseUrl",
+        CantParseEntities => "CantParseEntities",
+        CantGetUpdates => "CantGetUpdates",
+        BotKicked => "BotKicked",
+        BotKickedFromSupergroup => "BotKickedFromSupergroup",
+        UserDeactivated => "UserDeactivated",
+        CantInitiateConversation => "CantInitiateConversation",
+        CantTalkWithBots => "CantTalkWithBots",
+        WrongHttpUrl => "WrongHttpUrl",
+        TerminatedByOtherGetUpdates => "TerminatedByOtherGetUpdates",
+        FileIdInvalid => "FileIdInvalid",
+        Unknown(_) => "Unknown",
+    }
+}
+
+/// Total order of floats, same as `f64::total_cmp` (which is not available on
+/// our MSRV).
+fn total_cmp(a: &f64, b: &f64) -> cmp::Ordering {
+    let key = |x: &f64| {
+        let bits = x.to_bits() as i64;
+        bits ^ (((bits >> 63) as u64) >> 1) as i64
+    };
+
+    key(a).cmp(&key(b))
+}
+
+/// [`Recorder`] which keeps metrics in memory and renders them in the
+/// [Prometheus text format].
+///
+/// The following metrics are exported (all labeled by `method`):
+/// - `teloxide_requests_total` — counter of finished requests
+/// - `teloxide_request_errors_total` — counter of failed requests, also labeled
+///   by `error` (the [`RequestError`] variant) and `api_error` (the
+///   [`ApiError`] variant, empty for non-API errors)
+/// - `teloxide_request_duration_seconds` — histogram of request latencies
+///
+/// [Prometheus text format]: https://prometheus.io/docs/instrumenting/exposition_formats/
+/// [`ApiError`]: crate::ApiError
+#[derive(Debug)]
+pub struct PrometheusRecorder {
+    buckets: Vec<f64>,
+    methods: Mutex<BTreeMap<&'static str, MethodStats>>,
+}
+
+#[derive(Debug, Default)]
+struct MethodStats {
+    count: u64,
+    sum: f64,
+    // Non-cumulative counts, one per bucket
+    buckets: Vec<u64>,
+    errors: BTreeMap<ErrorKind, u64>,
+}
+
+impl PrometheusRecorder {
+    /// Default histogram buckets (in seconds).
+    pub const DEFAULT_BUCKETS: &'static [f64] = &[0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0];
+
+    /// Creates new recorder with [default buckets].
+    ///
+    /// [default buckets]: Self::DEFAULT_BUCKETS
+    pub fn new() -> Self {
+        Self::with_buckets(Self::DEFAULT_BUCKETS.to_vec())
+    }
+
+    /// Creates new recorder with given histogram buckets (upper bounds in
+    /// seconds).
+    pub fn with_buckets(mut buckets: Vec<f64>) -> Self {
+        buckets.sort_by(total_cmp);
+
+        Self {
+            buckets,
+            methods: <_>::default(),
+        }
+    }
+
+    /// Renders collected metrics in the Prometheus text format.
+    pub fn render(&self) -> String {
+        let methods = self.methods.lock().unwrap();
+        let mut out = String::new();
+
+        // `write!`ing to a `String` never fails, so errors are ignored
+        let _ = self.render_into(&methods, &mut out);
+        out
+    }
+
+    fn render_into(
+        &self,
+        methods: &BTreeMap<&'static str, MethodStats>,
+        out: &mut String,
+    ) -> fmt::Result {
+        writeln!(
+            out,
+            "# HELP teloxide_requests_total Number of finished requests."
+        )?;
+        writeln!(out, "# TYPE teloxide_requests_total counter")?;
+        for (method, stats) in methods {
+            writeln!(
+                out,
+                "teloxide_requests_total{{method=\"{}\"}} {}",
+                method, stats.count
+            )?;
+        }
+
+        writeln!(
+            out,
+            "# HELP teloxide_request_errors_total Number of failed requests."
+        )?;
+        writeln!(out, "# TYPE teloxide_request_errors_total counter")?;
+        for (method, stats) in methods {
+            for (kind, count) in &stats.errors {
+                writeln!(
+                    out,
+                    "teloxide_request_errors_total{{method=\"{}\",error=\"{}\",api_error=\"{}\"}} \
+                     {}",
+                    method,
+                    kind.variant,
+                    kind.api_error.unwrap_or(""),
+                    count
+                )?;
+            }
+        }
+
+        writeln!(
+            out,
+            "# HELP teloxide_request_duration_seconds Request latencies."
+        )?;
+        writeln!(out, "# TYPE teloxide_request_duration_seconds histogram")?;
+        for (method, stats) in methods {
+            let mut cumulative = 0;
+            for (le, count) in self.buckets.iter().zip(&stats.buckets) {
+                cumulative += count;
+                writeln!(
+                    out,
+                    "teloxide_request_duration_seconds_bucket{{method=\"{}\",le=\"{}\"}} {}",
+                    method, le, cumulative
+                )?;
+            }
+            writeln!(
+                out,
+                "teloxide_request_duration_seconds_bucket{{method=\"{}\",le=\"+Inf\"}} {}",
+                method, stats.count
+            )?;
+            writeln!(
+                out,
+                "teloxide_request_duration_seconds_sum{{method=\"{}\"}} {}",
+                method, stats.sum
+            )?;
+            writeln!(
+                out,
+                "teloxide_request_duration_seconds_count{{method=\"{}\"}} {}",
+                method, stats.count
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for PrometheusRecorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Recorder for PrometheusRecorder {
+    fn record(&self, method: &'static str, latency: Duration, result: Result<(), &RequestError>) {
+        let mut methods = self.methods.lock().unwrap();
+        let stats = methods.entry(method).or_default();
+
+        let secs = latency.as_secs_f64();
+        stats.count += 1;
+        stats.sum += secs;
+
+        stats.buckets.resize(self.buckets.len(), 0);
+        if let Some(i) = self.buckets.iter().position(|&le| secs <= le) {
+            stats.buckets[i] += 1;
+        }
+
+        if let Err(error) = result {
+            *stats.errors.entry(ErrorKind::of(error)).or_insert(0) += 1;
+        }
+    }
+}
+
+macro_rules! fty {
+    ($T:ident) => {
+        MetricsRequest<B::$T>
+    };
+}
+
+macro_rules! fwd_inner {
+    ($m:ident $this:ident ($($arg:ident : $T:ty),*)) => {
+        MetricsRequest {
+            inner: $this.inner().$m($($arg),*),
+            recorder: Arc::clone(&$this.recorder),
+        }
+    };
+}
+
+impl<B> Requester for Metrics<B>
+where
+    B: Requester<Err = RequestError>,
+{
+    type Err = RequestError;
+
+    requester_forward! {
+        get_me,
+        log_out,
+        close,
+        get_updates,
+        set_webhook,
+        delete_webhook,
+        get_webhook_info,
+        forward_message,
+        copy_message,
+        send_message,
+        send_photo,
+        send_audio,
+        send_document,
+        send_video,
+        send_animation,
+        send_voice,
+        send_video_note,
+        send_media_group,
+        send_location,
+        edit_message_live_location,
+        edit_message_live_location_inline,
+        stop_message_live_location,
+        stop_message_live_location_inline,
+        send_venue,
+        send_contact,
+        send_poll,
+        send_dice,
+        send_chat_action,
+        get_user_profile_photos,
+        get_file,
+        kick_chat_member,
+        ban_chat_member,
+        unban_chat_member,
+        restrict_chat_member,
+        promote_chat_member,
+        set_chat_administrator_custom_title,
+        ban_chat_sender_chat,
+        unban_chat_sender_chat,
+        set_chat_permissions,
+        export_chat_invite_link,
+        create_chat_invite_link,
+        edit_chat_invite_link,
+        revoke_chat_invite_link,
+        set_chat_photo,
+        delete_chat_photo,
+        set_chat_title,
+        set_chat_description,
+        pin_chat_message,
+        unpin_chat_message,
+        unpin_all_chat_messages,
+        leave_chat,
+        get_chat,
+        get_chat_administrators,
+        get_chat_members_count,
+        get_chat_member_count,
+        get_chat_member,
+        set_chat_sticker_set,
+        delete_chat_sticker_set,
+        answer_callback_query,
+        set_my_commands,
+        get_my_commands,
+        set_chat_menu_button,
+        get_chat_menu_button,
+        set_my_default_administrator_rights,
+        get_my_default_administrator_rights,
+        delete_my_commands,
+        answer_inline_query,
+        answer_web_app_query,
+        edit_message_text,
+        edit_message_text_inline,
+        edit_message_caption,
+        edit_message_caption_inline,
+        edit_message_media,
+        edit_message_media_inline,
+        edit_message_reply_markup,
+        edit_message_reply_markup_inline,
+        stop_poll,
+        delete_message,
+        send_sticker,
+        get_sticker_set,
+        upload_sticker_file,
+        create_new_sticker_set,
+        add_sticker_to_set,
+        set_sticker_position_in_set,
+        delete_sticker_from_set,
+        set_sticker_set_thumb,
+        send_invoice,
+        create_invoice_link,
+        answer_shipping_query,
+        answer_pre_checkout_query,
+        set_passport_data_errors,
+        send_game,
+        set_game_score,
+        set_game_score_inline,
+        get_game_high_scores,
+        approve_chat_join_request,
+        decline_chat_join_request
+        => fwd_inner, fty
+    }
+}
+
+download_forward! {
+    'w
+    B
+    Metrics<B>
+    { this => this.inner() }
+}
+
+/// Request returned by [`Metrics`] methods.
+#[must_use = "Requests are lazy and do nothing unless sent"]
+pub struct MetricsRequest<R> {
+    inner: R,
+    recorder: Arc<dyn Recorder>,
+}
+
+impl<R> HasPayload for MetricsRequest<R>
+where
+    R: HasPayload,
+{
+    type Payload = R::Payload;
+
+    fn payload_mut(&mut self) -> &mut Self::Payload {
+        self.inner.payload_mut()
+    }
+
+    fn payload_ref(&self) -> &Self::Payload {
+        self.inner.payload_ref()
+    }
+}
+
+impl<R> Request for MetricsRequest<R>
+where
+    R: Request<Err = RequestError>,
+{
+    type Err = RequestError;
+
+    type Send = Send<R::Send>;
+
+    type SendRef = Send<R::SendRef>;
+
+    fn send(self) -> Self::Send {
+        Send {
+            method: R::Payload::NAME,
+            recorder: self.recorder,
+            start: None,
+            inner: self.inner.send(),
+        }
+    }
+
+    fn send_ref(&self) -> Self::SendRef {
+        Send {
+            method: R::Payload::NAME,
+            recorder: Arc::clone(&self.recorder),
+            start: None,
+            inner: self.inner.send_ref(),
+        }
+    }
+}
+
+/// Future returned by [`MetricsRequest`]s.
+#[pin_project::pin_project]
+pub struct Send<F>
+where
+    F: Future,
+{
+    method: &'static str,
+    recorder: Arc<dyn Recorder>,
+    start: Option<Instant>,
+    #[pin]
+    inner: F,
+}
+
+impl<F, T> Future for Send<F>
+where
+    F: Future<Output = Result<T, RequestError>>,
+{
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let start = *this.start.get_or_insert_with(Instant::now);
+
+        let ret = ready!(this.inner.poll(cx));
+        this.recorder
+            .record(this.method, start.elapsed(), ret.as_ref().map(drop));
+        Poll::Ready(ret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{
+        adaptors::metrics::{ErrorKind, PrometheusRecorder, Recorder},
+        ApiError, RequestError,
+    };
+
+    #[test]
+    fn render() {
+        let recorder = PrometheusRecorder::with_buckets(vec![1.0, 0.1]);
+        recorder.record("SendMessage", Duration::from_millis(50), Ok(()));
+        recorder.record("SendMessage", Duration::from_millis(500), Ok(()));
+        recorder.record(
+            "EditMessageText",
+            Duration::from_secs(2),
+            Err(&RequestError::Api(ApiError::MessageNotModified)),
+        );
+
+        let expected = r#"# HELP teloxide_requests_total Number of finished requests.
+# TYPE teloxide_requests_total counter
+teloxide_requests_total{method="EditMessageText"} 1
+teloxide_requests_total{method="SendMessage"} 2
+# HELP teloxide_request_errors_total Number of failed requests.
+# TYPE teloxide_request_errors_total counter
+teloxide_request_errors_total{method="EditMessageText",error="Api",api_error="MessageNotModified"} 1
+# HELP teloxide_request_duration_seconds Request latencies.
+# TYPE teloxide_request_duration_seconds histogram
+teloxide_request_duration_seconds_bucket{method="EditMessageText",le="0.1"} 0
+teloxide_request_duration_seconds_bucket{method="EditMessageText",le="1"} 0
+teloxide_request_duration_seconds_bucket{method="EditMessageText",le="+Inf"} 1
+teloxide_request_duration_seconds_sum{method="EditMessageText"} 2
+teloxide_request_duration_seconds_count{method="EditMessageText"} 1
+teloxide_request_duration_seconds_bucket{method="SendMessage",le="0.1"} 1
+teloxide_request_duration_seconds_bucket{method="SendMessage",le="1"} 2
+teloxide_request_duration_seconds_bucket{method="SendMessage",le="+Inf"} 2
+teloxide_request_duration_seconds_sum{method="SendMessage"} 0.55
+teloxide_request_duration_seconds_count{method="SendMessage"} 2
+"#;
+        assert_eq!(recorder.render(), expected);
+
+        let recorder = PrometheusRecorder::with_buckets(vec![f64::NAN, 1.0, -1.0]);
+        assert_eq!(recorder.buckets[..2], [-1.0, 1.0]);
+
+        let kind = ErrorKind::of(&RequestError::Api(ApiError::Unknown("x".to_owned())));
+        assert_eq!(kind.api_error, Some("Unknown"));
+    }
+}
@@ -8,6 +8,10 @@ use crate::{
 
 /// Default parse mode adaptor, see
 /// [`RequesterExt::parse_mode`](crate::requests::RequesterExt::parse_mode).
+///
+/// See also [`DefaultValues`] which can set other default values too.
+///
+/// [`DefaultValues`]: crate::adaptors::DefaultValues
 #[derive(Clone, Debug)]
 pub struct DefaultParseMode<B> {
     bot: B,
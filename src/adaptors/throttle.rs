@@ -1,3 +1,5 @@
+/// Limits learned from `RetryAfter` errors
+mod adaptive;
 /// `ThrottlingRequest` and `ThrottlingSend` structures
 mod request;
 /// Lock that allows requests to wait until they are allowed to be sent
@@ -6,13 +8,12 @@ mod request_lock;
 mod requester_impl;
 /// `Settings` and `Limits` structures
 mod settings;
+/// `Storage` trait and its in-memory implementation
+mod storage;
 /// "Worker" that checks the limits
 mod worker;
 
-use std::{
-    future::Future,
-    hash::{Hash, Hasher},
-};
+use std::future::Future;
 
 use tokio::sync::{
     mpsc,
@@ -27,7 +28,8 @@ use self::{
 };
 
 pub use request::{ThrottlingRequest, ThrottlingSend};
-pub use settings::{Limits, Settings};
+pub use settings::{Limits, Settings, Weights};
+pub use storage::{InMemoryStorage, RequestsSent, Storage};
 
 /// Automatic request limits respecting mechanism.
 ///
@@ -40,6 +42,10 @@ pub use settings::{Limits, Settings};
 /// they could be sent without exceeding limits (request order in chats is not
 /// changed).
 ///
+/// Not all requests count the same: by default every message (including every
+/// item of a media group) counts as one, while edits and other chat-bound
+/// requests are not throttled. This can be changed with [`Weights`].
+///
 /// It's recommended to use this wrapper before other wrappers (i.e.:
 /// `SomeWrapper<Throttle<Bot>>` not `Throttle<SomeWrapper<Bot>>`) because if
 /// done otherwise inner wrappers may cause `Throttle` to miscalculate limits
@@ -70,11 +76,19 @@ pub use settings::{Limits, Settings};
 ///
 /// As such, we encourage not to use `ChatId::ChannelUsername(u)` with this bot
 /// wrapper.
+///
+/// ## Note about running several instances
+///
+/// By default the history of sent requests is kept in process memory, so if
+/// you run several instances of the same bot, each of them will believe that
+/// it can use the full limits. To share limits between instances use a shared
+/// [`Storage`] (see [`Settings::storage`]).
 #[derive(Clone, Debug)]
 pub struct Throttle<B> {
     bot: B,
     // `RequestLock` allows to unlock requests (allowing them to be sent).
-    queue: mpsc::Sender<(ChatIdHash, RequestLock)>,
+    queue: mpsc::Sender<(ChatIdHash, u32, RequestLock)>,
+    weights: Weights,
     info_tx: mpsc::Sender<InfoMessage>,
 }
 
@@ -106,11 +120,13 @@ impl<B> Throttle<B> {
     {
         let (tx, rx) = mpsc::channel(settings.limits.messages_per_sec_overall as usize);
         let (info_tx, info_rx) = mpsc::channel(2);
+        let weights = settings.weights;
 
         let worker = worker(settings, rx, info_rx, bot.clone());
         let this = Self {
             bot,
             queue: tx,
+            weights,
             info_tx,
         };
 
@@ -187,12 +203,15 @@ impl<B> Throttle<B> {
     }
 }
 
-/// An ID used in the worker.
+/// An ID used in the worker and [`Storage`].
 ///
 /// It is used instead of `ChatId` to make copying cheap even in case of
 /// usernames. (It is just a hashed username.)
+///
+/// Usernames are hashed with FNV-1a, so the hash is the same across builds and
+/// processes and can be used as a key of a shared [`Storage`].
 #[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
-enum ChatIdHash {
+pub enum ChatIdHash {
     Id(ChatId),
     ChannelUsernameHash(u64),
 }
@@ -206,18 +225,32 @@ impl ChatIdHash {
     }
 }
 
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
 impl From<&Recipient> for ChatIdHash {
     fn from(value: &Recipient) -> Self {
         match value {
             Recipient::Id(id) => ChatIdHash::Id(*id),
             Recipient::ChannelUsername(username) => {
-                // FIXME: this could probably use a faster hasher, `DefaultHasher` is known to
-                //        be slow (it's not like we _need_ this to be fast, but still)
-                let mut hasher = std::collections::hash_map::DefaultHasher::new();
-                username.hash(&mut hasher);
-                let hash = hasher.finish();
+                // `DefaultHasher` can't be used since its algorithm may change between
+                // builds, which would break storages shared between bot instances
+                let hash = username.bytes().fold(FNV_OFFSET_BASIS, |hash, byte| {
+                    (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME)
+                });
                 ChatIdHash::ChannelUsernameHash(hash)
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{adaptors::throttle::ChatIdHash, types::Recipient};
+
+    #[test]
+    fn stable_username_hash() {
+        let hash = ChatIdHash::from(&Recipient::ChannelUsername("@chan".to_owned()));
+        assert_eq!(hash, ChatIdHash::ChannelUsernameHash(0x8897_c980_8e72_d6d3));
+    }
+}
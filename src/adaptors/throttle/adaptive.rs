@@ -0,0 +1,155 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use crate::adaptors::throttle::{ChatIdHash, Limits};
+
+/// How much the rate is multiplied by when a `RetryAfter` error is observed.
+const DECREASE_FACTOR: f64 = 0.5;
+
+/// Rate is never lowered below this fraction of the configured limits.
+const MIN_FACTOR: f64 = 1.0 / 16.0;
+
+/// How much the rate is raised back every `RECOVERY_INTERVAL`.
+const RECOVERY_STEP: f64 = 0.1;
+const RECOVERY_INTERVAL: Duration = Duration::from_secs(30);
+
+/// If `RetryAfter` errors are observed for different chats within this window,
+/// we assume that the global limit was hit.
+const GLOBAL_WINDOW: Duration = Duration::from_secs(5);
+
+/// Effective rates, learned from `RetryAfter` errors.
+///
+/// Every time a `RetryAfter` error is observed for a chat, the rate for that
+/// chat is multiplicatively decreased. If errors are observed for several
+/// different chats in a short time, the overall rate is decreased too. Then
+/// rates are slowly (additively) raised back up toward the configured
+/// [`Limits`].
+#[derive(Debug, Default)]
+pub(super) struct AdaptiveLimits {
+    overall: Option<Rate>,
+    chats: HashMap<ChatIdHash, Rate>,
+    last_retry_after: Option<(ChatIdHash, Instant)>,
+}
+
+/// Lowered rate, expressed as a fraction of the configured limits.
+#[derive(Debug, Clone, Copy)]
+struct Rate {
+    lowered_to: f64,
+    at: Instant,
+}
+
+impl AdaptiveLimits {
+    /// Lowers the rate for `chat` (and the overall rate, if other chats
+    /// recently got `RetryAfter` errors too).
+    pub(super) fn on_retry_after(&mut self, chat: ChatIdHash) {
+        let now = Instant::now();
+
+        lower(
+            self.chats.entry(chat).or_insert_with(|| Rate::full(now)),
+            now,
+        );
+
+        let global = matches!(
+            self.last_retry_after,
+            Some((other, at)) if other != chat && now.saturating_duration_since(at) < GLOBAL_WINDOW
+        );
+        if global {
+            lower(self.overall.get_or_insert_with(|| Rate::full(now)), now);
+        }
+
+        self.last_retry_after = Some((chat, now));
+    }
+
+    /// Forgets rates that have fully recovered.
+    pub(super) fn recover(&mut self) {
+        let now = Instant::now();
+
+        self.chats.retain(|_, rate| rate.factor(now) < 1.0);
+        if matches!(self.overall, Some(rate) if rate.factor(now) >= 1.0) {
+            self.overall = None;
+        }
+    }
+
+    /// Returns currently allowed messages per second overall.
+    pub(super) fn messages_per_sec_overall(&self, limits: &Limits) -> u32 {
+        scale(limits.messages_per_sec_overall, self.overall)
+    }
+
+    /// Returns limits that should currently be used for `chat`.
+    pub(super) fn chat_limits(&self, chat: &ChatIdHash, limits: &Limits) -> Limits {
+        let rate = self.chats.get(chat).copied();
+
+        Limits {
+            messages_per_sec_chat: scale(limits.messages_per_sec_chat, rate),
+            messages_per_min_chat: scale(limits.messages_per_min_chat, rate),
+            messages_per_min_channel: scale(limits.messages_per_min_channel, rate),
+            messages_per_sec_overall: self.messages_per_sec_overall(limits),
+        }
+    }
+}
+
+impl Rate {
+    fn full(now: Instant) -> Self {
+        Self {
+            lowered_to: 1.0,
+            at: now,
+        }
+    }
+
+    fn factor(&self, now: Instant) -> f64 {
+        let intervals =
+            now.saturating_duration_since(self.at).as_secs_f64() / RECOVERY_INTERVAL.as_secs_f64();
+
+        (self.lowered_to + intervals * RECOVERY_STEP).min(1.0)
+    }
+}
+
+fn lower(rate: &mut Rate, now: Instant) {
+    *rate = Rate {
+        lowered_to: (rate.factor(now) * DECREASE_FACTOR).max(MIN_FACTOR),
+        at: now,
+    };
+}
+
+/// Scales `limit` by `rate`, never going lower than 1 (so requests are never
+/// blocked forever).
+fn scale(limit: u32, rate: Option<Rate>) -> u32 {
+    match rate {
+        Some(rate) => ((limit as f64 * rate.factor(Instant::now())) as u32).max(1),
+        None => limit,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        adaptors::throttle::{adaptive::AdaptiveLimits, ChatIdHash, Limits},
+        types::ChatId,
+    };
+
+    #[test]
+    fn lowers_chat_then_overall() {
+        let limits = Limits::default();
+        let a = ChatIdHash::Id(ChatId(1));
+        let b = ChatIdHash::Id(ChatId(2));
+
+        let mut adaptive = AdaptiveLimits::default();
+
+        adaptive.on_retry_after(a);
+        assert_eq!(adaptive.chat_limits(&a, &limits).messages_per_min_chat, 10);
+        assert_eq!(adaptive.chat_limits(&b, &limits), limits);
+
+        adaptive.on_retry_after(a);
+        assert_eq!(adaptive.chat_limits(&a, &limits).messages_per_min_chat, 5);
+        assert_eq!(adaptive.messages_per_sec_overall(&limits), 30);
+
+        adaptive.on_retry_after(b);
+        assert_eq!(adaptive.messages_per_sec_overall(&limits), 15);
+        assert_eq!(adaptive.chat_limits(&b, &limits).messages_per_sec_chat, 1);
+
+        adaptive.recover();
+        assert_eq!(adaptive.chats.len(), 2);
+    }
+}
@@ -7,7 +7,7 @@ use futures::{
 use tokio::sync::mpsc;
 
 use crate::{
-    adaptors::throttle::{channel, ChatIdHash, FreezeUntil, RequestLock},
+    adaptors::throttle::{channel, ChatIdHash, FreezeUntil, RequestLock, Weights},
     errors::AsResponseParameters,
     requests::{HasPayload, Output, Request},
 };
@@ -17,7 +17,9 @@ use crate::{
 pub struct ThrottlingRequest<R: HasPayload> {
     pub(super) request: Arc<R>,
     pub(super) chat_id: fn(&R::Payload) -> ChatIdHash,
-    pub(super) worker: mpsc::Sender<(ChatIdHash, RequestLock)>,
+    pub(super) weight: fn(&R::Payload, &Weights) -> Option<u32>,
+    pub(super) weights: Weights,
+    pub(super) worker: mpsc::Sender<(ChatIdHash, u32, RequestLock)>,
 }
 
 /// Future returned by [`ThrottlingRequest`]s.
@@ -56,19 +58,21 @@ where
 
     fn send(self) -> Self::Send {
         let chat = (self.chat_id)(self.payload_ref());
+        let weight = (self.weight)(self.payload_ref(), &self.weights);
         let request = match Arc::try_unwrap(self.request) {
             Ok(owned) => ShareableRequest::Owned(Some(owned)),
             Err(shared) => ShareableRequest::Shared(shared),
         };
-        let fut = send(request, chat, self.worker);
+        let fut = send(request, chat, weight, self.worker);
 
         ThrottlingSend(Box::pin(fut))
     }
 
     fn send_ref(&self) -> Self::SendRef {
         let chat = (self.chat_id)(self.payload_ref());
+        let weight = (self.weight)(self.payload_ref(), &self.weights);
         let request = ShareableRequest::Shared(Arc::clone(&self.request));
-        let fut = send(request, chat, self.worker.clone());
+        let fut = send(request, chat, weight, self.worker.clone());
 
         ThrottlingSend(Box::pin(fut))
     }
@@ -136,7 +140,8 @@ where
 async fn send<R>(
     mut request: ShareableRequest<R>,
     chat: ChatIdHash,
-    worker: mpsc::Sender<(ChatIdHash, RequestLock)>,
+    weight: Option<u32>,
+    worker: mpsc::Sender<(ChatIdHash, u32, RequestLock)>,
 ) -> Result<Output<R>, R::Err>
 where
     R: Request + Send + Sync + 'static,
@@ -148,13 +153,24 @@ where
     // All unwraps down below will succeed because we always return immediately
     // after taking.
 
+    // Requests without weight are not throttled, so just send them.
+    let weight = match weight {
+        Some(weight) => weight,
+        None => {
+            return match request {
+                ShareableRequest::Shared(shared) => shared.send_ref().await,
+                ShareableRequest::Owned(owned) => owned.unwrap().send().await,
+            }
+        }
+    };
+
     loop {
         let (lock, wait) = channel();
 
         // The worker is unlikely to drop queue before sending all requests,
         // but just in case it has dropped the queue, we want to just send the
         // request.
-        if worker.send((chat, lock)).await.is_err() {
+        if worker.send((chat, weight, lock)).await.is_err() {
             log::error!("Worker dropped the queue before sending all requests");
 
             let res = match &mut request {
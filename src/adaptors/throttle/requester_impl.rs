@@ -3,22 +3,82 @@ use std::sync::Arc;
 use url::Url;
 
 use crate::{
-    adaptors::{throttle::ThrottlingRequest, Throttle},
+    adaptors::{
+        throttle::{ChatIdHash, ThrottlingRequest},
+        Throttle,
+    },
     errors::AsResponseParameters,
     requests::{HasPayload, Requester},
     types::*,
 };
 
-macro_rules! f {
-    ($m:ident $this:ident ($($arg:ident : $T:ty),*)) => {
+macro_rules! throttled {
+    ($m:ident $this:ident ($($arg:ident : $T:ty),*), chat_id: $chat_id:expr, weight: $weight:expr) => {
         ThrottlingRequest {
             request: Arc::new($this.inner().$m($($arg),*)),
-            chat_id: |p| (&p.payload_ref().chat_id).into(),
+            chat_id: $chat_id,
+            weight: $weight,
+            weights: $this.weights,
             worker: $this.queue.clone(),
         }
     };
 }
 
+// Requests that send a single message
+macro_rules! f {
+    ($m:ident $this:ident ($($arg:ident : $T:ty),*)) => {
+        throttled!(
+            $m $this ($($arg : $T),*),
+            chat_id: |p| (&p.payload_ref().chat_id).into(),
+            weight: |_, w| Some(w.message)
+        )
+    };
+}
+
+// Requests that send a media group
+macro_rules! fmg {
+    ($m:ident $this:ident ($($arg:ident : $T:ty),*)) => {
+        throttled!(
+            $m $this ($($arg : $T),*),
+            chat_id: |p| (&p.payload_ref().chat_id).into(),
+            weight: |p, w| Some(w.media_group_item.saturating_mul(p.media.len() as u32))
+        )
+    };
+}
+
+// Requests that send a game (for some reason `chat_id` is `u32` there)
+macro_rules! fgame {
+    ($m:ident $this:ident ($($arg:ident : $T:ty),*)) => {
+        throttled!(
+            $m $this ($($arg : $T),*),
+            chat_id: |p| ChatIdHash::Id(ChatId(p.payload_ref().chat_id.into())),
+            weight: |_, w| Some(w.message)
+        )
+    };
+}
+
+// Requests that edit a message
+macro_rules! fedit {
+    ($m:ident $this:ident ($($arg:ident : $T:ty),*)) => {
+        throttled!(
+            $m $this ($($arg : $T),*),
+            chat_id: |p| (&p.payload_ref().chat_id).into(),
+            weight: |_, w| w.edit
+        )
+    };
+}
+
+// Other requests that are bound to a chat
+macro_rules! fchat {
+    ($m:ident $this:ident ($($arg:ident : $T:ty),*)) => {
+        throttled!(
+            $m $this ($($arg : $T),*),
+            chat_id: |p| (&p.payload_ref().chat_id).into(),
+            weight: |_, w| w.chat_bound
+        )
+    };
+}
+
 macro_rules! fty {
     ($T:ident) => {
         ThrottlingRequest<B::$T>
@@ -51,7 +111,6 @@ where
     B::SendAnimation: Clone + Send + Sync + 'static,
     B::SendVoice: Clone + Send + Sync + 'static,
     B::SendVideoNote: Clone + Send + Sync + 'static,
-    B::SendMediaGroup: Clone + Send + Sync + 'static,
     B::SendLocation: Clone + Send + Sync + 'static,
     B::SendVenue: Clone + Send + Sync + 'static,
     B::SendContact: Clone + Send + Sync + 'static,
@@ -59,6 +118,23 @@ where
     B::SendDice: Clone + Send + Sync + 'static,
     B::SendSticker: Clone + Send + Sync + 'static,
     B::SendInvoice: Clone + Send + Sync + 'static,
+    B::SendGame: Clone + Send + Sync + 'static,
+
+    B::SendMediaGroup: Clone + Send + Sync + 'static,
+
+    B::EditMessageText: Clone + Send + Sync + 'static,
+    B::EditMessageCaption: Clone + Send + Sync + 'static,
+    B::EditMessageMedia: Clone + Send + Sync + 'static,
+    B::EditMessageReplyMarkup: Clone + Send + Sync + 'static,
+    B::EditMessageLiveLocation: Clone + Send + Sync + 'static,
+    B::StopMessageLiveLocation: Clone + Send + Sync + 'static,
+    B::StopPoll: Clone + Send + Sync + 'static,
+
+    B::SendChatAction: Clone + Send + Sync + 'static,
+    B::DeleteMessage: Clone + Send + Sync + 'static,
+    B::PinChatMessage: Clone + Send + Sync + 'static,
+    B::UnpinChatMessage: Clone + Send + Sync + 'static,
+    B::UnpinAllChatMessages: Clone + Send + Sync + 'static,
 {
     type Err = B::Err;
 
@@ -73,7 +149,6 @@ where
         send_animation,
         send_voice,
         send_video_note,
-        send_media_group,
         send_location,
         send_venue,
         send_contact,
@@ -84,6 +159,36 @@ where
         => f, fty
     }
 
+    requester_forward! {
+        send_media_group
+        => fmg, fty
+    }
+
+    requester_forward! {
+        send_game
+        => fgame, fty
+    }
+
+    requester_forward! {
+        edit_message_text,
+        edit_message_caption,
+        edit_message_media,
+        edit_message_reply_markup,
+        edit_message_live_location,
+        stop_message_live_location,
+        stop_poll
+        => fedit, fty
+    }
+
+    requester_forward! {
+        send_chat_action,
+        delete_message,
+        pin_chat_message,
+        unpin_chat_message,
+        unpin_all_chat_messages
+        => fchat, fty
+    }
+
     requester_forward! {
         get_me,
         log_out,
@@ -92,11 +197,8 @@ where
         set_webhook,
         delete_webhook,
         get_webhook_info,
-        edit_message_live_location,
         edit_message_live_location_inline,
-        stop_message_live_location,
         stop_message_live_location_inline,
-        send_chat_action,
         get_user_profile_photos,
         get_file,
         kick_chat_member,
@@ -116,9 +218,6 @@ where
         delete_chat_photo,
         set_chat_title,
         set_chat_description,
-        pin_chat_message,
-        unpin_chat_message,
-        unpin_all_chat_messages,
         leave_chat,
         get_chat,
         get_chat_administrators,
@@ -137,16 +236,10 @@ where
         delete_my_commands,
         answer_inline_query,
         answer_web_app_query,
-        edit_message_text,
         edit_message_text_inline,
-        edit_message_caption,
         edit_message_caption_inline,
-        edit_message_media,
         edit_message_media_inline,
-        edit_message_reply_markup,
         edit_message_reply_markup_inline,
-        stop_poll,
-        delete_message,
         get_sticker_set,
         upload_sticker_file,
         create_new_sticker_set,
@@ -158,7 +251,6 @@ where
         create_invoice_link,
         answer_pre_checkout_query,
         set_passport_data_errors,
-        send_game,
         set_game_score,
         set_game_score_inline,
         approve_chat_join_request,
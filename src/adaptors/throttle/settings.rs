@@ -2,6 +2,8 @@ use std::pin::Pin;
 
 use futures::{future::ready, Future};
 
+use crate::adaptors::throttle::{InMemoryStorage, Storage};
+
 // Required to not trigger `clippy::type-complexity` lint
 type BoxedFnMut<I, O> = Box<dyn FnMut(I) -> O + Send>;
 type BoxedFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
@@ -27,6 +29,9 @@ pub struct Settings {
     pub on_queue_full: BoxedFnMut<usize, BoxedFuture>,
     pub retry: bool,
     pub check_slow_mode: bool,
+    pub adaptive: bool,
+    pub storage: Box<dyn Storage>,
+    pub weights: Weights,
 }
 
 /// Telegram request limits.
@@ -53,6 +58,38 @@ pub struct Limits {
     pub messages_per_sec_overall: u32,
 }
 
+/// Weights of requests, i.e.: how many messages a request counts as when
+/// checking [`Limits`].
+///
+/// Requests which have a `None` weight are not throttled at all (they are sent
+/// immediately, bypassing the queue). Inline edits (i.e. edits by
+/// `inline_message_id`) are never throttled since they are not bound to a
+/// chat.
+///
+/// Note that a request is allowed to be sent as long as limits are not yet
+/// exceeded, so a heavy request (e.g. a media group with 10 items) may exceed
+/// limits a bit, in which case following requests will wait longer.
+///
+/// This struct is used in [`Settings`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub struct Weights {
+    /// Weight of requests that send a single message (`send_message`,
+    /// `send_photo`, `send_game`, etc).
+    pub message: u32,
+
+    /// Weight of every item of a media group sent with `send_media_group`.
+    pub media_group_item: u32,
+
+    /// Weight of requests that edit messages (`edit_message_text`,
+    /// `edit_message_live_location`, `stop_poll`, etc).
+    pub edit: Option<u32>,
+
+    /// Weight of other requests bound to a chat (`send_chat_action`,
+    /// `delete_message`, `pin_chat_message`, etc).
+    pub chat_bound: Option<u32>,
+}
+
 impl Settings {
     pub fn limits(mut self, val: Limits) -> Self {
         self.limits = val;
@@ -77,6 +114,33 @@ impl Settings {
         self.check_slow_mode = true;
         self
     }
+
+    /// Enables adaptive limits.
+    ///
+    /// When `RetryAfter` errors are observed for a chat (or for several chats,
+    /// which likely means that the global limit was hit), the effective rate is
+    /// lowered for that chat (or globally) and then slowly raised back up
+    /// toward the configured [`Limits`].
+    ///
+    /// This allows to use higher [`Limits`] if your bot was whitelisted for
+    /// them, while still backing off if telegram doesn't agree.
+    pub fn adaptive(mut self) -> Self {
+        self.adaptive = true;
+        self
+    }
+
+    pub fn weights(mut self, val: Weights) -> Self {
+        self.weights = val;
+        self
+    }
+
+    pub fn storage<S>(mut self, val: S) -> Self
+    where
+        S: Storage + 'static,
+    {
+        self.storage = Box::new(val);
+        self
+    }
 }
 
 impl Default for Settings {
@@ -89,6 +153,9 @@ impl Default for Settings {
             }),
             retry: true,
             check_slow_mode: false,
+            adaptive: false,
+            storage: Box::new(InMemoryStorage::new()),
+            weights: <_>::default(),
         }
     }
 }
@@ -107,3 +174,38 @@ impl Default for Limits {
         }
     }
 }
+
+impl Weights {
+    pub fn message(mut self, val: u32) -> Self {
+        self.message = val;
+        self
+    }
+
+    pub fn media_group_item(mut self, val: u32) -> Self {
+        self.media_group_item = val;
+        self
+    }
+
+    pub fn edit(mut self, val: Option<u32>) -> Self {
+        self.edit = val;
+        self
+    }
+
+    pub fn chat_bound(mut self, val: Option<u32>) -> Self {
+        self.chat_bound = val;
+        self
+    }
+}
+
+/// By default every message (including every item of a media group) has
+/// weight 1, while edits and other chat-bound requests are not throttled.
+impl Default for Weights {
+    fn default() -> Self {
+        Self {
+            message: 1,
+            media_group_item: 1,
+            edit: None,
+            chat_bound: None,
+        }
+    }
+}
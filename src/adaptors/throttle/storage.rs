@@ -0,0 +1,169 @@
+use std::{
+    collections::{hash_map::Entry, HashMap, VecDeque},
+    time::{Duration, Instant},
+};
+
+use futures::future::{ready, BoxFuture};
+
+use crate::adaptors::throttle::ChatIdHash;
+
+const MINUTE: Duration = Duration::from_secs(60);
+const SECOND: Duration = Duration::from_secs(1);
+
+/// Storage of the requests history used by [`Throttle`].
+///
+/// [`Throttle`] consults the storage on every iteration of its worker to find
+/// out how many requests were recently sent, and reports every request it lets
+/// through. By default history is kept in process memory (see
+/// [`InMemoryStorage`]), which means that limits are only respected for a
+/// single process. If you run several instances of the same bot, implement this
+/// trait on top of some shared store (a file with an advisory lock, redis,
+/// etc) and pass it via [`Settings::storage`], so that all instances share the
+/// limits.
+///
+/// Note that coordination through a shared storage is approximate: requests
+/// are first checked against a snapshot and only recorded after they are
+/// allowed, so instances that check limits simultaneously may slightly exceed
+/// them.
+///
+/// Implementations are expected to handle their errors on their own (e.g.:
+/// log them and return the latest known snapshot), the worker never stops
+/// because of the storage.
+///
+/// [`Throttle`]: crate::adaptors::throttle::Throttle
+/// [`Settings::storage`]: crate::adaptors::throttle::Settings::storage
+pub trait Storage: Send {
+    /// Returns statistics of requests sent during the last minute.
+    ///
+    /// Implementations may use this call to forget records older than a
+    /// minute.
+    fn requests_sent(&mut self) -> BoxFuture<'_, RequestsSent>;
+
+    /// Records that requests to `chats` were just sent.
+    ///
+    /// `chats` may contain the same chat several times, in which case every
+    /// occurrence must be counted.
+    fn record<'a>(&'a mut self, chats: &'a [ChatIdHash]) -> BoxFuture<'a, ()>;
+}
+
+/// Statistics of recently sent requests, returned by
+/// [`Storage::requests_sent`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RequestsSent {
+    /// Number of requests sent to all chats during the last second.
+    pub overall_per_sec: u32,
+
+    /// Number of requests sent to each chat during the last second.
+    ///
+    /// Chats without requests may be omitted.
+    pub per_sec: HashMap<ChatIdHash, u32>,
+
+    /// Number of requests sent to each chat during the last minute.
+    ///
+    /// Chats without requests may be omitted.
+    pub per_min: HashMap<ChatIdHash, u32>,
+}
+
+/// [`Storage`] which keeps the history in process memory.
+///
+/// This is the default storage used by [`Throttle`].
+///
+/// [`Throttle`]: crate::adaptors::throttle::Throttle
+#[derive(Debug, Default)]
+pub struct InMemoryStorage {
+    history: VecDeque<(ChatIdHash, Instant)>,
+    // I wish there was special data structure for history which removed the
+    // need in this hashmap
+    // (waffle)
+    per_min: HashMap<ChatIdHash, u32>,
+}
+
+impl InMemoryStorage {
+    /// Creates an empty storage.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Storage for InMemoryStorage {
+    fn requests_sent(&mut self) -> BoxFuture<'_, RequestsSent> {
+        let now = Instant::now();
+        let min_back = now - MINUTE;
+        let sec_back = now - SECOND;
+
+        // make history and per_min up-to-date
+        while let Some((_, time)) = self.history.front() {
+            // history is sorted, we found first up-to-date thing
+            if time >= &min_back {
+                break;
+            }
+
+            if let Some((chat, _)) = self.history.pop_front() {
+                let entry = self.per_min.entry(chat).and_modify(|count| {
+                    *count -= 1;
+                });
+
+                if let Entry::Occupied(entry) = entry {
+                    if *entry.get() == 0 {
+                        entry.remove_entry();
+                    }
+                }
+            }
+        }
+
+        // It's easier to just recompute last second stats, instead of keeping
+        // track of it alongside with minute stats.
+        let mut per_sec = HashMap::new();
+        for (chat, _) in self
+            .history
+            .iter()
+            .rev()
+            .take_while(|(_, time)| time > &sec_back)
+        {
+            *per_sec.entry(*chat).or_insert(0) += 1;
+        }
+
+        let overall_per_sec = per_sec.values().sum();
+
+        Box::pin(ready(RequestsSent {
+            overall_per_sec,
+            per_sec,
+            per_min: self.per_min.clone(),
+        }))
+    }
+
+    fn record<'a>(&'a mut self, chats: &'a [ChatIdHash]) -> BoxFuture<'a, ()> {
+        let now = Instant::now();
+
+        for &chat in chats {
+            *self.per_min.entry(chat).or_insert(0) += 1;
+            self.history.push_back((chat, now));
+        }
+
+        Box::pin(ready(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        adaptors::throttle::{ChatIdHash, InMemoryStorage, Storage},
+        types::ChatId,
+    };
+
+    #[tokio::test]
+    async fn in_memory_counts() {
+        let a = ChatIdHash::Id(ChatId(1));
+        let b = ChatIdHash::Id(ChatId(2));
+
+        let mut storage = InMemoryStorage::new();
+        storage.record(&[a, b, a]).await;
+
+        let sent = storage.requests_sent().await;
+        assert_eq!(sent.overall_per_sec, 3);
+        assert_eq!(sent.per_sec[&a], 2);
+        assert_eq!(sent.per_sec[&b], 1);
+        assert_eq!(sent.per_min[&a], 2);
+        assert_eq!(sent.per_min[&b], 1);
+    }
+}
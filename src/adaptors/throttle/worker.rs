@@ -1,5 +1,5 @@
 use std::{
-    collections::{hash_map::Entry, HashMap, VecDeque},
+    collections::HashMap,
     time::{Duration, Instant},
 };
 
@@ -7,14 +7,13 @@ use tokio::sync::{mpsc, mpsc::error::TryRecvError, oneshot::Sender};
 use vecrem::VecExt;
 
 use crate::{
-    adaptors::throttle::{request_lock::RequestLock, ChatIdHash, Limits, Settings},
+    adaptors::throttle::{
+        adaptive::AdaptiveLimits, request_lock::RequestLock, ChatIdHash, Limits, Settings,
+    },
     errors::AsResponseParameters,
     requests::{Request, Requester},
 };
 
-const MINUTE: Duration = Duration::from_secs(60);
-const SECOND: Duration = Duration::from_secs(1);
-
 // Delay between worker iterations.
 //
 // For now it's `second/4`, but that number is chosen pretty randomly, we may
@@ -30,17 +29,6 @@ pub(super) enum InfoMessage {
     SetLimits { new: Limits, response: Sender<()> },
 }
 
-type RequestsSent = u32;
-
-// I wish there was special data structure for history which removed the
-// need in 2 hashmaps
-// (waffle)
-#[derive(Default)]
-struct RequestsSentToChats {
-    per_min: HashMap<ChatIdHash, RequestsSent>,
-    per_sec: HashMap<ChatIdHash, RequestsSent>,
-}
-
 pub(super) struct FreezeUntil {
     pub(super) until: Instant,
     pub(super) after: Duration,
@@ -52,17 +40,22 @@ pub(super) struct FreezeUntil {
 //
 // ### Request
 //
-// When a throttling request is sent, it sends a tuple of `ChatId` and
-// `Sender<()>` to the worker. Then the request waits for a notification from
-// the worker. When notification is received, it sends the underlying request.
+// When a throttling request is sent, it sends a tuple of `ChatId`, weight of
+// the request (i.e. how many messages it counts as) and `Sender<()>` to the
+// worker. Then the request waits for a notification from the worker. When
+// notification is received, it sends the underlying request.
+//
+// Requests which are not throttled (their weight is `None`) are sent
+// immediately, bypassing the worker.
 //
 // ### Worker
 //
 // The worker does the most important job -- it ensures that the limits are
 // never exceeded.
 //
-// The worker stores a history of requests sent in the last minute (and to which
-// chats they were sent) and a queue of pending updates.
+// The worker stores a queue of pending updates, while the history of requests
+// sent in the last minute (and to which chats they were sent) is kept by a
+// `Storage` (in memory by default, but it may be shared between processes).
 //
 // The worker does the following algorithm loop:
 //
@@ -72,31 +65,33 @@ pub(super) struct FreezeUntil {
 // 2. Read all present messages from an incoming channel and transfer them to
 // the queue.
 //
-// 3. Record the current time.
-//
-// 4. Clear the history from records whose time < (current time - minute).
+// 3. Ask the storage how many requests were sent last second and last minute
+// overall and to which chats (i.e.: `Map<ChatId, Count>`). The storage clears
+// the history from records whose time < (current time - minute).
 //
-// 5. Count all requests which were sent last second, `allowed =
-// limit.messages_per_sec_overall - count`.
+// 4. Compute `allowed = limit.messages_per_sec_overall - count`, where `count`
+// is the number of requests sent last second. (If adaptive limits are enabled,
+// limits may be lowered after `RetryAfter` errors, see `adaptive.rs`.)
 //
-// 6. If `allowed == 0` wait a bit and `continue` to the next iteration.
+// 5. If `allowed == 0` wait a bit and `continue` to the next iteration.
 //
-// 7. Count how many requests were sent to which chats (i.e.: create
-// `Map<ChatId, Count>`). (Note: the same map, but for last minute also exists,
-// but it's updated, instead of recreation.)
+// 6. While `allowed > 0` search for requests which chat haven't exceed the
+// limits (i.e.: map[chat] < limit), if one is found, decrease `allowed` by the
+// weight of the request, notify the request that it can be now executed,
+// increase counts by the weight.
 //
-// 8. While `allowed >= 0` search for requests which chat haven't exceed the
-// limits (i.e.: map[chat] < limit), if one is found, decrease `allowed`, notify
-// the request that it can be now executed, increase counts, add record to the
-// history.
+// 7. Record all requests that were unlocked in the storage.
 pub(super) async fn worker<B>(
     Settings {
         mut limits,
         mut on_queue_full,
         retry,
         check_slow_mode,
+        adaptive,
+        mut storage,
+        weights: _,
     }: Settings,
-    mut rx: mpsc::Receiver<(ChatIdHash, RequestLock)>,
+    mut rx: mpsc::Receiver<(ChatIdHash, u32, RequestLock)>,
     mut info_rx: mpsc::Receiver<InfoMessage>,
     bot: B,
 ) where
@@ -106,15 +101,16 @@ pub(super) async fn worker<B>(
     // FIXME(waffle): Make an research about data structures for this queue.
     //                Currently this is O(n) removing (n = number of elements
     //                stayed), amortized O(1) push (vec+vecrem).
-    let mut queue: Vec<(ChatIdHash, RequestLock)> =
+    let mut queue: Vec<(ChatIdHash, u32, RequestLock)> =
         Vec::with_capacity(limits.messages_per_sec_overall as usize);
 
-    let mut history: VecDeque<(ChatIdHash, Instant)> = VecDeque::new();
-    let mut requests_sent = RequestsSentToChats::default();
+    let mut sent = Vec::new();
 
     let mut slow_mode: Option<HashMap<ChatIdHash, (Duration, Instant)>> =
         check_slow_mode.then(HashMap::new);
 
+    let mut adaptive: Option<AdaptiveLimits> = adaptive.then(<_>::default);
+
     let mut rx_is_closed = false;
 
     let mut last_queue_full = Instant::now()
@@ -140,6 +136,7 @@ pub(super) async fn worker<B>(
                     freeze(
                         &mut freeze_rx,
                         slow_mode.as_mut(),
+                        adaptive.as_mut(),
                         &bot,
                         freeze_until
                     )
@@ -188,48 +185,24 @@ pub(super) async fn worker<B>(
         //
         // (waffle)
 
-        let now = Instant::now();
-        let min_back = now - MINUTE;
-        let sec_back = now - SECOND;
+        let mut requests_sent = storage.requests_sent().await;
 
-        // make history and requests_sent up-to-date
-        while let Some((_, time)) = history.front() {
-            // history is sorted, we found first up-to-date thing
-            if time >= &min_back {
-                break;
-            }
-
-            if let Some((chat, _)) = history.pop_front() {
-                let entry = requests_sent.per_min.entry(chat).and_modify(|count| {
-                    *count -= 1;
-                });
-
-                if let Entry::Occupied(entry) = entry {
-                    if *entry.get() == 0 {
-                        entry.remove_entry();
-                    }
-                }
-            }
+        if let Some(adaptive) = &mut adaptive {
+            adaptive.recover();
         }
 
-        // as truncates which is ok since in case of truncation it would always be >=
-        // limits.overall_s
-        let used = history
-            .iter()
-            .take_while(|(_, time)| time > &sec_back)
-            .count() as u32;
-        let mut allowed = limits.messages_per_sec_overall.saturating_sub(used);
+        let messages_per_sec_overall = match &adaptive {
+            Some(adaptive) => adaptive.messages_per_sec_overall(&limits),
+            None => limits.messages_per_sec_overall,
+        };
+
+        let mut allowed = messages_per_sec_overall.saturating_sub(requests_sent.overall_per_sec);
 
         if allowed == 0 {
-            requests_sent.per_sec.clear();
             tokio::time::sleep(DELAY).await;
             continue;
         }
 
-        for (chat, _) in history.iter().take_while(|(_, time)| time > &sec_back) {
-            *requests_sent.per_sec.entry(*chat).or_insert(0) += 1;
-        }
-
         let mut queue_removing = queue.removing();
 
         while let Some(entry) = queue_removing.next() {
@@ -246,33 +219,39 @@ pub(super) async fn worker<B>(
             let requests_sent_per_sec_count = requests_sent.per_sec.get(chat).copied().unwrap_or(0);
             let requests_sent_per_min_count = requests_sent.per_min.get(chat).copied().unwrap_or(0);
 
+            let chat_limits = match &adaptive {
+                Some(adaptive) => adaptive.chat_limits(chat, &limits),
+                None => limits,
+            };
+
             let messages_per_min_limit = if chat.is_channel() {
-                limits.messages_per_min_channel
+                chat_limits.messages_per_min_channel
             } else {
-                limits.messages_per_min_chat
+                chat_limits.messages_per_min_chat
             };
 
-            let limits_not_exceeded = requests_sent_per_sec_count < limits.messages_per_sec_chat
+            let limits_not_exceeded = requests_sent_per_sec_count
+                < chat_limits.messages_per_sec_chat
                 && requests_sent_per_min_count < messages_per_min_limit;
 
             if limits_not_exceeded {
                 // Unlock the associated request.
 
                 let chat = *chat;
-                let (_, lock) = entry.remove();
+                let (_, weight, lock) = entry.remove();
 
                 // Only count request as sent if the request wasn't dropped before unlocked
                 if lock.unlock(retry, freeze_tx.clone()).is_ok() {
-                    *requests_sent.per_sec.entry(chat).or_insert(0) += 1;
-                    *requests_sent.per_min.entry(chat).or_insert(0) += 1;
-                    history.push_back((chat, Instant::now()));
+                    *requests_sent.per_sec.entry(chat).or_insert(0) += weight;
+                    *requests_sent.per_min.entry(chat).or_insert(0) += weight;
+                    sent.extend((0..weight).map(|_| chat));
 
                     if let Some((_, last)) = slow_mode {
                         *last = Instant::now();
                     }
 
-                    // We have "sent" one request, so now we can send one less.
-                    allowed -= 1;
+                    // We have "sent" `weight` requests, so now we can send less.
+                    allowed = allowed.saturating_sub(weight);
                     if allowed == 0 {
                         break;
                     }
@@ -280,9 +259,11 @@ pub(super) async fn worker<B>(
             }
         }
 
-        // It's easier to just recompute last second stats, instead of keeping
-        // track of it alongside with minute stats, so we just throw this away.
-        requests_sent.per_sec.clear();
+        if !sent.is_empty() {
+            storage.record(&sent).await;
+            sent.clear();
+        }
+
         tokio::time::sleep(DELAY).await;
     }
 }
@@ -304,12 +285,19 @@ fn answer_info(rx: &mut mpsc::Receiver<InfoMessage>, limits: &mut Limits) {
 async fn freeze(
     rx: &mut mpsc::Receiver<FreezeUntil>,
     mut slow_mode: Option<&mut HashMap<ChatIdHash, (Duration, Instant)>>,
+    mut adaptive: Option<&mut AdaptiveLimits>,
     bot: &impl Requester,
     mut imm: Option<FreezeUntil>,
 ) {
     while let Some(freeze_until) = imm.take().or_else(|| rx.try_recv().ok()) {
         let FreezeUntil { until, after, chat } = freeze_until;
 
+        // See the comment about `.as_deref_mut()` down below
+        #[allow(clippy::needless_option_as_deref)]
+        if let Some(adaptive) = adaptive.as_deref_mut() {
+            adaptive.on_retry_after(chat);
+        }
+
         // Clippy thinks that this `.as_deref_mut()` doesn't change the type (&mut
         // HashMap -> &mut HashMap), but it's actually a reborrow (the lifetimes
         // differ), since we are in a loop, simply using `slow_mode` would produce a
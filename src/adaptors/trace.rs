@@ -1,11 +1,15 @@
 use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
     fmt::Debug,
     future::Future,
     pin::Pin,
+    sync::Arc,
     task::{self, Poll},
 };
 
 use futures::ready;
+use serde::Serialize;
 use url::Url;
 
 use crate::{
@@ -25,15 +29,44 @@ use crate::{
 /// TRACE teloxide_core::adaptors::trace > Sending `SendDice` request: SendDice { chat_id: Id(0), emoji: Some(Dice), disable_notification: None, reply_to_message_id: None, allow_sending_without_reply: None, reply_markup: None }
 /// TRACE teloxide_core::adaptors::trace > Got response from `SendDice` request: Ok(Message { id: 13812, date: 1625926524, chat: Chat { .. }, via_bot: None, kind: Dice(MessageDice { dice: Dice { emoji: Dice, value: 3 } }) })
 /// ```
+///
+/// With the `tracing` feature enabled and [`Settings::TRACE_SPANS`] set, this
+/// adaptor also opens a [`tracing`] span for every request. The span is a
+/// child of the span which was current when the request was sent and has the
+/// following fields:
+/// - `method` — name of the method, e.g. `SendMessage`
+/// - `chat_id` — chat id (or channel username), if the request has one
+/// - `payload_size` — size of the JSON-serialized payload in bytes (files are
+///   not included)
+/// - `duration_ms` — time passed from the first poll until the response
+/// - `outcome` — `"ok"` or `"error"`
+/// - `error` — the error, if the request has failed
+///
+/// Verbose traces may contain secrets and personal data (phone numbers,
+/// payment provider tokens, etc). Use [`Trace::with_redaction`] to hide them,
+/// see [`Redaction`] for more.
+///
+/// [`tracing`]: https://docs.rs/tracing
 #[derive(Clone, Debug)]
 pub struct Trace<B> {
     inner: B,
     settings: Settings,
+    redaction: Arc<Redaction>,
 }
 
 impl<B> Trace<B> {
     pub fn new(inner: B, settings: Settings) -> Self {
-        Self { inner, settings }
+        Self {
+            inner,
+            settings,
+            redaction: Arc::new(Redaction::new()),
+        }
+    }
+
+    /// Sets rules of hiding data from verbose traces.
+    pub fn with_redaction(mut self, redaction: Redaction) -> Self {
+        self.redaction = Arc::new(redaction);
+        self
     }
 
     pub fn inner(&self) -> &B {
@@ -47,6 +80,10 @@ impl<B> Trace<B> {
     pub fn settings(&self) -> Settings {
         self.settings
     }
+
+    pub fn redaction(&self) -> &Redaction {
+        &self.redaction
+    }
 }
 
 bitflags::bitflags! {
@@ -90,6 +127,336 @@ bitflags::bitflags! {
         ///
         /// Implies [`TRACE_REQUESTS_VERBOSE`] and [`TRACE_RESPONSES_VERBOSE`].
         const TRACE_EVERYTHING_VERBOSE = Self::TRACE_REQUESTS_VERBOSE.bits | Self::TRACE_RESPONSES_VERBOSE.bits;
+
+        /// Open a `tracing` span for every request.
+        ///
+        /// Has no effect unless the `tracing` feature is enabled.
+        const TRACE_SPANS = 0b00010000;
+
+        /// Output verbose traces as JSON.
+        ///
+        /// Payloads and responses are serialized the same way they are sent
+        /// to (received from) Telegram. Has effect only together with
+        /// [`TRACE_REQUESTS_VERBOSE`] or [`TRACE_RESPONSES_VERBOSE`].
+        ///
+        /// Note that verbose traces are always output as JSON when
+        /// [`Redaction`] rules are set.
+        const TRACE_JSON = 0b00100000;
+    }
+}
+
+/// Rules of hiding sensitive data from verbose traces of [`Trace`].
+///
+/// Data can be hidden either by field name (in all payloads and responses, at
+/// any depth) or by a per-payload [`Policy`]. Redacted values are replaced with
+/// `"<redacted>"`.
+///
+/// Since `Debug` output can't be reliably redacted, verbose traces are output
+/// as JSON (see [`Settings::TRACE_JSON`]) if any rules are set.
+///
+/// ## Examples
+///
+/// ```
+/// use teloxide_core::{
+///     adaptors::trace::{Policy, Redaction, Settings},
+///     payloads::{SendContact, SendInvoice},
+///     requests::RequesterExt,
+///     Bot,
+/// };
+///
+/// let redaction = Redaction::new()
+///     .field("phone_number")
+///     .payload::<SendInvoice>(Policy::fields(["provider_token", "payload"]))
+///     .payload::<SendContact>(Policy::Hide);
+///
+/// let bot = Bot::new("TOKEN")
+///     .trace(Settings::TRACE_EVERYTHING_VERBOSE)
+///     .with_redaction(redaction);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Redaction {
+    fields: HashSet<Cow<'static, str>>,
+    payloads: HashMap<&'static str, Policy>,
+}
+
+/// Policy of redacting traces of requests with a particular payload, see
+/// [`Redaction::payload`].
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub enum Policy {
+    /// Redact fields with the given names (in addition to the fields redacted
+    /// for all payloads).
+    Fields(Vec<Cow<'static, str>>),
+
+    /// Don't output the payload and the response at all.
+    Hide,
+}
+
+impl Policy {
+    /// Creates [`Policy::Fields`].
+    pub fn fields<I>(fields: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<Cow<'static, str>>,
+    {
+        Self::Fields(fields.into_iter().map(Into::into).collect())
+    }
+}
+
+impl Redaction {
+    const REDACTED: &'static str = "<redacted>";
+    const HIDDEN: &'static str = "<hidden>";
+
+    /// Creates rules which redact nothing.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates rules which redact commonly sensitive data: phone numbers,
+    /// emails, shipping addresses, payment provider tokens and Telegram
+    /// Passport data.
+    pub fn sensitive() -> Self {
+        Self::new()
+            .field("phone_number")
+            .field("email")
+            .field("shipping_address")
+            .field("provider_token")
+            .field("passport_data")
+            .payload::<crate::payloads::SetPassportDataErrors>(Policy::Hide)
+    }
+
+    /// Redacts fields named `name` in all payloads and responses.
+    ///
+    /// `name` is the name of the field as it is sent to (received from)
+    /// Telegram, e.g. `"phone_number"`.
+    pub fn field<N>(mut self, name: N) -> Self
+    where
+        N: Into<Cow<'static, str>>,
+    {
+        self.fields.insert(name.into());
+        self
+    }
+
+    /// Sets the policy for payload `P` (and responses to requests with it).
+    pub fn payload<P: Payload>(mut self, policy: Policy) -> Self {
+        self.payloads.insert(P::NAME, policy);
+        self
+    }
+
+    /// Returns `true` if no rules are set.
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty() && self.payloads.is_empty()
+    }
+
+    fn render_payload<P>(&self, payload: &P) -> String
+    where
+        P: Payload + Serialize,
+    {
+        self.render::<P, _>(payload)
+    }
+
+    fn render_response<P, T, E>(&self, response: &Result<T, E>) -> String
+    where
+        P: Payload,
+        T: Serialize,
+        E: Debug,
+    {
+        #[derive(Serialize)]
+        #[serde(rename_all = "snake_case")]
+        enum Response<'a, T> {
+            Ok(&'a T),
+            Error(String),
+        }
+
+        let response = match response {
+            Ok(output) => Response::Ok(output),
+            Err(err) => {
+                let err = format!("{:?}", err);
+                Response::Error(self.redact_text(&err, self.payload_fields::<P>()))
+            }
+        };
+
+        self.render::<P, _>(&response)
+    }
+
+    fn render<P, T>(&self, value: &T) -> String
+    where
+        P: Payload,
+        T: Serialize,
+    {
+        let policy = self.payloads.get(P::NAME);
+        if let Some(Policy::Hide) = policy {
+            return Self::HIDDEN.to_owned();
+        }
+
+        let mut value = match serde_json::to_value(value) {
+            Ok(value) => value,
+            Err(err) => return format!("<failed to serialize: {}>", err),
+        };
+
+        self.redact(&mut value, self.payload_fields::<P>());
+
+        value.to_string()
+    }
+
+    fn payload_fields<P: Payload>(&self) -> &[Cow<'static, str>] {
+        match self.payloads.get(P::NAME) {
+            Some(Policy::Fields(fields)) => fields,
+            _ => &[],
+        }
+    }
+
+    fn is_redacted(&self, key: &str, payload_fields: &[Cow<'static, str>]) -> bool {
+        self.fields.contains(key) || payload_fields.iter().any(|field| field == key)
+    }
+
+    fn redact(&self, value: &mut serde_json::Value, payload_fields: &[Cow<'static, str>]) {
+        use serde_json::Value;
+
+        match value {
+            Value::Object(map) => {
+                for (key, value) in map {
+                    if self.is_redacted(key, payload_fields) {
+                        *value = Value::String(Self::REDACTED.to_owned());
+                    } else {
+                        self.redact(value, payload_fields);
+                    }
+                }
+            }
+            Value::Array(values) => {
+                for value in values {
+                    self.redact(value, payload_fields);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Redacts fields of JSON embedded into `text`, e.g. of the raw response
+    /// in the `Debug` output of an error.
+    ///
+    /// JSON can be embedded as is or as an escaped string.
+    fn redact_text(&self, text: &str, payload_fields: &[Cow<'static, str>]) -> String {
+        let mut out = String::with_capacity(text.len());
+        let mut rest = text;
+
+        while let Some(quote) = rest.find('"') {
+            let escaped = rest[..quote].ends_with('\\');
+            let mut json = EmbeddedJson {
+                text: &rest[quote + 1..],
+                escaped,
+            };
+
+            let key_start = quote + 1;
+            let redacted = json.skip_string().is_some() && {
+                let quote_len = if escaped { 2 } else { 1 };
+                let key_end = rest.len() - json.text.len() - quote_len;
+                let key = &rest[key_start..key_end];
+
+                json.skip_whitespace();
+                json.bump() == Some(':') && self.is_redacted(key, payload_fields)
+            };
+
+            if !redacted {
+                out.push_str(&rest[..key_start]);
+                rest = &rest[key_start..];
+                continue;
+            }
+
+            json.skip_whitespace();
+            let value_start = rest.len() - json.text.len();
+            out.push_str(&rest[..value_start]);
+
+            let quote = if escaped { "\\\"" } else { "\"" };
+            out.push_str(quote);
+            out.push_str(Self::REDACTED);
+            out.push_str(quote);
+
+            json.skip_value();
+            rest = json.text;
+        }
+
+        out.push_str(rest);
+        out
+    }
+}
+
+/// Reader of JSON embedded into text, see [`Redaction::redact_text`].
+struct EmbeddedJson<'a> {
+    text: &'a str,
+    /// `true` if JSON is escaped as a string, i.e. `"` is `\"` and `\` is `\\`.
+    escaped: bool,
+}
+
+impl EmbeddedJson<'_> {
+    /// Returns the next JSON character and its length in the text.
+    fn peek(&self) -> Option<(char, usize)> {
+        let mut chars = self.text.chars();
+        match chars.next()? {
+            '\\' if self.escaped => {
+                let c = chars.next()?;
+                let unescaped = match c {
+                    'n' => '\n',
+                    'r' => '\r',
+                    't' => '\t',
+                    c => c,
+                };
+                Some((unescaped, 1 + c.len_utf8()))
+            }
+            c => Some((c, c.len_utf8())),
+        }
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let (c, len) = self.peek()?;
+        self.text = &self.text[len..];
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some((c, _)) if c.is_whitespace()) {
+            self.bump();
+        }
+    }
+
+    /// Skips the rest of a string, after the opening quote.
+    fn skip_string(&mut self) -> Option<()> {
+        loop {
+            match self.bump()? {
+                '\\' => {
+                    self.bump()?;
+                }
+                '"' => return Some(()),
+                _ => {}
+            }
+        }
+    }
+
+    fn skip_value(&mut self) -> Option<()> {
+        match self.bump()? {
+            '"' => self.skip_string(),
+            '{' | '[' => {
+                let mut depth = 1;
+                while depth > 0 {
+                    match self.bump()? {
+                        '"' => self.skip_string()?,
+                        '{' | '[' => depth += 1,
+                        '}' | ']' => depth -= 1,
+                        _ => {}
+                    }
+                }
+                Some(())
+            }
+            _ => {
+                while let Some((c, _)) = self.peek() {
+                    if matches!(c, ',' | '}' | ']') || c.is_whitespace() {
+                        break;
+                    }
+                    self.bump();
+                }
+                Some(())
+            }
+        }
     }
 }
 
@@ -103,7 +470,9 @@ macro_rules! fwd_inner {
     ($m:ident $this:ident ($($arg:ident : $T:ty),*)) => {
         TraceRequest {
             inner: $this.inner().$m($($arg),*),
-            settings: $this.settings
+            settings: $this.settings,
+            redaction: Arc::clone(&$this.redaction),
+            hooks: Hooks::new(),
         }
     };
 }
@@ -217,20 +586,150 @@ where
 }
 
 #[must_use = "Requests are lazy and do nothing unless sent"]
-pub struct TraceRequest<R> {
+pub struct TraceRequest<R: Request> {
     inner: R,
     settings: Settings,
+    redaction: Arc<Redaction>,
+    hooks: Hooks<R>,
+}
+
+type Render<T> = fn(&Redaction, &T) -> String;
+
+/// Functions which need the payload and the output to be `Serialize`.
+///
+/// They are created by [`Trace`] methods, where the payload type is known, so
+/// that [`TraceRequest`] only requires `Debug`.
+struct Hooks<R: Request> {
+    render_payload: Render<R::Payload>,
+    render_response: Render<Result<Output<R>, R::Err>>,
+    #[cfg_attr(not(feature = "tracing"), allow(dead_code))]
+    chat_id: fn(&R::Payload) -> Option<Recipient>,
+    #[cfg_attr(not(feature = "tracing"), allow(dead_code))]
+    payload_size: fn(&R::Payload) -> Option<usize>,
+}
+
+impl<R> Hooks<R>
+where
+    R: Request,
+    R::Payload: Serialize + ChatIdPayload,
+    Output<R>: Serialize,
+    R::Err: Debug,
+{
+    fn new() -> Self {
+        Self {
+            render_payload: |redaction, payload| redaction.render_payload(payload),
+            render_response: |redaction, response| {
+                redaction.render_response::<R::Payload, _, _>(response)
+            },
+            chat_id: ChatIdPayload::chat_id,
+            payload_size: |payload| serde_json::to_vec(payload).ok().map(|json| json.len()),
+        }
+    }
+}
+
+/// Payloads with a chat, recorded in [`RequestSpan`]s.
+trait ChatIdPayload {
+    fn chat_id(&self) -> Option<Recipient>;
+}
+
+macro_rules! impl_chat_id_payload {
+    (
+        recipient: $($R:ident),*;
+        game: $($G:ident),*;
+        menu_button: $($M:ident),*;
+        none: $($N:ident),*;
+    ) => {
+        $(
+            impl ChatIdPayload for crate::payloads::$R {
+                fn chat_id(&self) -> Option<Recipient> {
+                    Some(self.chat_id.clone())
+                }
+            }
+        )*
+
+        $(
+            impl ChatIdPayload for crate::payloads::$G {
+                fn chat_id(&self) -> Option<Recipient> {
+                    Some(Recipient::Id(ChatId(self.chat_id.into())))
+                }
+            }
+        )*
+
+        $(
+            impl ChatIdPayload for crate::payloads::$M {
+                fn chat_id(&self) -> Option<Recipient> {
+                    self.chat_id.map(Recipient::Id)
+                }
+            }
+        )*
+
+        $(
+            impl ChatIdPayload for crate::payloads::$N {
+                fn chat_id(&self) -> Option<Recipient> {
+                    None
+                }
+            }
+        )*
+    };
+}
+
+impl_chat_id_payload! {
+    recipient:
+        ApproveChatJoinRequest, BanChatMember, BanChatSenderChat, CopyMessage,
+        CreateChatInviteLink, DeclineChatJoinRequest, DeleteChatPhoto, DeleteChatStickerSet,
+        DeleteMessage, EditChatInviteLink, EditMessageCaption, EditMessageLiveLocation,
+        EditMessageMedia, EditMessageReplyMarkup, EditMessageText, ExportChatInviteLink,
+        ForwardMessage, GetChat, GetChatAdministrators, GetChatMember, GetChatMemberCount,
+        GetChatMembersCount, KickChatMember, LeaveChat, PinChatMessage, PromoteChatMember,
+        RestrictChatMember, RevokeChatInviteLink, SendAnimation, SendAudio, SendChatAction,
+        SendContact, SendDice, SendDocument, SendInvoice, SendLocation, SendMediaGroup,
+        SendMessage, SendPhoto, SendPoll, SendSticker, SendVenue, SendVideo, SendVideoNote,
+        SendVoice, SetChatAdministratorCustomTitle, SetChatDescription, SetChatPermissions,
+        SetChatPhoto, SetChatStickerSet, SetChatTitle, StopMessageLiveLocation, StopPoll,
+        UnbanChatMember, UnbanChatSenderChat, UnpinAllChatMessages, UnpinChatMessage;
+    game:
+        SendGame, SetGameScore;
+    menu_button:
+        GetChatMenuButton, SetChatMenuButton;
+    none:
+        AddStickerToSet, AnswerCallbackQuery, AnswerInlineQuery, AnswerPreCheckoutQuery,
+        AnswerShippingQuery, AnswerWebAppQuery, Close, CreateInvoiceLink, CreateNewStickerSet,
+        DeleteMyCommands, DeleteStickerFromSet, DeleteWebhook, EditMessageCaptionInline,
+        EditMessageLiveLocationInline, EditMessageMediaInline, EditMessageReplyMarkupInline,
+        EditMessageTextInline, GetFile, GetGameHighScores, GetMe, GetMyCommands,
+        GetMyDefaultAdministratorRights, GetStickerSet, GetUpdates, GetUserProfilePhotos,
+        GetWebhookInfo, LogOut, SetGameScoreInline, SetMyCommands,
+        SetMyDefaultAdministratorRights, SetPassportDataErrors, SetStickerPositionInSet,
+        SetStickerSetThumb, SetWebhook, StopMessageLiveLocationInline, UploadStickerFile;
 }
 
+impl<R: Request> Clone for Hooks<R> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<R: Request> Copy for Hooks<R> {}
+
 impl<R> TraceRequest<R>
 where
     R: Request,
 {
+    fn uses_json(&self) -> bool {
+        self.settings.contains(Settings::TRACE_JSON) || !self.redaction.is_empty()
+    }
+
     fn trace_request(&self)
     where
         R::Payload: Debug,
     {
-        if self.settings.contains(Settings::TRACE_REQUESTS_VERBOSE) {
+        if self.settings.contains(Settings::TRACE_REQUESTS_VERBOSE) && self.uses_json() {
+            log::trace!(
+                "Sending `{}` request: {}",
+                R::Payload::NAME,
+                (self.hooks.render_payload)(&self.redaction, self.inner.payload_ref())
+            );
+        } else if self.settings.contains(Settings::TRACE_REQUESTS_VERBOSE) {
             log::trace!(
                 "Sending `{}` request: {:?}",
                 <R::Payload as Payload>::NAME,
@@ -241,13 +740,24 @@ where
         }
     }
 
-    fn trace_response_fn(&self) -> fn(&Result<Output<R>, R::Err>)
+    #[allow(clippy::type_complexity)]
+    fn trace_response_fn(
+        &self,
+    ) -> fn(&Result<Output<R>, R::Err>, &Redaction, Render<Result<Output<R>, R::Err>>)
     where
         Output<R>: Debug,
         R::Err: Debug,
     {
-        if self.settings.contains(Settings::TRACE_RESPONSES_VERBOSE) {
-            |response| {
+        if self.settings.contains(Settings::TRACE_RESPONSES_VERBOSE) && self.uses_json() {
+            |response, redaction, render| {
+                log::trace!(
+                    "Got response from `{}` request: {}",
+                    R::Payload::NAME,
+                    render(redaction, response)
+                )
+            }
+        } else if self.settings.contains(Settings::TRACE_RESPONSES_VERBOSE) {
+            |response, _, _| {
                 log::trace!(
                     "Got response from `{}` request: {:?}",
                     R::Payload::NAME,
@@ -255,16 +765,16 @@ where
                 )
             }
         } else if self.settings.contains(Settings::TRACE_RESPONSES) {
-            |_| log::trace!("Got response from `{}` request", R::Payload::NAME)
+            |_, _, _| log::trace!("Got response from `{}` request", R::Payload::NAME)
         } else {
-            |_| {}
+            |_, _, _| {}
         }
     }
 }
 
 impl<R> HasPayload for TraceRequest<R>
 where
-    R: HasPayload,
+    R: Request,
 {
     type Payload = R::Payload;
 
@@ -295,6 +805,9 @@ where
 
         Send {
             trace_fn: self.trace_response_fn(),
+            render: self.hooks.render_response,
+            redaction: Arc::clone(&self.redaction),
+            span: RequestSpan::new(self.settings, self.inner.payload_ref(), &self.hooks),
             inner: self.inner.send(),
         }
     }
@@ -304,6 +817,9 @@ where
 
         Send {
             trace_fn: self.trace_response_fn(),
+            render: self.hooks.render_response,
+            redaction: Arc::clone(&self.redaction),
+            span: RequestSpan::new(self.settings, self.inner.payload_ref(), &self.hooks),
             inner: self.inner.send_ref(),
         }
     }
@@ -314,22 +830,203 @@ pub struct Send<F>
 where
     F: Future,
 {
-    trace_fn: fn(&F::Output),
+    trace_fn: fn(&F::Output, &Redaction, Render<F::Output>),
+    render: Render<F::Output>,
+    redaction: Arc<Redaction>,
+    span: RequestSpan,
     #[pin]
     inner: F,
 }
 
-impl<F> Future for Send<F>
+impl<F, T, E> Future for Send<F>
 where
-    F: Future,
+    F: Future<Output = Result<T, E>>,
+    E: Debug,
 {
     type Output = F::Output;
 
     fn poll(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
         let this = self.project();
 
-        let ret = ready!(this.inner.poll(cx));
-        (this.trace_fn)(&ret);
+        let inner = this.inner;
+        let ret = ready!(this.span.in_scope(|| inner.poll(cx)));
+        (this.trace_fn)(&ret, this.redaction, *this.render);
+        this.span.finish(&ret);
         Poll::Ready(ret)
     }
 }
+
+/// `tracing` span of a request, see [`Settings::TRACE_SPANS`].
+#[cfg(feature = "tracing")]
+struct RequestSpan {
+    span: tracing::Span,
+    start: Option<std::time::Instant>,
+}
+
+// Older versions of `tracing` require values to be passed by reference to
+// `Span::record`, newer accept both.
+#[cfg(feature = "tracing")]
+#[allow(
+    unknown_lints,
+    clippy::needless_borrow,
+    clippy::needless_borrows_for_generic_args
+)]
+impl RequestSpan {
+    fn new<R: Request>(settings: Settings, payload: &R::Payload, hooks: &Hooks<R>) -> Self {
+        use tracing::field::Empty;
+
+        let span = if settings.contains(Settings::TRACE_SPANS) {
+            tracing::info_span!(
+                "request",
+                method = R::Payload::NAME,
+                chat_id = Empty,
+                payload_size = Empty,
+                duration_ms = Empty,
+                outcome = Empty,
+                error = Empty,
+            )
+        } else {
+            tracing::Span::none()
+        };
+
+        // Don't serialize the payload if nobody is interested in the span
+        if !span.is_disabled() {
+            match (hooks.chat_id)(payload) {
+                Some(Recipient::Id(id)) => {
+                    span.record("chat_id", &id.0);
+                }
+                Some(Recipient::ChannelUsername(username)) => {
+                    span.record("chat_id", &username.as_str());
+                }
+                None => {}
+            }
+
+            if let Some(size) = (hooks.payload_size)(payload) {
+                span.record("payload_size", &size);
+            }
+        }
+
+        Self { span, start: None }
+    }
+
+    fn in_scope<T>(&mut self, f: impl FnOnce() -> T) -> T {
+        self.start.get_or_insert_with(std::time::Instant::now);
+        self.span.in_scope(f)
+    }
+
+    fn finish<T, E>(&self, result: &Result<T, E>)
+    where
+        E: Debug,
+    {
+        if let Some(start) = self.start {
+            self.span
+                .record("duration_ms", &(start.elapsed().as_millis() as u64));
+        }
+
+        match result {
+            Ok(_) => {
+                self.span.record("outcome", &"ok");
+            }
+            Err(err) => {
+                self.span.record("outcome", &"error");
+                self.span.record("error", &tracing::field::debug(err));
+            }
+        }
+    }
+}
+
+#[cfg(not(feature = "tracing"))]
+struct RequestSpan;
+
+#[cfg(not(feature = "tracing"))]
+impl RequestSpan {
+    fn new<R: Request>(_: Settings, _: &R::Payload, _: &Hooks<R>) -> Self {
+        Self
+    }
+
+    fn in_scope<T>(&mut self, f: impl FnOnce() -> T) -> T {
+        f()
+    }
+
+    fn finish<T, E>(&self, _: &Result<T, E>) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        adaptors::trace::{ChatIdPayload, Policy, Redaction},
+        payloads::{GetChatMenuButton, GetMe, SendContact, SendGame, SendInvoice},
+        types::{ChatId, Recipient},
+        RequestError,
+    };
+
+    #[test]
+    fn chat_id() {
+        let recipient = Recipient::ChannelUsername("@chan".to_owned());
+        let payload = SendContact::new(recipient.clone(), "+1234", "Ann");
+        assert_eq!(payload.chat_id(), Some(recipient));
+        assert_eq!(
+            SendGame::new(2, "game").chat_id(),
+            Some(Recipient::Id(ChatId(2)))
+        );
+        assert_eq!(GetChatMenuButton::new().chat_id(), None);
+        assert_eq!(GetMe::new().chat_id(), None);
+    }
+
+    #[test]
+    fn redact() {
+        let redaction = Redaction::new()
+            .field("phone_number")
+            .payload::<SendInvoice>(Policy::Hide);
+
+        let payload = SendContact::new(ChatId(1), "+1234", "Ann");
+        assert_eq!(
+            redaction.render_payload(&payload),
+            r#"{"chat_id":1,"first_name":"Ann","phone_number":"<redacted>"}"#
+        );
+
+        let redaction = redaction.payload::<SendContact>(Policy::fields(["first_name"]));
+        assert_eq!(
+            redaction.render_payload(&payload),
+            r#"{"chat_id":1,"first_name":"<redacted>","phone_number":"<redacted>"}"#
+        );
+
+        let redaction = redaction.payload::<SendContact>(Policy::Hide);
+        assert_eq!(redaction.render_payload(&payload), "<hidden>");
+    }
+
+    #[test]
+    fn redact_response() {
+        let redaction = Redaction::new().field("phone_number");
+
+        let response: Result<_, ()> = Ok(vec![
+            serde_json::json!({ "contact": { "phone_number": "+1" } }),
+        ]);
+        assert_eq!(
+            redaction.render_response::<SendContact, _, _>(&response),
+            r#"{"ok":[{"contact":{"phone_number":"<redacted>"}}]}"#
+        );
+
+        let response: Result<(), _> = Err("oops");
+        assert_eq!(
+            redaction.render_response::<SendContact, _, _>(&response),
+            r#"{"error":"\"oops\""}"#
+        );
+
+        // Raw response in the error
+        let response: Result<(), _> = Err(RequestError::InvalidJson {
+            source: serde_json::from_str::<()>("").unwrap_err(),
+            raw: r#"{"ok":true,"result":{"phone_number" : "+1","contact":{"phone_number":{"a":"}"}}}}"#
+                .into(),
+        });
+        let rendered = redaction.render_response::<SendContact, _, _>(&response);
+        assert!(!rendered.contains("+1") && !rendered.contains("\"a\""));
+        assert_eq!(rendered.matches("<redacted>").count(), 2);
+
+        let text = r#"X { "phone_number": 1, "phone_number" }"#;
+        assert_eq!(
+            redaction.redact_text(text, &[]),
+            r#"X { "phone_number": "<redacted>", "phone_number" }"#
+        );
+    }
+}
@@ -774,6 +774,20 @@ impl From<reqwest::Error> for RequestError {
     }
 }
 
+/// Returns `true` if `description` of an [`ApiError::Unknown`] is a reason
+/// phrase of a 5xx HTTP status, i.e. telegram servers (or a proxy in front of
+/// them) failed to process the request.
+pub(crate) fn is_server_error(description: &str) -> bool {
+    [
+        "Internal Server Error",
+        "Bad Gateway",
+        "Service Unavailable",
+        "Gateway Timeout",
+    ]
+    .iter()
+    .any(|phrase| description.starts_with(phrase))
+}
+
 /// Replaces token in the url in the error with `token:redacted` string.
 pub(crate) fn hide_token(mut error: reqwest::Error) -> reqwest::Error {
     let url = match error.url_mut() {
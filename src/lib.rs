@@ -48,9 +48,18 @@
 //! - `rustls` — use [`rustls`] tls implementation
 //! - `auto_send` — enables [`AutoSend`] bot adaptor
 //! - `trace_adaptor` — enables [`Trace`] bot adaptor
+//! - `tracing` — enables `tracing` spans in the [`Trace`] bot adaptor
 //! - `erased` — enables [`ErasedRequester`] bot adaptor
 //! - `throttle` — enables [`Throttle`] bot adaptor
 //! - `cache_me` — enables [`CacheMe`] bot adaptor
+//! - `cache` — enables [`Cache`] bot adaptor
+//! - `dry_run` — enables [`DryRun`] bot adaptor
+//! - `circuit_breaker` — enables [`CircuitBreaker`] bot adaptor
+//! - `coalesce_edits` — enables [`CoalesceEdits`] bot adaptor
+//! - `ignore_not_modified` — enables [`IgnoreNotModified`] bot adaptor
+//! - `auto_migrate` — enables [`AutoMigrate`] bot adaptor
+//! - `metrics` — enables [`Metrics`] bot adaptor
+//! - `schedule` — enables [`Scheduler`] of delayed requests
 //! - `full` — enables all features except `nightly` and tls-related
 //! - `nightly` — enables nightly-only features, currently:
 //!   - Removes some future boxing using `#![feature(type_alias_impl_trait)]`
@@ -61,6 +70,14 @@
 //! [`ErasedRequester`]: adaptors::ErasedRequester
 //! [`Throttle`]: adaptors::Throttle
 //! [`CacheMe`]: adaptors::CacheMe
+//! [`Cache`]: adaptors::Cache
+//! [`DryRun`]: adaptors::DryRun
+//! [`CircuitBreaker`]: adaptors::CircuitBreaker
+//! [`CoalesceEdits`]: adaptors::CoalesceEdits
+//! [`IgnoreNotModified`]: adaptors::IgnoreNotModified
+//! [`AutoMigrate`]: adaptors::AutoMigrate
+//! [`Metrics`]: adaptors::Metrics
+//! [`Scheduler`]: requests::Scheduler
 //! [`native-tls`]: https://docs.rs/native-tls
 //! [`rustls`]: https://docs.rs/rustls
 
@@ -109,6 +126,7 @@ pub mod payloads;
 pub mod prelude;
 pub mod requests;
 pub mod types;
+pub mod utils;
 
 // reexported
 mod bot;
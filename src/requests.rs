@@ -1,23 +1,39 @@
 //! Telegram API requests.
 
 pub use self::{
-    has_payload::HasPayload, json::JsonRequest, multipart::MultipartRequest,
-    multipart_payload::MultipartPayload, payload::Payload, request::Request, requester::Requester,
+    broadcast::{broadcast, BroadcastPayload, Delivery, DeliveryError},
+    has_payload::HasPayload,
+    json::JsonRequest,
+    multipart::MultipartRequest,
+    multipart_payload::MultipartPayload,
+    payload::Payload,
+    profile::{BotProfile, ProfileChange, ScopedCommands},
+    request::Request,
+    requester::Requester,
     requester_ext::RequesterExt,
 };
 
+#[cfg(feature = "schedule")]
+pub use self::schedule::{
+    JobHandle, JobId, JobStore, NoStore, ScheduleError, Scheduler, StoredJob,
+};
+
 /// A type that is returned after making a request to Telegram.
 pub type ResponseResult<T> = Result<T, crate::RequestError>;
 
 /// An output type of [`Payload`] in [`HasPayload`].
 pub type Output<T> = <<T as HasPayload>::Payload as Payload>::Output;
 
+mod broadcast;
 mod has_payload;
 mod json;
 mod multipart;
 pub(crate) mod multipart_payload;
 mod payload;
+mod profile;
 mod request;
 mod requester;
 mod requester_ext;
+#[cfg(feature = "schedule")]
+mod schedule;
 mod utils;
@@ -0,0 +1,263 @@
+use futures::{Stream, StreamExt};
+
+use crate::{
+    errors::{is_server_error, ApiError, RequestError},
+    payloads::*,
+    requests::{HasPayload, Payload, Request, Requester},
+    types::Recipient,
+};
+
+/// Sends copies of `template` to all `recipients`.
+///
+/// `chat_id` of the `template` is ignored and replaced by every recipient in
+/// turn. At most `concurrency` requests are sent simultaneously.
+///
+/// Requests are built with `bot`, so if it is (or wraps) a [`Throttle`], the
+/// limits are respected.
+///
+/// The returned stream yields a [`Delivery`] for every recipient (not
+/// necessarily in the same order as recipients) which allows to find out which
+/// recipients can't receive messages anymore (see [`DeliveryError`]).
+///
+/// To broadcast to an iterator of recipients use [`futures::stream::iter`].
+///
+/// ## Examples
+///
+/// ```no_run
+/// use futures::StreamExt;
+/// use teloxide_core::{
+///     payloads::SendMessage,
+///     requests::{broadcast, DeliveryError},
+///     types::{ChatId, Recipient},
+///     Bot,
+/// };
+///
+/// # async {
+/// let bot = Bot::new("TOKEN");
+/// let subscribers = vec![ChatId(1), ChatId(2), ChatId(3)];
+///
+/// let template = SendMessage::new(ChatId(0), "Hi!");
+/// let recipients = futures::stream::iter(subscribers).map(Recipient::Id);
+///
+/// broadcast(&bot, template, recipients, 8)
+///     .for_each(|delivery| async move {
+///         if let Err(DeliveryError::Permanent(_)) = delivery.result {
+///             /* remove `delivery.recipient` from subscribers */
+///         }
+///     })
+///     .await;
+/// # };
+/// ```
+///
+/// ## Panics
+///
+/// If `concurrency` is 0.
+///
+/// [`Throttle`]: crate::adaptors::Throttle
+pub fn broadcast<'a, B, P, S>(
+    bot: &'a B,
+    template: P,
+    recipients: S,
+    concurrency: usize,
+) -> impl Stream<Item = Delivery<P::Output>> + 'a
+where
+    B: Requester<Err = RequestError> + ?Sized,
+    P: BroadcastPayload<B> + 'a,
+    P::Request: 'a,
+    S: Stream<Item = Recipient> + 'a,
+{
+    assert!(concurrency > 0, "`concurrency` must be greater than 0");
+
+    recipients
+        .map(move |recipient| {
+            let mut payload = template.clone();
+            payload.set_recipient(recipient.clone());
+            let request = payload.request(bot);
+
+            async move {
+                let result = request.send().await.map_err(DeliveryError::from);
+                Delivery { recipient, result }
+            }
+        })
+        .buffer_unordered(concurrency)
+}
+
+/// Result of sending a message to one of the recipients of a [`broadcast`].
+#[derive(Debug)]
+pub struct Delivery<T> {
+    pub recipient: Recipient,
+    pub result: Result<T, DeliveryError>,
+}
+
+/// An error which happened while sending a message to one of the recipients
+/// of a [`broadcast`].
+#[derive(Debug, thiserror::Error)]
+pub enum DeliveryError {
+    /// The recipient can't receive messages from the bot anymore (the bot was
+    /// blocked or kicked, the user was deactivated, the chat was not found,
+    /// etc).
+    ///
+    /// Retrying is pointless, you may want to remove the recipient from your
+    /// subscribers.
+    #[error("Recipient is unreachable: {0}")]
+    Permanent(#[source] RequestError),
+
+    /// The request itself was rejected (invalid payload, message is too long,
+    /// file can't be read, etc).
+    ///
+    /// The recipient may still be reachable, but retrying the same request is
+    /// pointless.
+    #[error("Request was rejected: {0}")]
+    Rejected(#[source] RequestError),
+
+    /// A temporary failure: network error, flood control, server error or the
+    /// chat migration. Retrying may succeed.
+    ///
+    /// Note that this includes [`RequestError::MigrateToChatId`], in which
+    /// case the message should be sent to the new chat id.
+    #[error(transparent)]
+    Retriable(RequestError),
+}
+
+impl DeliveryError {
+    /// Returns the underlying error.
+    pub fn into_inner(self) -> RequestError {
+        match self {
+            Self::Permanent(err) | Self::Rejected(err) | Self::Retriable(err) => err,
+        }
+    }
+}
+
+impl From<RequestError> for DeliveryError {
+    fn from(err: RequestError) -> Self {
+        use ApiError::*;
+
+        match err {
+            RequestError::Api(
+                BotBlocked
+                | BotKicked
+                | BotKickedFromSupergroup
+                | UserDeactivated
+                | ChatNotFound
+                | GroupDeactivated
+                | CantInitiateConversation
+                | CantTalkWithBots,
+            ) => Self::Permanent(err),
+            RequestError::Network(_)
+            | RequestError::RetryAfter(_)
+            | RequestError::MigrateToChatId(_)
+            // Proxies respond to server errors with HTML
+            | RequestError::InvalidJson { .. } => Self::Retriable(err),
+            RequestError::Api(Unknown(ref description)) if is_server_error(description) => {
+                Self::Retriable(err)
+            }
+            err => Self::Rejected(err),
+        }
+    }
+}
+
+/// Payloads that can be used as a [`broadcast`] template.
+///
+/// This trait is implemented for payloads of all methods that send a message
+/// to a chat identified by a [`Recipient`].
+pub trait BroadcastPayload<B: Requester + ?Sized>: Payload + Clone {
+    /// Type of the request built by `B` for this payload.
+    type Request: Request<Payload = Self, Err = B::Err>;
+
+    /// Sets the chat to which the message will be sent.
+    fn set_recipient(&mut self, recipient: Recipient);
+
+    /// Builds a request with this payload.
+    fn request(self, bot: &B) -> Self::Request;
+}
+
+macro_rules! impl_broadcast_payload {
+    ($($P:ident => $m:ident($($field:ident),*);)*) => {
+        $(
+            impl<B: Requester + ?Sized> BroadcastPayload<B> for $P {
+                type Request = B::$P;
+
+                fn set_recipient(&mut self, recipient: Recipient) {
+                    self.chat_id = recipient;
+                }
+
+                #[allow(clippy::clone_on_copy)]
+                fn request(self, bot: &B) -> Self::Request {
+                    let mut request = bot.$m(self.chat_id.clone() $(, self.$field.clone())*);
+                    *request.payload_mut() = self;
+                    request
+                }
+            }
+        )*
+    };
+}
+
+impl_broadcast_payload! {
+    SendMessage => send_message(text);
+    ForwardMessage => forward_message(from_chat_id, message_id);
+    CopyMessage => copy_message(from_chat_id, message_id);
+    SendPhoto => send_photo(photo);
+    SendAudio => send_audio(audio);
+    SendDocument => send_document(document);
+    SendVideo => send_video(video);
+    SendAnimation => send_animation(animation);
+    SendVoice => send_voice(voice);
+    SendVideoNote => send_video_note(video_note);
+    SendMediaGroup => send_media_group(media);
+    SendLocation => send_location(latitude, longitude);
+    SendVenue => send_venue(latitude, longitude, title, address);
+    SendContact => send_contact(phone_number, first_name);
+    SendPoll => send_poll(question, options);
+    SendDice => send_dice();
+    SendSticker => send_sticker(sticker);
+    SendInvoice => send_invoice(title, description, payload, provider_token, currency, prices);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{
+        errors::{ApiError, RequestError},
+        payloads::SendMessage,
+        requests::{broadcast, DeliveryError},
+        types::{ChatId, Recipient},
+        Bot,
+    };
+
+    #[test]
+    #[should_panic = "`concurrency` must be greater than 0"]
+    fn zero_concurrency() {
+        let bot = Bot::new("TOKEN");
+        let template = SendMessage::new(ChatId(0), "Hi!");
+        let _ = broadcast(&bot, template, futures::stream::empty::<Recipient>(), 0);
+    }
+
+    #[test]
+    fn classify() {
+        let err = DeliveryError::from(RequestError::Api(ApiError::BotBlocked));
+        assert!(matches!(err, DeliveryError::Permanent(_)));
+
+        let err = DeliveryError::from(RequestError::Api(ApiError::ChatNotFound));
+        assert!(matches!(err, DeliveryError::Permanent(_)));
+
+        let err = DeliveryError::from(RequestError::Api(ApiError::MessageIsTooLong));
+        assert!(matches!(err, DeliveryError::Rejected(_)));
+
+        let err = DeliveryError::from(RequestError::Api(ApiError::Unknown(
+            "Some new error".to_owned(),
+        )));
+        assert!(matches!(err, DeliveryError::Rejected(_)));
+
+        let err = DeliveryError::from(RequestError::Api(ApiError::Unknown(
+            "Internal Server Error: restart".to_owned(),
+        )));
+        assert!(matches!(err, DeliveryError::Retriable(_)));
+
+        let err = DeliveryError::from(RequestError::RetryAfter(Duration::from_secs(1)));
+        assert!(matches!(err, DeliveryError::Retriable(_)));
+
+        let err = DeliveryError::from(RequestError::MigrateToChatId(1));
+        assert!(matches!(err, DeliveryError::Retriable(_)));
+    }
+}
@@ -0,0 +1,391 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    payloads::{
+        DeleteMyCommands, SetChatMenuButton, SetMyCommands, SetMyDefaultAdministratorRights,
+    },
+    requests::{HasPayload, Request, Requester},
+    types::{BotCommand, BotCommandScope, ChatAdministratorRights, MenuButton},
+};
+
+/// Desired state of bot's commands, default menu button and default
+/// administrator rights.
+///
+/// A profile can be (de)serialized, so it can be stored in version control,
+/// and is brought into effect by [`BotProfile::sync`], which only sends
+/// requests for settings which differ from the current ones.
+///
+/// Only the settings present in the profile are managed: commands are only
+/// compared for the listed scopes and languages (telegram has no method to
+/// list all of them), `None` menu button and rights are left as is. To delete
+/// commands of a scope, list it with no commands.
+///
+/// ## Examples
+///
+/// ```no_run
+/// use teloxide_core::{requests::BotProfile, Bot};
+///
+/// # async {
+/// let profile: BotProfile = serde_json::from_str(
+///     r#"{
+///         "commands": [
+///             {
+///                 "scope": { "type": "default" },
+///                 "commands": [{ "command": "start", "description": "Start" }]
+///             },
+///             {
+///                 "scope": { "type": "all_private_chats" },
+///                 "language_code": "de",
+///                 "commands": []
+///             }
+///         ],
+///         "menu_button": { "type": "commands" }
+///     }"#,
+/// )?;
+///
+/// let bot = Bot::new("TOKEN");
+///
+/// // Only report what would be changed
+/// for change in profile.diff(&bot).await? {
+///     println!("{}", change);
+/// }
+///
+/// profile.sync(&bot).await?;
+/// # Ok::<_, Box<dyn std::error::Error>>(()) };
+/// ```
+#[serde_with_macros::skip_serializing_none]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BotProfile {
+    /// Commands for different scopes and languages.
+    #[serde(default)]
+    pub commands: Vec<ScopedCommands>,
+
+    /// Default menu button, i.e. the menu button of private chats for which
+    /// no specific menu button is set.
+    pub menu_button: Option<MenuButton>,
+
+    /// Default administrator rights requested by the bot when it's added to
+    /// groups and supergroups.
+    pub group_administrator_rights: Option<ChatAdministratorRights>,
+
+    /// Default administrator rights requested by the bot when it's added to
+    /// channels.
+    pub channel_administrator_rights: Option<ChatAdministratorRights>,
+}
+
+/// Commands for a scope and a language, a part of [`BotProfile`].
+#[serde_with_macros::skip_serializing_none]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScopedCommands {
+    /// Scope of users for which the commands are relevant.
+    pub scope: BotCommandScope,
+
+    /// A two-letter ISO 639-1 language code of users for which the commands
+    /// are relevant, `None` for all users of the scope, for whose language
+    /// there are no dedicated commands.
+    pub language_code: Option<String>,
+
+    /// The commands, the commands of the scope are deleted if this is empty.
+    pub commands: Vec<BotCommand>,
+}
+
+/// A change which needs to be made to bring a bot into the state described by
+/// a [`BotProfile`].
+///
+/// `Display` implementation of this type describes the change in a
+/// human-readable form, which is useful for dry-run reports.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProfileChange {
+    SetCommands(SetMyCommands),
+    DeleteCommands(DeleteMyCommands),
+    SetMenuButton(SetChatMenuButton),
+    SetAdministratorRights(SetMyDefaultAdministratorRights),
+}
+
+impl BotProfile {
+    /// Returns changes which need to be made to bring the bot into the state
+    /// described by this profile, without making them.
+    ///
+    /// This only sends `get*` requests.
+    pub async fn diff<B>(&self, bot: &B) -> Result<Vec<ProfileChange>, B::Err>
+    where
+        B: Requester + ?Sized,
+    {
+        let mut changes = Vec::new();
+
+        for scoped in &self.commands {
+            let mut request = bot.get_my_commands();
+            request.payload_mut().scope = Some(scoped.scope.clone());
+            request.payload_mut().language_code = scoped.language_code.clone();
+
+            if request.send().await? == scoped.commands {
+                continue;
+            }
+
+            changes.push(if scoped.commands.is_empty() {
+                ProfileChange::DeleteCommands(DeleteMyCommands {
+                    scope: Some(scoped.scope.clone()),
+                    language_code: scoped.language_code.clone(),
+                })
+            } else {
+                ProfileChange::SetCommands(SetMyCommands {
+                    commands: scoped.commands.clone(),
+                    scope: Some(scoped.scope.clone()),
+                    language_code: scoped.language_code.clone(),
+                })
+            });
+        }
+
+        if let Some(menu_button) = &self.menu_button {
+            if bot.get_chat_menu_button().send().await? != *menu_button {
+                changes.push(ProfileChange::SetMenuButton(SetChatMenuButton {
+                    chat_id: None,
+                    menu_button: Some(menu_button.clone()),
+                }));
+            }
+        }
+
+        let rights = [
+            (false, &self.group_administrator_rights),
+            (true, &self.channel_administrator_rights),
+        ];
+        for (for_channels, rights) in rights {
+            if let Some(rights) = rights {
+                let mut request = bot.get_my_default_administrator_rights();
+                request.payload_mut().for_channels = Some(for_channels);
+
+                if request.send().await? != *rights {
+                    changes.push(ProfileChange::SetAdministratorRights(
+                        SetMyDefaultAdministratorRights {
+                            rights: Some(rights.clone()),
+                            for_channels: Some(for_channels),
+                        },
+                    ));
+                }
+            }
+        }
+
+        Ok(changes)
+    }
+
+    /// Brings the bot into the state described by this profile, returns the
+    /// changes which were made.
+    ///
+    /// Changes are computed by [`BotProfile::diff`] and applied in order,
+    /// stopping at the first error.
+    pub async fn sync<B>(&self, bot: &B) -> Result<Vec<ProfileChange>, B::Err>
+    where
+        B: Requester + ?Sized,
+    {
+        let changes = self.diff(bot).await?;
+        for change in &changes {
+            change.apply(bot).await?;
+        }
+
+        Ok(changes)
+    }
+}
+
+impl ProfileChange {
+    /// Makes the change.
+    pub async fn apply<B>(&self, bot: &B) -> Result<(), B::Err>
+    where
+        B: Requester + ?Sized,
+    {
+        match self {
+            Self::SetCommands(payload) => {
+                let mut request = bot.set_my_commands(Vec::new());
+                *request.payload_mut() = payload.clone();
+                request.send().await?;
+            }
+            Self::DeleteCommands(payload) => {
+                let mut request = bot.delete_my_commands();
+                *request.payload_mut() = payload.clone();
+                request.send().await?;
+            }
+            Self::SetMenuButton(payload) => {
+                let mut request = bot.set_chat_menu_button();
+                *request.payload_mut() = payload.clone();
+                request.send().await?;
+            }
+            Self::SetAdministratorRights(payload) => {
+                let mut request = bot.set_my_default_administrator_rights();
+                *request.payload_mut() = payload.clone();
+                request.send().await?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for ProfileChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SetCommands(payload) => {
+                f.write_str("set commands for ")?;
+                write_target(f, &payload.scope, &payload.language_code)?;
+                f.write_str(":")?;
+                for command in &payload.commands {
+                    write!(f, " /{}", command.command)?;
+                }
+                Ok(())
+            }
+            Self::DeleteCommands(payload) => {
+                f.write_str("delete commands for ")?;
+                write_target(f, &payload.scope, &payload.language_code)
+            }
+            Self::SetMenuButton(payload) => {
+                write!(f, "set default menu button to {:?}", payload.menu_button)
+            }
+            Self::SetAdministratorRights(payload) => {
+                let chats = match payload.for_channels {
+                    Some(true) => "channels",
+                    _ => "groups",
+                };
+                write!(
+                    f,
+                    "set default administrator rights for {} to {:?}",
+                    chats, payload.rights
+                )
+            }
+        }
+    }
+}
+
+/// Writes scope and language of commands.
+fn write_target(
+    f: &mut fmt::Formatter<'_>,
+    scope: &Option<BotCommandScope>,
+    language_code: &Option<String>,
+) -> fmt::Result {
+    match scope {
+        Some(scope) => write!(f, "{:?}", scope)?,
+        None => f.write_str("Default")?,
+    }
+
+    match language_code {
+        Some(language_code) => write!(f, " ({})", language_code),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        payloads::{DeleteMyCommands, SetMyCommands},
+        types::{BotCommand, BotCommandScope},
+    };
+
+    #[cfg(feature = "dry_run")]
+    use crate::{
+        payloads::{GetChatMenuButton, GetMyCommands, GetMyDefaultAdministratorRights},
+        requests::RequesterExt,
+        Bot,
+    };
+
+    use super::{BotProfile, ProfileChange, ScopedCommands};
+
+    #[test]
+    fn serde_and_display() {
+        let profile: BotProfile = serde_json::from_str(
+            r#"{
+                "commands": [{
+                    "scope": { "type": "all_group_chats" },
+                    "language_code": "en",
+                    "commands": [{ "command": "ban", "description": "Ban" }]
+                }]
+            }"#,
+        )
+        .unwrap();
+
+        let commands = ScopedCommands {
+            scope: BotCommandScope::AllGroupChats,
+            language_code: Some("en".to_owned()),
+            commands: vec![BotCommand::new("ban", "Ban")],
+        };
+        assert_eq!(
+            profile,
+            BotProfile {
+                commands: vec![commands],
+                ..BotProfile::default()
+            }
+        );
+        assert_eq!(
+            serde_json::to_string(&profile).unwrap(),
+            r#"{"commands":[{"scope":{"type":"all_group_chats"},"language_code":"en","commands":[{"command":"ban","description":"Ban"}]}]}"#
+        );
+
+        let change = ProfileChange::SetCommands(SetMyCommands {
+            commands: vec![
+                BotCommand::new("start", "Start"),
+                BotCommand::new("help", "Help"),
+            ],
+            scope: Some(BotCommandScope::AllPrivateChats),
+            language_code: Some("de".to_owned()),
+        });
+        assert_eq!(
+            change.to_string(),
+            "set commands for AllPrivateChats (de): /start /help"
+        );
+
+        let change = ProfileChange::DeleteCommands(DeleteMyCommands::new());
+        assert_eq!(change.to_string(), "delete commands for Default");
+    }
+
+    #[cfg(feature = "dry_run")]
+    #[tokio::test]
+    async fn diff() {
+        let bot = Bot::new("TOKEN")
+            .dry_run()
+            .with_output::<GetMyCommands, _>(|payload| match &payload.scope {
+                Some(BotCommandScope::Default) => vec![BotCommand::new("start", "Start")],
+                Some(BotCommandScope::AllPrivateChats) => vec![BotCommand::new("help", "Help")],
+                _ => Vec::new(),
+            })
+            .with_output::<GetChatMenuButton, _>(|_| unreachable!("menu button is not managed"))
+            .with_output::<GetMyDefaultAdministratorRights, _>(|_| {
+                unreachable!("administrator rights are not managed")
+            });
+
+        let scoped = |scope, commands| ScopedCommands {
+            scope,
+            language_code: None,
+            commands,
+        };
+        let profile = BotProfile {
+            commands: vec![
+                // Unchanged
+                scoped(
+                    BotCommandScope::Default,
+                    vec![BotCommand::new("start", "Start")],
+                ),
+                // Already empty
+                scoped(BotCommandScope::AllChatAdministrators, Vec::new()),
+                scoped(BotCommandScope::AllPrivateChats, Vec::new()),
+                scoped(
+                    BotCommandScope::AllGroupChats,
+                    vec![BotCommand::new("ban", "Ban")],
+                ),
+            ],
+            ..BotProfile::default()
+        };
+
+        assert_eq!(
+            profile.diff(&bot).await.unwrap(),
+            [
+                ProfileChange::DeleteCommands(DeleteMyCommands {
+                    scope: Some(BotCommandScope::AllPrivateChats),
+                    language_code: None,
+                }),
+                ProfileChange::SetCommands(SetMyCommands {
+                    commands: vec![BotCommand::new("ban", "Ban")],
+                    scope: Some(BotCommandScope::AllGroupChats),
+                    language_code: None,
+                }),
+            ]
+        );
+    }
+}
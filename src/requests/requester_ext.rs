@@ -1,11 +1,33 @@
-use crate::{adaptors::DefaultParseMode, requests::Requester, types::ParseMode};
+use crate::{
+    adaptors::{DefaultParseMode, DefaultValues, Defaults},
+    requests::Requester,
+    types::ParseMode,
+};
 
 #[cfg(feature = "cache_me")]
 use crate::adaptors::CacheMe;
 
+#[cfg(feature = "cache")]
+use crate::adaptors::{cache::Ttls, Cache};
+
 #[cfg(feature = "auto_send")]
 use crate::adaptors::AutoSend;
 
+#[cfg(feature = "auto_migrate")]
+use crate::{adaptors::AutoMigrate, types::ChatId};
+
+#[cfg(feature = "dry_run")]
+use crate::adaptors::DryRun;
+
+#[cfg(feature = "circuit_breaker")]
+use crate::adaptors::{circuit_breaker, CircuitBreaker};
+
+#[cfg(feature = "coalesce_edits")]
+use crate::adaptors::CoalesceEdits;
+
+#[cfg(feature = "ignore_not_modified")]
+use crate::adaptors::IgnoreNotModified;
+
 #[cfg(feature = "erased")]
 use crate::adaptors::ErasedRequester;
 
@@ -15,6 +37,9 @@ use crate::adaptors::trace::{Settings, Trace};
 #[cfg(feature = "throttle")]
 use crate::adaptors::throttle::{Limits, Throttle};
 
+#[cfg(feature = "metrics")]
+use crate::adaptors::{metrics::Recorder, Metrics};
+
 /// Extensions methods for [`Requester`].
 pub trait RequesterExt: Requester {
     /// Add `get_me` caching ability, see [`CacheMe`] for more.
@@ -26,6 +51,53 @@ pub trait RequesterExt: Requester {
         CacheMe::new(self)
     }
 
+    /// Cache responses to read-only requests, see [`Cache`] for more.
+    #[cfg(feature = "cache")]
+    fn cache(self, ttls: Ttls) -> Cache<Self>
+    where
+        Self: Sized,
+    {
+        Cache::new(self, ttls)
+    }
+
+    /// Fabricate responses instead of sending requests, see [`DryRun`] for
+    /// more.
+    #[cfg(feature = "dry_run")]
+    fn dry_run(self) -> DryRun<Self>
+    where
+        Self: Sized,
+    {
+        DryRun::new(self)
+    }
+
+    /// Fail fast during telegram outages, see [`CircuitBreaker`] for more.
+    #[cfg(feature = "circuit_breaker")]
+    fn circuit_breaker(self, settings: circuit_breaker::Settings) -> CircuitBreaker<Self>
+    where
+        Self: Sized,
+    {
+        CircuitBreaker::new(self, settings)
+    }
+
+    /// Coalesce edits of the same message, see [`CoalesceEdits`] for more.
+    #[cfg(feature = "coalesce_edits")]
+    fn coalesce_edits(self, interval: std::time::Duration) -> CoalesceEdits<Self>
+    where
+        Self: Sized,
+    {
+        CoalesceEdits::new(self, interval)
+    }
+
+    /// Treat "message is not modified" errors of edits as success, see
+    /// [`IgnoreNotModified`] for more.
+    #[cfg(feature = "ignore_not_modified")]
+    fn ignore_not_modified(self) -> IgnoreNotModified<Self>
+    where
+        Self: Sized,
+    {
+        IgnoreNotModified::new(self)
+    }
+
     /// Send requests automatically, see [`AutoSend`] for more.
     #[cfg(feature = "auto_send")]
     fn auto_send(self) -> AutoSend<Self>
@@ -35,6 +107,16 @@ pub trait RequesterExt: Requester {
         AutoSend::new(self)
     }
 
+    /// Retry requests to migrated groups, see [`AutoMigrate`] for more.
+    #[cfg(feature = "auto_migrate")]
+    fn auto_migrate<F>(self, on_migrate: F) -> AutoMigrate<Self>
+    where
+        Self: Sized,
+        F: Fn(ChatId, ChatId) + Send + Sync + 'static,
+    {
+        AutoMigrate::new(self, on_migrate)
+    }
+
     /// Erase requester type.
     #[cfg(feature = "erased")]
     fn erase<'a>(self) -> ErasedRequester<'a, Self::Err>
@@ -54,6 +136,15 @@ pub trait RequesterExt: Requester {
         Trace::new(self, settings)
     }
 
+    /// Collect metrics of requests, see [`Metrics`] for more.
+    #[cfg(feature = "metrics")]
+    fn metrics(self, recorder: std::sync::Arc<dyn Recorder>) -> Metrics<Self>
+    where
+        Self: Sized,
+    {
+        Metrics::new(self, recorder)
+    }
+
     /// Add throttling ability, see [`Throttle`] for more.
     ///
     /// Note: this spawns the worker, just as [`Throttle::new_spawn`].
@@ -101,6 +192,15 @@ pub trait RequesterExt: Requester {
     {
         DefaultParseMode::new(self, parse_mode)
     }
+
+    /// Sets default values (`disable_notification`, `protect_content`,
+    /// `parse_mode`, etc) to all requests, see [`DefaultValues`] for more.
+    fn defaults(self, defaults: Defaults) -> DefaultValues<Self>
+    where
+        Self: Sized,
+    {
+        DefaultValues::new(self, defaults)
+    }
 }
 
 impl<T> RequesterExt for T
@@ -0,0 +1,652 @@
+use std::{
+    cmp,
+    collections::HashMap,
+    fmt::{self, Debug},
+    future::Future,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use futures::future::BoxFuture;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tokio::sync::{watch, Mutex as AsyncMutex};
+
+use crate::requests::Request;
+
+/// Sends requests at a specified time or after a delay.
+///
+/// Every scheduled request (or _job_) gets a [`JobHandle`] which allows to
+/// cancel or reschedule it. Errors of scheduled requests are logged.
+///
+/// Requests scheduled with [`Scheduler::schedule_at`] live only in memory. To
+/// make jobs survive restarts, create the scheduler with a [`JobStore`],
+/// [`register`] a handler for every kind of job and schedule jobs with
+/// [`Scheduler::schedule_job_at`]. Such jobs are described by serializable
+/// data, saved to the store and loaded from it with [`Scheduler::restore`]
+/// after a restart. Jobs whose time has passed while the bot was down are
+/// run immediately after they are restored.
+///
+/// Note that a job is removed from the store only after it's done, so a job
+/// which was running when the bot went down is run again after the restart.
+///
+/// The scheduler uses [`tokio::spawn`], so it must be used inside of a tokio
+/// runtime.
+///
+/// ## Examples
+///
+/// ```no_run
+/// use std::time::{Duration, SystemTime};
+///
+/// use serde::{Deserialize, Serialize};
+/// use teloxide_core::{prelude::*, requests::Scheduler, types::ChatId};
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Reminder {
+///     chat_id: ChatId,
+///     text: String,
+/// }
+///
+/// # async {
+/// # let store = teloxide_core::requests::NoStore;
+/// # let tomorrow_9am = SystemTime::now();
+/// let bot = Bot::new("TOKEN");
+/// let scheduler = Scheduler::with_store(store);
+///
+/// let b = bot.clone();
+/// scheduler.register("reminder", move |reminder: Reminder| {
+///     let request = b.send_message(reminder.chat_id, reminder.text);
+///     async move {
+///         if let Err(err) = request.send().await {
+///             log::error!("Couldn't send a reminder: {}", err);
+///         }
+///     }
+/// });
+/// // Schedule reminders which were not sent before the restart
+/// scheduler.restore().await;
+///
+/// // "send reminder at 9am"
+/// let reminder = Reminder {
+///     chat_id: ChatId(1),
+///     text: "Wake up!".to_owned(),
+/// };
+/// scheduler
+///     .schedule_job_at(tomorrow_9am, "reminder", &reminder)
+///     .await?;
+///
+/// // "delete this warning after 30s"
+/// let warning = bot.send_message(ChatId(2), "Don't do that").send().await?;
+/// let handle = scheduler.schedule_in(
+///     Duration::from_secs(30),
+///     bot.delete_message(warning.chat.id, warning.id),
+/// );
+///
+/// // ...or later
+/// handle.reschedule_in(Duration::from_secs(60)).await;
+/// # Ok::<_, Box<dyn std::error::Error>>(()) };
+/// ```
+///
+/// [`register`]: Scheduler::register
+#[derive(Clone)]
+pub struct Scheduler {
+    state: Arc<State>,
+}
+
+impl Scheduler {
+    /// Creates new scheduler which keeps all jobs in memory.
+    pub fn new() -> Self {
+        Self::with_boxed_store(None)
+    }
+
+    /// Creates new scheduler which saves jobs scheduled with
+    /// [`Scheduler::schedule_job_at`] to `store`.
+    pub fn with_store<S>(store: S) -> Self
+    where
+        S: JobStore + 'static,
+    {
+        Self::with_boxed_store(Some(Box::new(store)))
+    }
+
+    fn with_boxed_store(store: Option<Box<dyn JobStore>>) -> Self {
+        Self {
+            state: Arc::new(State {
+                store,
+                handlers: <_>::default(),
+                jobs: <_>::default(),
+            }),
+        }
+    }
+
+    /// Sends `request` at `at` (or as soon as possible, if `at` has passed).
+    ///
+    /// The job is not saved to the store.
+    pub fn schedule_at<R>(&self, at: SystemTime, request: R) -> JobHandle
+    where
+        R: Request + Send + 'static,
+        R::Send: Send,
+        R::Err: Debug,
+    {
+        let id = self.state.next_id();
+        let job = async move {
+            if let Err(err) = request.send().await {
+                log::warn!("Scheduled job {} failed: {:?}", id.0, err);
+            }
+        };
+
+        self.state.spawn(id, at, None, <_>::default(), job)
+    }
+
+    /// Sends `request` after `delay`.
+    ///
+    /// The job is not saved to the store.
+    pub fn schedule_in<R>(&self, delay: Duration, request: R) -> JobHandle
+    where
+        R: Request + Send + 'static,
+        R::Send: Send,
+        R::Err: Debug,
+    {
+        self.schedule_at(SystemTime::now() + delay, request)
+    }
+
+    /// Registers `handler` which runs jobs of the given `kind`.
+    ///
+    /// Jobs which can't be deserialized as `J` are logged and dropped.
+    pub fn register<J, F, Fut>(&self, kind: impl Into<String>, handler: F)
+    where
+        J: DeserializeOwned,
+        F: Fn(J) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let handler: Handler = Arc::new(move |data| match serde_json::from_value(data) {
+            Ok(job) => Some(Box::pin(handler(job)) as BoxFuture<'static, ()>),
+            Err(err) => {
+                log::error!("Couldn't deserialize a scheduled job: {}", err);
+                None
+            }
+        });
+
+        self.state
+            .handlers
+            .lock()
+            .unwrap()
+            .insert(kind.into(), handler);
+    }
+
+    /// Runs the handler of `kind` with `job` at `at` (or as soon as possible,
+    /// if `at` has passed).
+    ///
+    /// The job is saved to the store (if any) and removed from it when it's
+    /// done.
+    ///
+    /// Fails if there is no handler registered for `kind` or if `job` can't be
+    /// serialized.
+    pub async fn schedule_job_at<J>(
+        &self,
+        at: SystemTime,
+        kind: impl Into<String>,
+        job: &J,
+    ) -> Result<JobHandle, ScheduleError>
+    where
+        J: Serialize,
+    {
+        let kind = kind.into();
+        if !self.state.handlers.lock().unwrap().contains_key(&kind) {
+            return Err(ScheduleError::UnknownKind(kind));
+        }
+
+        let job = StoredJob {
+            id: self.state.next_id(),
+            at,
+            kind,
+            data: serde_json::to_value(job)?,
+        };
+
+        if let Some(store) = &self.state.store {
+            store.save(job.clone()).await;
+        }
+
+        Ok(self.state.spawn_stored(job))
+    }
+
+    /// Runs the handler of `kind` with `job` after `delay`.
+    ///
+    /// See [`Scheduler::schedule_job_at`] for more.
+    pub async fn schedule_job_in<J>(
+        &self,
+        delay: Duration,
+        kind: impl Into<String>,
+        job: &J,
+    ) -> Result<JobHandle, ScheduleError>
+    where
+        J: Serialize,
+    {
+        self.schedule_job_at(SystemTime::now() + delay, kind, job)
+            .await
+    }
+
+    /// Schedules all jobs from the store, returns the number of scheduled
+    /// jobs.
+    ///
+    /// Jobs of kinds without registered handlers are logged and left in the
+    /// store, so handlers should be registered before calling this method.
+    pub async fn restore(&self) -> usize {
+        let store = match &self.state.store {
+            Some(store) => store,
+            None => return 0,
+        };
+
+        let mut restored = 0;
+        for job in store.load().await {
+            self.state.bump_id(job.id);
+
+            if !self.state.handlers.lock().unwrap().contains_key(&job.kind) {
+                log::error!(
+                    "There is no handler registered for jobs of kind `{}`, job {} is not restored",
+                    job.kind,
+                    job.id.0
+                );
+                continue;
+            }
+
+            if self.state.jobs.lock().unwrap().jobs.contains_key(&job.id) {
+                continue;
+            }
+
+            self.state.spawn_stored(job);
+            restored += 1;
+        }
+
+        restored
+    }
+
+    /// Returns a handle of the pending job with the given id.
+    pub fn job(&self, id: JobId) -> Option<JobHandle> {
+        self.state
+            .jobs
+            .lock()
+            .unwrap()
+            .jobs
+            .contains_key(&id)
+            .then(|| JobHandle {
+                id,
+                state: Arc::clone(&self.state),
+            })
+    }
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Debug for Scheduler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Scheduler").finish_non_exhaustive()
+    }
+}
+
+/// Handle of a job scheduled by a [`Scheduler`].
+///
+/// Dropping the handle doesn't cancel the job.
+#[derive(Clone)]
+pub struct JobHandle {
+    id: JobId,
+    state: Arc<State>,
+}
+
+impl JobHandle {
+    /// Returns id of the job.
+    pub fn id(&self) -> JobId {
+        self.id
+    }
+
+    /// Returns `true` if the job is neither started nor cancelled.
+    pub fn is_pending(&self) -> bool {
+        self.state.jobs.lock().unwrap().jobs.contains_key(&self.id)
+    }
+
+    /// Cancels the job, returns `false` if the job was already started or
+    /// cancelled.
+    pub async fn cancel(&self) -> bool {
+        let job = self.state.jobs.lock().unwrap().jobs.remove(&self.id);
+
+        match job {
+            Some(job) => {
+                if let (Some(store), Some(_)) = (&self.state.store, job.stored) {
+                    let _write = job.store_write.lock().await;
+                    store.remove(self.id).await;
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Changes the time of the job, returns `false` if the job was already
+    /// started or cancelled.
+    pub async fn reschedule_at(&self, at: SystemTime) -> bool {
+        let store_write = match self.state.jobs.lock().unwrap().jobs.get(&self.id) {
+            Some(job) => Arc::clone(&job.store_write),
+            None => return false,
+        };
+        // Held until the job is saved, so the job can't be removed from the
+        // store in between and then saved again
+        let _write = store_write.lock().await;
+
+        let stored = {
+            let mut jobs = self.state.jobs.lock().unwrap();
+            let job = match jobs.jobs.get_mut(&self.id) {
+                Some(job) => job,
+                None => return false,
+            };
+
+            // The job task may have just finished waiting, in which case it's
+            // too late anyway
+            let _ = job.at.send(at);
+            job.stored.as_mut().map(|stored| {
+                stored.at = at;
+                stored.clone()
+            })
+        };
+
+        if let (Some(store), Some(stored)) = (&self.state.store, stored) {
+            store.save(stored).await;
+        }
+
+        true
+    }
+
+    /// Changes the time of the job to `delay` from now, returns `false` if the
+    /// job was already started or cancelled.
+    pub async fn reschedule_in(&self, delay: Duration) -> bool {
+        self.reschedule_at(SystemTime::now() + delay).await
+    }
+}
+
+impl Debug for JobHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JobHandle").field("id", &self.id).finish()
+    }
+}
+
+/// Id of a job scheduled by a [`Scheduler`].
+///
+/// Ids are unique even across restarts (they are based on the current time).
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize
+)]
+#[serde(transparent)]
+pub struct JobId(pub u64);
+
+/// An error returned from [`Scheduler::schedule_job_at`].
+#[derive(Debug, thiserror::Error)]
+pub enum ScheduleError {
+    /// There is no handler registered for jobs of this kind, see
+    /// [`Scheduler::register`].
+    #[error("There is no handler registered for jobs of kind `{0}`")]
+    UnknownKind(String),
+
+    /// The job couldn't be serialized.
+    #[error("Couldn't serialize the job: {0}")]
+    Serialize(#[from] serde_json::Error),
+}
+
+/// Job saved to a [`JobStore`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StoredJob {
+    pub id: JobId,
+
+    /// Time at which the job should run.
+    pub at: SystemTime,
+
+    /// Kind of the job, see [`Scheduler::register`].
+    pub kind: String,
+
+    /// Serialized job data passed to the handler.
+    pub data: serde_json::Value,
+}
+
+/// Persistent storage of jobs used by [`Scheduler`].
+///
+/// Implementations should handle errors themselves (e.g. log them), the
+/// scheduler keeps working even if the store is unavailable.
+pub trait JobStore: Send + Sync {
+    /// Saves `job`, replacing a job with the same id, if any.
+    fn save(&self, job: StoredJob) -> BoxFuture<'_, ()>;
+
+    /// Removes the job with the given id.
+    fn remove(&self, id: JobId) -> BoxFuture<'_, ()>;
+
+    /// Returns all saved jobs.
+    fn load(&self) -> BoxFuture<'_, Vec<StoredJob>>;
+}
+
+/// [`JobStore`] which doesn't save anything.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoStore;
+
+impl JobStore for NoStore {
+    fn save(&self, _: StoredJob) -> BoxFuture<'_, ()> {
+        Box::pin(async {})
+    }
+
+    fn remove(&self, _: JobId) -> BoxFuture<'_, ()> {
+        Box::pin(async {})
+    }
+
+    fn load(&self) -> BoxFuture<'_, Vec<StoredJob>> {
+        Box::pin(async { Vec::new() })
+    }
+}
+
+type Handler = Arc<dyn Fn(serde_json::Value) -> Option<BoxFuture<'static, ()>> + Send + Sync>;
+
+/// State shared between [`Scheduler`], [`JobHandle`]s and job tasks.
+struct State {
+    store: Option<Box<dyn JobStore>>,
+    handlers: Mutex<HashMap<String, Handler>>,
+    jobs: Mutex<Jobs>,
+}
+
+#[derive(Default)]
+struct Jobs {
+    /// Pending jobs, dropping a job cancels it.
+    jobs: HashMap<JobId, Job>,
+    last_id: u64,
+}
+
+struct Job {
+    at: watch::Sender<SystemTime>,
+    stored: Option<StoredJob>,
+    /// Serializes writes of the job to the store.
+    store_write: Arc<AsyncMutex<()>>,
+}
+
+impl State {
+    fn next_id(&self) -> JobId {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos() as u64;
+
+        let mut jobs = self.jobs.lock().unwrap();
+        jobs.last_id = cmp::max(jobs.last_id + 1, now);
+        JobId(jobs.last_id)
+    }
+
+    /// Makes sure that ids of new jobs are greater than `id`.
+    fn bump_id(&self, id: JobId) {
+        let mut jobs = self.jobs.lock().unwrap();
+        jobs.last_id = cmp::max(jobs.last_id, id.0);
+    }
+
+    fn spawn_stored(self: &Arc<Self>, job: StoredJob) -> JobHandle {
+        let (id, at, stored) = (job.id, job.at, job.clone());
+        let store_write = Arc::new(AsyncMutex::new(()));
+        let state = Arc::clone(self);
+        let write = Arc::clone(&store_write);
+        let run = async move {
+            let StoredJob { kind, data, .. } = job;
+            let handler = state.handlers.lock().unwrap().get(&kind).cloned();
+            match handler.and_then(|handler| handler(data)) {
+                Some(fut) => fut.await,
+                None => log::error!("Couldn't run job {} of kind `{}`", id.0, kind),
+            }
+
+            if let Some(store) = &state.store {
+                let _write = write.lock().await;
+                store.remove(id).await;
+            }
+        };
+
+        self.spawn(id, at, Some(stored), store_write, run)
+    }
+
+    fn spawn<F>(
+        self: &Arc<Self>,
+        id: JobId,
+        at: SystemTime,
+        stored: Option<StoredJob>,
+        store_write: Arc<AsyncMutex<()>>,
+        job: F,
+    ) -> JobHandle
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let (tx, rx) = watch::channel(at);
+        let job_state = Job {
+            at: tx,
+            stored,
+            store_write,
+        };
+        self.jobs.lock().unwrap().jobs.insert(id, job_state);
+
+        let state = Arc::clone(self);
+        tokio::spawn(async move {
+            if !wait(rx).await {
+                return;
+            }
+
+            // Removing the job decides the race with `cancel`
+            let started = state.jobs.lock().unwrap().jobs.remove(&id).is_some();
+            if started {
+                job.await;
+            }
+        });
+
+        JobHandle {
+            id,
+            state: Arc::clone(self),
+        }
+    }
+}
+
+/// Waits until the time of the job, returns `false` if the job was cancelled.
+async fn wait(mut at: watch::Receiver<SystemTime>) -> bool {
+    loop {
+        let delay = at
+            .borrow()
+            .duration_since(SystemTime::now())
+            .unwrap_or_default();
+
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => return true,
+            changed = at.changed() => if changed.is_err() {
+                return false;
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{Arc, Mutex},
+        time::{Duration, SystemTime},
+    };
+
+    use futures::future::BoxFuture;
+
+    use super::{JobId, JobStore, ScheduleError, Scheduler, StoredJob};
+
+    #[derive(Clone, Default)]
+    struct Store(Arc<Mutex<Vec<StoredJob>>>);
+
+    impl JobStore for Store {
+        fn save(&self, job: StoredJob) -> BoxFuture<'_, ()> {
+            let mut jobs = self.0.lock().unwrap();
+            jobs.retain(|j| j.id != job.id);
+            jobs.push(job);
+            Box::pin(async {})
+        }
+
+        fn remove(&self, id: JobId) -> BoxFuture<'_, ()> {
+            self.0.lock().unwrap().retain(|j| j.id != id);
+            Box::pin(async {})
+        }
+
+        fn load(&self) -> BoxFuture<'_, Vec<StoredJob>> {
+            let jobs = self.0.lock().unwrap().clone();
+            Box::pin(async { jobs })
+        }
+    }
+
+    fn scheduler(store: &Store, done: &Arc<Mutex<Vec<u32>>>) -> Scheduler {
+        let scheduler = Scheduler::with_store(store.clone());
+        let done = Arc::clone(done);
+        scheduler.register("job", move |n: u32| {
+            done.lock().unwrap().push(n);
+            async {}
+        });
+        scheduler
+    }
+
+    #[tokio::test]
+    async fn cancel_reschedule_restore() {
+        tokio::time::pause();
+        let store = Store::default();
+        let done = Arc::new(Mutex::new(Vec::new()));
+        let ms = Duration::from_millis;
+
+        let s = scheduler(&store, &done);
+        assert!(matches!(
+            s.schedule_job_in(ms(10), "unknown", &0).await,
+            Err(ScheduleError::UnknownKind(kind)) if kind == "unknown"
+        ));
+
+        let a = s.schedule_job_in(ms(10), "job", &1).await.unwrap();
+        let b = s.schedule_job_in(ms(10), "job", &2).await.unwrap();
+        let c = s.schedule_job_in(ms(10), "job", &3).await.unwrap();
+        assert!(b.cancel().await);
+        assert!(c.reschedule_in(Duration::from_secs(60)).await);
+        assert_eq!(store.0.lock().unwrap().len(), 2);
+
+        tokio::time::sleep(ms(50)).await;
+        assert_eq!(*done.lock().unwrap(), [1]);
+        assert!(!a.is_pending() && !a.cancel().await);
+        assert!(c.is_pending());
+
+        // "Restart": the rescheduled job is in the store
+        let stored = store.0.lock().unwrap().clone();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].id, c.id());
+        assert!(stored[0].at > SystemTime::now() + Duration::from_secs(50));
+
+        store.0.lock().unwrap()[0].at = SystemTime::now();
+        let s = scheduler(&store, &done);
+        assert_eq!(s.restore().await, 1);
+        tokio::time::sleep(ms(50)).await;
+        assert_eq!(*done.lock().unwrap(), [1, 3]);
+        assert!(store.0.lock().unwrap().is_empty());
+        assert!(s.job(c.id()).is_none());
+    }
+}
@@ -58,6 +58,7 @@ pub use input_media::*;
 pub use input_message_content::*;
 pub use input_sticker::*;
 pub use invoice::*;
+pub use keyboard::KeyboardError;
 pub use keyboard_button::*;
 pub use keyboard_button_poll_type::*;
 pub use label_price::*;
@@ -144,6 +145,7 @@ mod input_media;
 mod input_message_content;
 mod input_sticker;
 mod invoice;
+mod keyboard;
 mod keyboard_button;
 mod keyboard_button_poll_type;
 mod label_price;
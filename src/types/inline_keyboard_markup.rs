@@ -1,6 +1,9 @@
 use serde::{Deserialize, Serialize};
 
-use crate::types::InlineKeyboardButton;
+use crate::types::{
+    keyboard::{grid, paginated, validate},
+    InlineKeyboardButton, InlineKeyboardButtonKind, KeyboardError,
+};
 
 /// This object represents an [inline keyboard] that appears right next to the
 /// message it belongs to.
@@ -73,6 +76,112 @@ impl InlineKeyboardMarkup {
         };
         self
     }
+
+    /// Maximum number of buttons in a row.
+    ///
+    /// Note that this limit is observed, it's not documented by the Bot API.
+    pub const MAX_ROW_LEN: usize = 8;
+
+    /// Maximum number of buttons in a keyboard.
+    ///
+    /// Note that this limit is observed, it's not documented by the Bot API.
+    pub const MAX_BUTTONS: usize = 100;
+
+    /// Maximum length of callback data, in bytes.
+    pub const MAX_CALLBACK_DATA_LEN: usize = 64;
+
+    /// Creates a keyboard laying out `buttons` in rows of `columns` buttons
+    /// (the last row may be shorter).
+    ///
+    /// ## Panics
+    ///
+    /// If `columns` is 0.
+    pub fn grid<I>(buttons: I, columns: usize) -> Self
+    where
+        I: IntoIterator<Item = InlineKeyboardButton>,
+    {
+        Self::new(grid(buttons, columns))
+    }
+
+    /// Creates a keyboard with the page number `page` (starting from 0) of a
+    /// long list of `buttons`, laid out like [`grid`] does.
+    ///
+    /// The last row contains `«` and `»` buttons which lead to the previous
+    /// and the next pages (if they exist), `callback_data` returns callback
+    /// data of these buttons given the number of the page they lead to.
+    ///
+    /// ## Panics
+    ///
+    /// If `columns` or `page_size` is 0.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use teloxide_core::types::{InlineKeyboardButton, InlineKeyboardMarkup};
+    ///
+    /// let items = (0..10).map(|i| InlineKeyboardButton::callback(i.to_string(), i.to_string()));
+    /// let markup = InlineKeyboardMarkup::paginated(items, 2, 4, 1, |page| format!("page:{}", page));
+    ///
+    /// let expected = InlineKeyboardMarkup::new([
+    ///     vec![
+    ///         InlineKeyboardButton::callback("4", "4"),
+    ///         InlineKeyboardButton::callback("5", "5"),
+    ///     ],
+    ///     vec![
+    ///         InlineKeyboardButton::callback("6", "6"),
+    ///         InlineKeyboardButton::callback("7", "7"),
+    ///     ],
+    ///     vec![
+    ///         InlineKeyboardButton::callback("«", "page:0"),
+    ///         InlineKeyboardButton::callback("»", "page:2"),
+    ///     ],
+    /// ]);
+    /// assert_eq!(markup, expected);
+    /// ```
+    ///
+    /// [`grid`]: InlineKeyboardMarkup::grid
+    pub fn paginated<I, F>(
+        buttons: I,
+        columns: usize,
+        page_size: usize,
+        page: usize,
+        mut callback_data: F,
+    ) -> Self
+    where
+        I: IntoIterator<Item = InlineKeyboardButton>,
+        F: FnMut(usize) -> String,
+    {
+        Self::new(paginated(buttons, columns, page_size, page, |to| {
+            let text = if to < page { "«" } else { "»" };
+            InlineKeyboardButton::callback(text, callback_data(to))
+        }))
+    }
+
+    /// Checks that the keyboard fits into telegram limits: [`MAX_ROW_LEN`],
+    /// [`MAX_BUTTONS`] and [`MAX_CALLBACK_DATA_LEN`].
+    ///
+    /// [`MAX_ROW_LEN`]: InlineKeyboardMarkup::MAX_ROW_LEN
+    /// [`MAX_BUTTONS`]: InlineKeyboardMarkup::MAX_BUTTONS
+    /// [`MAX_CALLBACK_DATA_LEN`]: InlineKeyboardMarkup::MAX_CALLBACK_DATA_LEN
+    pub fn validate(&self) -> Result<(), KeyboardError> {
+        validate(&self.inline_keyboard, Self::MAX_ROW_LEN, Self::MAX_BUTTONS)?;
+
+        for (row, buttons) in self.inline_keyboard.iter().enumerate() {
+            for (column, button) in buttons.iter().enumerate() {
+                if let InlineKeyboardButtonKind::CallbackData(data) = &button.kind {
+                    if data.is_empty() || data.len() > Self::MAX_CALLBACK_DATA_LEN {
+                        return Err(KeyboardError::InvalidCallbackData {
+                            row,
+                            column,
+                            len: data.len(),
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -129,4 +238,43 @@ mod tests {
 
         assert_eq!(markup, expected);
     }
+
+    #[test]
+    fn navigation_and_callback_data() {
+        let button = |n: u32| InlineKeyboardButton::callback(n.to_string(), n.to_string());
+
+        let markup = InlineKeyboardMarkup::paginated((0..5).map(button), 2, 2, 1, |page| {
+            format!("page:{}", page)
+        });
+        assert_eq!(
+            markup.inline_keyboard.last().unwrap(),
+            &[
+                InlineKeyboardButton::callback("«", "page:0"),
+                InlineKeyboardButton::callback("»", "page:2")
+            ]
+        );
+
+        let with_data = |data: String| {
+            InlineKeyboardMarkup::default()
+                .append_row([button(0), InlineKeyboardButton::callback("x", data)])
+                .validate()
+        };
+        assert_eq!(with_data("x".repeat(64)), Ok(()));
+        assert_eq!(
+            with_data("x".repeat(65)),
+            Err(KeyboardError::InvalidCallbackData {
+                row: 0,
+                column: 1,
+                len: 65
+            })
+        );
+        assert_eq!(
+            with_data(String::new()),
+            Err(KeyboardError::InvalidCallbackData {
+                row: 0,
+                column: 1,
+                len: 0
+            })
+        );
+    }
 }
@@ -0,0 +1,148 @@
+use std::mem;
+
+/// An error returned by [`InlineKeyboardMarkup::validate`] and
+/// [`KeyboardMarkup::validate`] for keyboards which don't fit into telegram
+/// limits.
+///
+/// [`InlineKeyboardMarkup::validate`]: crate::types::InlineKeyboardMarkup::validate
+/// [`KeyboardMarkup::validate`]: crate::types::KeyboardMarkup::validate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, thiserror::Error)]
+#[non_exhaustive]
+pub enum KeyboardError {
+    /// A row has too many buttons.
+    #[error("row {row} has {len} buttons, at most {max} are allowed")]
+    RowTooLong { row: usize, len: usize, max: usize },
+
+    /// The keyboard has too many buttons.
+    #[error("keyboard has {len} buttons, at most {max} are allowed")]
+    TooManyButtons { len: usize, max: usize },
+
+    /// Callback data of a button is empty or too long.
+    #[error(
+        "callback data of button {column} in row {row} is {len} bytes long, 1-64 bytes are allowed"
+    )]
+    InvalidCallbackData {
+        row: usize,
+        column: usize,
+        len: usize,
+    },
+}
+
+/// Lays out `buttons` in rows of `columns` buttons.
+pub(crate) fn grid<T>(buttons: impl IntoIterator<Item = T>, columns: usize) -> Vec<Vec<T>> {
+    assert!(columns > 0, "`columns` must be greater than 0");
+
+    let mut rows = Vec::new();
+    let mut row = Vec::with_capacity(columns);
+    for button in buttons {
+        row.push(button);
+        if row.len() == columns {
+            rows.push(mem::replace(&mut row, Vec::with_capacity(columns)));
+        }
+    }
+
+    if !row.is_empty() {
+        rows.push(row);
+    }
+
+    rows
+}
+
+/// Lays out the page number `page` of `buttons`, with a row of navigation
+/// buttons created by `nav` from the number of the page they lead to.
+pub(crate) fn paginated<T>(
+    buttons: impl IntoIterator<Item = T>,
+    columns: usize,
+    page_size: usize,
+    page: usize,
+    mut nav: impl FnMut(usize) -> T,
+) -> Vec<Vec<T>> {
+    assert!(page_size > 0, "`page_size` must be greater than 0");
+
+    let mut buttons = buttons.into_iter().skip(page.saturating_mul(page_size));
+    let mut rows = grid(buttons.by_ref().take(page_size), columns);
+
+    let mut nav_row = Vec::new();
+    if page > 0 {
+        nav_row.push(nav(page - 1));
+    }
+    if buttons.next().is_some() {
+        nav_row.push(nav(page + 1));
+    }
+    if !nav_row.is_empty() {
+        rows.push(nav_row);
+    }
+
+    rows
+}
+
+/// Checks sizes of rows and the number of buttons.
+pub(crate) fn validate<T>(
+    rows: &[Vec<T>],
+    max_row_len: usize,
+    max_buttons: usize,
+) -> Result<(), KeyboardError> {
+    if let Some((row, buttons)) = rows
+        .iter()
+        .enumerate()
+        .find(|(_, buttons)| buttons.len() > max_row_len)
+    {
+        return Err(KeyboardError::RowTooLong {
+            row,
+            len: buttons.len(),
+            max: max_row_len,
+        });
+    }
+
+    let len = rows.iter().map(Vec::len).sum();
+    if len > max_buttons {
+        return Err(KeyboardError::TooManyButtons {
+            len,
+            max: max_buttons,
+        });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layout() {
+        assert_eq!(grid(0..5, 2), [vec![0, 1], vec![2, 3], vec![4]]);
+        assert_eq!(grid(0..0, 3), Vec::<Vec<i32>>::new());
+
+        // Both navigation buttons are on the same row, "previous" first
+        assert_eq!(
+            paginated(0..7, 2, 2, 1, |to| 100 + to),
+            [vec![2, 3], vec![100, 102]]
+        );
+        // Pages past the end are empty
+        assert_eq!(paginated(0..2, 2, 2, 3, |to| 100 + to), [vec![102]]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn zero_columns() {
+        grid(0..1, 0);
+    }
+
+    #[test]
+    fn limits() {
+        assert_eq!(validate(&[vec![0; 2], vec![0; 2]], 2, 4), Ok(()));
+        assert_eq!(
+            validate(&[vec![0; 2], vec![0; 3]], 2, 10),
+            Err(KeyboardError::RowTooLong {
+                row: 1,
+                len: 3,
+                max: 2
+            })
+        );
+        assert_eq!(
+            validate(&[vec![0; 2], vec![0; 2], vec![0]], 2, 4),
+            Err(KeyboardError::TooManyButtons { len: 5, max: 4 })
+        );
+    }
+}
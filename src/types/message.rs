@@ -5,7 +5,7 @@ use serde::{Deserialize, Serialize};
 use url::Url;
 
 use crate::types::{
-    Animation, Audio, BareChatId, Chat, ChatId, Contact, Dice, Document, Game,
+    Animation, Audio, BareChatId, Chat, ChatId, Contact, Dice, Document, EntityOffsetError, Game,
     InlineKeyboardMarkup, Invoice, Location, MessageAutoDeleteTimerChanged, MessageEntity,
     MessageEntityRef, PassportData, PhotoSize, Poll, ProximityAlertTriggered, Sticker,
     SuccessfulPayment, True, User, Venue, Video, VideoChatEnded, VideoChatParticipantsInvited,
@@ -1230,12 +1230,13 @@ impl Message {
     ///
     /// This function returns `Some(entities)` for **text messages** and
     /// `None` for all other kinds of messages (including photos with
-    /// captions).
+    /// captions). `Some(Err(_))` is returned if the entities have invalid
+    /// offsets, which is only possible if they were modified.
     ///
     /// See also: [`parse_caption_entities`].
     ///
     /// [`parse_caption_entities`]: Message::parse_caption_entities
-    pub fn parse_entities(&self) -> Option<Vec<MessageEntityRef<'_>>> {
+    pub fn parse_entities(&self) -> Option<Result<Vec<MessageEntityRef<'_>>, EntityOffsetError>> {
         self.text()
             .zip(self.entities())
             .map(|(t, e)| MessageEntityRef::parse(t, e))
@@ -1245,11 +1246,15 @@ impl Message {
     ///
     /// This function returns `Some(entities)` for **media messages** and
     /// `None` for all other kinds of messages (including text messages).
+    /// `Some(Err(_))` is returned if the entities have invalid offsets, which
+    /// is only possible if they were modified.
     ///
     /// See also: [`parse_entities`].
     ///
     /// [`parse_entities`]: Message::parse_entities
-    pub fn parse_caption_entities(&self) -> Option<Vec<MessageEntityRef<'_>>> {
+    pub fn parse_caption_entities(
+        &self,
+    ) -> Option<Result<Vec<MessageEntityRef<'_>>, EntityOffsetError>> {
         self.caption()
             .zip(self.caption_entities())
             .map(|(t, e)| MessageEntityRef::parse(t, e))
@@ -1697,7 +1702,7 @@ mod tests {
         let entities = message.parse_caption_entities();
         assert!(entities.is_some());
 
-        let entities = entities.unwrap();
+        let entities = entities.unwrap().unwrap();
         assert!(!entities.is_empty());
         assert_eq!(entities[0].kind().clone(), MessageEntityKind::Url);
     }
@@ -206,13 +206,19 @@ impl<'a> MessageEntityRef<'a> {
     }
 
     /// Parses telegram [`MessageEntity`]s converting offsets to UTF-8.
-    pub fn parse(text: &'a str, entities: &'a [MessageEntity]) -> Vec<Self> {
+    ///
+    /// Returns an error if an entity is out of bounds of `text` or if its
+    /// offset points into the middle of a character.
+    pub fn parse(
+        text: &'a str,
+        entities: &'a [MessageEntity],
+    ) -> Result<Vec<Self>, EntityOffsetError> {
         // This creates entities with **wrong** offsets (UTF-16) that we later patch.
         let mut entities: Vec<_> = entities
             .iter()
             .map(|e| Self {
                 message: text,
-                range: e.offset..e.offset + e.length,
+                range: e.offset..e.offset.saturating_add(e.length),
                 kind: &e.kind,
             })
             .collect();
@@ -234,36 +240,47 @@ impl<'a> MessageEntityRef<'a> {
         // removed more easily
         offsets.sort_unstable_by_key(|&&mut offset| cmp::Reverse(offset));
 
-        let _ = text
-            .chars()
-            .chain(['\0']) // this is needed to process offset pointing at the end of the string
-            .try_fold((0, 0), |(len_utf8, len_utf16), c| {
-                // Stop if there are no more offsets to patch
-                if offsets.is_empty() {
-                    return None;
-                }
-
-                // Patch all offsets that can be patched
-                while offsets
-                    .last()
-                    .map(|&&mut offset| offset <= len_utf16)
-                    .unwrap_or(false)
-                {
-                    let offset = offsets.pop().unwrap();
-                    assert_eq!(*offset, len_utf16, "Invalid utf-16 offset");
-
-                    // Patch the offset to be UTF-8
-                    *offset = len_utf8;
-                }
-
-                // Update "running" length
-                Some((len_utf8 + c.len_utf8(), len_utf16 + c.len_utf16()))
-            });
-
-        entities
+        let mut chars = text.chars();
+        let (mut len_utf8, mut len_utf16) = (0, 0);
+        while let Some(offset) = offsets.pop() {
+            // Advance "running" length up to the offset
+            while len_utf16 < *offset {
+                let c = chars.next().ok_or(EntityOffsetError::OutOfBounds {
+                    offset: *offset,
+                    len: len_utf16,
+                })?;
+
+                len_utf8 += c.len_utf8();
+                len_utf16 += c.len_utf16();
+            }
+
+            if len_utf16 != *offset {
+                return Err(EntityOffsetError::InsideCharacter { offset: *offset });
+            }
+
+            // Patch the offset to be UTF-8
+            *offset = len_utf8;
+        }
+
+        Ok(entities)
     }
 }
 
+/// An error returned by [`MessageEntityRef::parse`] for entities with invalid
+/// offsets.
+///
+/// All offsets are in UTF-16 code units.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, thiserror::Error)]
+pub enum EntityOffsetError {
+    /// An entity starts or ends after the end of the text.
+    #[error("offset {offset} is out of bounds of text with length {len}")]
+    OutOfBounds { offset: usize, len: usize },
+
+    /// An entity starts or ends in the middle of a surrogate pair.
+    #[error("offset {offset} points into the middle of a character")]
+    InsideCharacter { offset: usize },
+}
+
 #[serde_with_macros::skip_serializing_none]
 #[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -373,7 +390,8 @@ mod tests {
                     length: 1,
                 },
             ],
-        );
+        )
+        .unwrap();
 
         assert_matches!(
             parsed,
@@ -396,7 +414,8 @@ mod tests {
                 offset: 5,
                 length: 3,
             }],
-        );
+        )
+        .unwrap();
 
         assert_matches!(
             parsed,
@@ -426,7 +445,8 @@ mod tests {
                     length: 1,
                 },
             ],
-        );
+        )
+        .unwrap();
 
         assert_matches!(
             parsed,
@@ -440,7 +460,7 @@ mod tests {
 
     #[test]
     fn parse_nothing() {
-        let parsed = MessageEntityRef::parse("a", &[]);
+        let parsed = MessageEntityRef::parse("a", &[]).unwrap();
         assert_eq!(parsed, []);
     }
 
@@ -462,7 +482,8 @@ mod tests {
                     length: 0,
                 },
             ],
-        );
+        )
+        .unwrap();
 
         assert_matches!(
             parsed,
@@ -472,4 +493,29 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn parse_invalid() {
+        let entity = |offset, length| MessageEntity {
+            kind: Bold,
+            offset,
+            length,
+        };
+
+        assert_eq!(
+            MessageEntityRef::parse("a😀", &[entity(1, 3)]),
+            Err(EntityOffsetError::OutOfBounds { offset: 4, len: 3 })
+        );
+        assert_eq!(
+            MessageEntityRef::parse("a😀", &[entity(0, 1), entity(0, 2)]),
+            Err(EntityOffsetError::InsideCharacter { offset: 2 })
+        );
+        assert_eq!(
+            MessageEntityRef::parse("a", &[entity(usize::MAX, 1)]),
+            Err(EntityOffsetError::OutOfBounds {
+                offset: usize::MAX,
+                len: 1
+            })
+        );
+    }
 }
@@ -1,6 +1,9 @@
 use serde::{Deserialize, Serialize};
 
-use crate::types::KeyboardButton;
+use crate::types::{
+    keyboard::{grid, paginated, validate},
+    KeyboardButton, KeyboardError,
+};
 
 /// This object represents a [custom keyboard] with reply options (see
 /// [Introduction to bots] for details and examples).
@@ -67,6 +70,67 @@ impl KeyboardMarkup {
         }
     }
 
+    /// Maximum number of buttons in a row.
+    ///
+    /// Note that this limit is observed, it's not documented by the Bot API.
+    pub const MAX_ROW_LEN: usize = 12;
+
+    /// Maximum number of buttons in a keyboard.
+    ///
+    /// Note that this limit is observed, it's not documented by the Bot API.
+    pub const MAX_BUTTONS: usize = 300;
+
+    /// Creates a keyboard laying out `buttons` in rows of `columns` buttons
+    /// (the last row may be shorter).
+    ///
+    /// ## Panics
+    ///
+    /// If `columns` is 0.
+    pub fn grid<I>(buttons: I, columns: usize) -> Self
+    where
+        I: IntoIterator<Item = KeyboardButton>,
+    {
+        Self::new(grid(buttons, columns))
+    }
+
+    /// Creates a keyboard with the page number `page` (starting from 0) of a
+    /// long list of `buttons`, laid out like [`grid`] does.
+    ///
+    /// The last row contains buttons which lead to the previous and the next
+    /// pages (if they exist), `nav_text` returns text of these buttons (which
+    /// is sent by the user when the button is pressed) given the number of the
+    /// page they lead to.
+    ///
+    /// ## Panics
+    ///
+    /// If `columns` or `page_size` is 0.
+    ///
+    /// [`grid`]: KeyboardMarkup::grid
+    pub fn paginated<I, F>(
+        buttons: I,
+        columns: usize,
+        page_size: usize,
+        page: usize,
+        mut nav_text: F,
+    ) -> Self
+    where
+        I: IntoIterator<Item = KeyboardButton>,
+        F: FnMut(usize) -> String,
+    {
+        Self::new(paginated(buttons, columns, page_size, page, |to| {
+            KeyboardButton::new(nav_text(to))
+        }))
+    }
+
+    /// Checks that the keyboard fits into telegram limits: [`MAX_ROW_LEN`] and
+    /// [`MAX_BUTTONS`].
+    ///
+    /// [`MAX_ROW_LEN`]: KeyboardMarkup::MAX_ROW_LEN
+    /// [`MAX_BUTTONS`]: KeyboardMarkup::MAX_BUTTONS
+    pub fn validate(&self) -> Result<(), KeyboardError> {
+        validate(&self.keyboard, Self::MAX_ROW_LEN, Self::MAX_BUTTONS)
+    }
+
     pub fn append_row<R>(mut self, buttons: R) -> Self
     where
         R: IntoIterator<Item = KeyboardButton>,
@@ -115,3 +179,24 @@ impl KeyboardMarkup {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn navigation() {
+        let button = |n: u32| KeyboardButton::new(n.to_string());
+
+        let markup = KeyboardMarkup::paginated((0..5).map(button), 2, 2, 1, |page| {
+            format!("Page {}", page + 1)
+        });
+        assert_eq!(
+            markup.keyboard,
+            [
+                vec![button(2), button(3)],
+                vec![KeyboardButton::new("Page 1"), KeyboardButton::new("Page 3")]
+            ]
+        );
+    }
+}
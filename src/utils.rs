@@ -0,0 +1,17 @@
+//! Utilities for working with formatted text and bot commands.
+
+pub use command::ParsedCommand;
+pub use command_scopes::CommandScopes;
+#[doc(hidden)]
+pub use escape::__check_captures;
+pub use escape::{escape, escape_code, escape_html, escape_markdown_v2, Escaped};
+pub use text_builder::TextBuilder;
+
+pub mod parse;
+pub mod render;
+pub mod split;
+
+mod command;
+mod command_scopes;
+mod escape;
+mod text_builder;
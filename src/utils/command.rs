@@ -0,0 +1,198 @@
+use crate::{
+    types::{BotCommand, Me, Message, MessageEntity, MessageEntityKind, MessageEntityRef},
+    utils::text_builder::clip,
+};
+
+/// A bot command (`/command@botname arguments`) parsed from a message.
+///
+/// Commands are found by [`MessageEntityKind::BotCommand`] entities, so only
+/// commands detected by telegram are parsed and a command must be at the very
+/// beginning of the text.
+///
+/// ## Examples
+///
+/// ```
+/// use teloxide_core::{
+///     types::{MessageEntity, MessageEntityKind},
+///     utils::ParsedCommand,
+/// };
+///
+/// let text = "/ban@my_bot 42 *spam*";
+/// let entities = [
+///     MessageEntity::new(MessageEntityKind::BotCommand, 0, 11),
+///     MessageEntity::bold(15, 6),
+/// ];
+///
+/// let command = ParsedCommand::parse(text, &entities).unwrap();
+/// assert_eq!(command.name, "ban");
+/// assert_eq!(command.mention, Some("my_bot"));
+/// assert_eq!(command.args, "42 *spam*");
+/// assert_eq!(command.args_entities, [MessageEntity::bold(3, 6)]);
+/// assert_eq!(command.split_args().collect::<Vec<_>>(), ["42", "*spam*"]);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ParsedCommand<'a> {
+    /// Name of the command, without the leading `/`.
+    pub name: &'a str,
+
+    /// Username of the bot the command is addressed to, without the leading
+    /// `@`.
+    pub mention: Option<&'a str>,
+
+    /// Text after the command, with leading whitespace removed.
+    pub args: &'a str,
+
+    /// Entities of [`args`], with offsets relative to it.
+    ///
+    /// [`args`]: ParsedCommand::args
+    pub args_entities: Vec<MessageEntity>,
+}
+
+impl<'a> ParsedCommand<'a> {
+    /// Parses a command from `text` with `entities`.
+    ///
+    /// Returns `None` if the text doesn't start with a command or if
+    /// `entities` have invalid offsets.
+    pub fn parse(text: &'a str, entities: &[MessageEntity]) -> Option<Self> {
+        let command = MessageEntityRef::parse(text, entities)
+            .ok()?
+            .into_iter()
+            .find(|entity| {
+                entity.start() == 0 && matches!(entity.kind(), MessageEntityKind::BotCommand)
+            })?;
+
+        let command_text = &text[command.range()];
+        let command_text = command_text.strip_prefix('/')?;
+        let (name, mention) = match command_text.split_once('@') {
+            Some((name, mention)) => (name, Some(mention)),
+            None => (command_text, None),
+        };
+
+        let rest = &text[command.end()..];
+        let args = rest.trim_start();
+        let args_start = text.len() - args.len();
+        let start = text[..args_start].encode_utf16().count();
+        let end = start + args.encode_utf16().count();
+
+        Some(Self {
+            name,
+            mention,
+            args,
+            args_entities: clip(entities, start, end),
+        })
+    }
+
+    /// Parses a command from the text of a text message or from the caption of
+    /// a media message.
+    ///
+    /// Returns `None` if the text doesn't start with a command.
+    pub fn from_message(message: &'a Message) -> Option<Self> {
+        let (text, entities) = match message.text() {
+            Some(text) => (text, message.entities()),
+            None => (message.caption()?, message.caption_entities()),
+        };
+
+        Self::parse(text, entities.unwrap_or_default())
+    }
+
+    /// Returns `true` if the command is addressed to `me`, i.e. it has no
+    /// mention or mentions the username of `me`.
+    ///
+    /// Commands addressed to other bots should usually be ignored.
+    pub fn is_for(&self, me: &Me) -> bool {
+        match (self.mention, me.user.username.as_deref()) {
+            (None, _) => true,
+            (Some(mention), Some(username)) => mention.eq_ignore_ascii_case(username),
+            (Some(_), None) => false,
+        }
+    }
+
+    /// Returns the command from `commands` with the same name, if any.
+    ///
+    /// This can be used to validate commands against a list set by
+    /// [`SetMyCommands`].
+    ///
+    /// [`SetMyCommands`]: crate::payloads::SetMyCommands
+    pub fn find_in<'c>(&self, commands: &'c [BotCommand]) -> Option<&'c BotCommand> {
+        commands.iter().find(|command| command.command == self.name)
+    }
+
+    /// Returns an iterator over whitespace separated arguments.
+    pub fn split_args(&self) -> std::str::SplitWhitespace<'a> {
+        self.args.split_whitespace()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::{BotCommand, Me, MessageEntity, MessageEntityKind, User, UserId};
+
+    use super::ParsedCommand;
+
+    #[test]
+    fn parse_and_check() {
+        let command = |length| MessageEntity::new(MessageEntityKind::BotCommand, 0, length);
+
+        let parsed = ParsedCommand::parse("/start", &[command(6)]).unwrap();
+        assert_eq!(parsed.name, "start");
+        assert_eq!(parsed.mention, None);
+        assert_eq!(parsed.args, "");
+        assert_eq!(parsed.split_args().count(), 0);
+
+        let parsed = ParsedCommand::parse(
+            "/say@Bot \n 😀 hi",
+            &[command(8), MessageEntity::italic(14, 2)],
+        )
+        .unwrap();
+        assert_eq!(parsed.name, "say");
+        assert_eq!(parsed.mention, Some("Bot"));
+        assert_eq!(parsed.args, "😀 hi");
+        assert_eq!(parsed.args_entities, [MessageEntity::italic(3, 2)]);
+
+        // Not at the start, no entity, invalid entity
+        assert_eq!(
+            ParsedCommand::parse(
+                "a /start",
+                &[MessageEntity::new(MessageEntityKind::BotCommand, 2, 6)]
+            ),
+            None
+        );
+        assert_eq!(ParsedCommand::parse("/start", &[]), None);
+        assert_eq!(ParsedCommand::parse("/s", &[command(3)]), None);
+
+        let me = Me {
+            user: User {
+                id: UserId(1),
+                is_bot: true,
+                first_name: "Bot".to_owned(),
+                last_name: None,
+                username: Some("bot".to_owned()),
+                language_code: None,
+                is_premium: false,
+                added_to_attachment_menu: false,
+            },
+            can_join_groups: true,
+            can_read_all_group_messages: false,
+            supports_inline_queries: false,
+        };
+        assert!(parsed.is_for(&me));
+        assert!(!ParsedCommand::parse("/say@other", &[command(10)])
+            .unwrap()
+            .is_for(&me));
+        assert!(ParsedCommand::parse("/say", &[command(4)])
+            .unwrap()
+            .is_for(&me));
+
+        let commands = [
+            BotCommand::new("start", "Start"),
+            BotCommand::new("say", "Say"),
+        ];
+        assert_eq!(parsed.find_in(&commands), Some(&commands[1]));
+        assert_eq!(
+            ParsedCommand::parse("/help", &[command(5)])
+                .unwrap()
+                .find_in(&commands),
+            None
+        );
+    }
+}
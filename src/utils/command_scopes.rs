@@ -0,0 +1,245 @@
+use std::collections::HashMap;
+
+use crate::{
+    payloads::SetMyCommands,
+    types::{BotCommand, BotCommandScope, Recipient, UserId},
+};
+
+/// Offline model of bot commands set for different [`BotCommandScope`]s and
+/// languages.
+///
+/// This resolves the list of commands a user sees the same way telegram does
+/// (see [`BotCommandScope`] docs), so command configuration can be tested
+/// without checking it manually in clients.
+///
+/// Chats are compared as given, so a scope set for a chat by its username
+/// doesn't apply to the chat given by its id and vice versa.
+///
+/// ## Examples
+///
+/// ```
+/// use teloxide_core::{
+///     types::{BotCommand, BotCommandScope, ChatId, Recipient, UserId},
+///     utils::CommandScopes,
+/// };
+///
+/// let help = BotCommand::new("help", "Show help");
+/// let ban = BotCommand::new("ban", "Ban a user");
+/// let hilfe = BotCommand::new("help", "Hilfe anzeigen");
+///
+/// let mut scopes = CommandScopes::new();
+/// scopes.set(BotCommandScope::Default, None, vec![help.clone()]);
+/// scopes.set(BotCommandScope::Default, Some("de"), vec![hilfe.clone()]);
+/// scopes.set(
+///     BotCommandScope::AllChatAdministrators,
+///     None,
+///     vec![help.clone(), ban.clone()],
+/// );
+///
+/// let group = Recipient::Id(ChatId(-1));
+/// assert_eq!(
+///     scopes.group_chat(&group, UserId(1), false, Some("en")),
+///     [help.clone()]
+/// );
+/// assert_eq!(
+///     scopes.group_chat(&group, UserId(1), false, Some("de")),
+///     [hilfe]
+/// );
+/// assert_eq!(
+///     scopes.group_chat(&group, UserId(1), true, Some("de")),
+///     [help, ban]
+/// );
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CommandScopes {
+    commands: HashMap<(BotCommandScope, Option<String>), Vec<BotCommand>>,
+}
+
+impl CommandScopes {
+    /// Creates an empty set of scopes, for which no commands are set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets `commands` for `scope` and `language_code`, like
+    /// [`SetMyCommands`] does.
+    ///
+    /// Setting an empty list is the same as [removing] it, i.e. commands of
+    /// lower precedence are used instead.
+    ///
+    /// [removing]: CommandScopes::remove
+    pub fn set(
+        &mut self,
+        scope: BotCommandScope,
+        language_code: Option<&str>,
+        commands: Vec<BotCommand>,
+    ) {
+        let key = (scope, language_code.map(ToOwned::to_owned));
+        if commands.is_empty() {
+            self.commands.remove(&key);
+        } else {
+            self.commands.insert(key, commands);
+        }
+    }
+
+    /// Sets commands described by a [`SetMyCommands`] payload.
+    pub fn apply(&mut self, payload: &SetMyCommands) {
+        self.set(
+            payload.scope.clone().unwrap_or(BotCommandScope::Default),
+            payload.language_code.as_deref(),
+            payload.commands.clone(),
+        )
+    }
+
+    /// Removes commands set for `scope` and `language_code`, like
+    /// [`DeleteMyCommands`] does.
+    ///
+    /// [`DeleteMyCommands`]: crate::payloads::DeleteMyCommands
+    pub fn remove(&mut self, scope: &BotCommandScope, language_code: Option<&str>) {
+        self.commands
+            .remove(&(scope.clone(), language_code.map(ToOwned::to_owned)));
+    }
+
+    /// Returns commands set for exactly `scope` and `language_code`, like
+    /// [`GetMyCommands`] does.
+    ///
+    /// [`GetMyCommands`]: crate::payloads::GetMyCommands
+    pub fn get(&self, scope: &BotCommandScope, language_code: Option<&str>) -> &[BotCommand] {
+        self.commands
+            .get(&(scope.clone(), language_code.map(ToOwned::to_owned)))
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Returns commands which the user with `user_id` and `language_code`
+    /// sees in the private chat with the bot.
+    pub fn private_chat(&self, user_id: UserId, language_code: Option<&str>) -> &[BotCommand] {
+        self.resolve(
+            &[
+                BotCommandScope::Chat {
+                    chat_id: user_id.into(),
+                },
+                BotCommandScope::AllPrivateChats,
+                BotCommandScope::Default,
+            ],
+            language_code,
+        )
+    }
+
+    /// Returns commands which the user with `user_id` and `language_code`
+    /// sees in the group or supergroup `chat`.
+    ///
+    /// `is_admin` is whether the user is an administrator of the chat.
+    pub fn group_chat(
+        &self,
+        chat: &Recipient,
+        user_id: UserId,
+        is_admin: bool,
+        language_code: Option<&str>,
+    ) -> &[BotCommand] {
+        let chat_id = chat.clone();
+        let mut scopes = vec![BotCommandScope::ChatMember {
+            chat_id: chat_id.clone(),
+            user_id,
+        }];
+        if is_admin {
+            scopes.push(BotCommandScope::ChatAdministrators {
+                chat_id: chat_id.clone(),
+            });
+        }
+        scopes.push(BotCommandScope::Chat { chat_id });
+        if is_admin {
+            scopes.push(BotCommandScope::AllChatAdministrators);
+        }
+        scopes.push(BotCommandScope::AllGroupChats);
+        scopes.push(BotCommandScope::Default);
+
+        self.resolve(&scopes, language_code)
+    }
+
+    /// Returns the first list of commands which is set, trying every scope
+    /// with `language_code` first and without it after.
+    fn resolve(&self, scopes: &[BotCommandScope], language_code: Option<&str>) -> &[BotCommand] {
+        scopes
+            .iter()
+            .flat_map(|scope| {
+                language_code
+                    .map(|language_code| (scope, Some(language_code)))
+                    .into_iter()
+                    .chain([(scope, None)])
+            })
+            .map(|(scope, language_code)| self.get(scope, language_code))
+            .find(|commands| !commands.is_empty())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        payloads::{setters::*, SetMyCommands},
+        types::{BotCommand, BotCommandScope, ChatId, Recipient, UserId},
+    };
+
+    use super::CommandScopes;
+
+    #[test]
+    fn precedence() {
+        let command = |name: &str| vec![BotCommand::new(name, name)];
+        let group = Recipient::Id(ChatId(-1));
+        let user = UserId(1);
+
+        let mut scopes = CommandScopes::new();
+        assert!(scopes.private_chat(user, None).is_empty());
+
+        scopes.set(BotCommandScope::Default, None, command("default"));
+        scopes.set(
+            BotCommandScope::AllPrivateChats,
+            Some("en"),
+            command("private_en"),
+        );
+        scopes.apply(
+            &SetMyCommands::new(command("member")).scope(BotCommandScope::ChatMember {
+                chat_id: group.clone(),
+                user_id: user,
+            }),
+        );
+        scopes.set(
+            BotCommandScope::ChatAdministrators {
+                chat_id: group.clone(),
+            },
+            Some("en"),
+            command("admins_en"),
+        );
+        scopes.set(BotCommandScope::AllGroupChats, None, command("groups"));
+
+        assert_eq!(scopes.private_chat(user, Some("en")), command("private_en"));
+        assert_eq!(scopes.private_chat(user, Some("de")), command("default"));
+
+        // `ChatMember` without language code has precedence over admin scopes
+        assert_eq!(
+            scopes.group_chat(&group, user, true, Some("en")),
+            command("member")
+        );
+        assert_eq!(
+            scopes.group_chat(&group, UserId(2), true, Some("en")),
+            command("admins_en")
+        );
+        assert_eq!(
+            scopes.group_chat(&group, UserId(2), true, None),
+            command("groups")
+        );
+        assert_eq!(
+            scopes.group_chat(&group, UserId(2), false, Some("en")),
+            command("groups")
+        );
+
+        scopes.set(BotCommandScope::AllGroupChats, None, vec![]);
+        assert_eq!(
+            scopes.group_chat(&group, UserId(2), false, None),
+            command("default")
+        );
+        scopes.remove(&BotCommandScope::Default, None);
+        assert!(scopes.group_chat(&group, UserId(2), false, None).is_empty());
+    }
+}
@@ -0,0 +1,421 @@
+//! Escaping of text for different [`ParseMode`]s.
+
+use std::fmt::{self, Alignment, Debug, Display, Write};
+
+use crate::types::ParseMode;
+
+/// Escapes `text` for [`ParseMode::Html`].
+///
+/// ## Examples
+///
+/// ```
+/// use teloxide_core::utils::escape_html;
+///
+/// assert_eq!(escape_html("<b>a & b</b>"), "&lt;b&gt;a &amp; b&lt;/b&gt;");
+/// ```
+pub fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    push_html(text, &mut out);
+    out
+}
+
+/// Escapes `text` for [`ParseMode::MarkdownV2`].
+///
+/// Note that text inside of code and pre entities must be escaped with
+/// [`escape_code`] instead.
+///
+/// ## Examples
+///
+/// ```
+/// use teloxide_core::utils::escape_markdown_v2;
+///
+/// assert_eq!(
+///     escape_markdown_v2("snake_case 2*2=4."),
+///     "snake\\_case 2\\*2\\=4\\."
+/// );
+/// ```
+pub fn escape_markdown_v2(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    push_markdown_v2(text, false, &mut out);
+    out
+}
+
+/// Escapes `text` for code and pre entities of [`ParseMode::MarkdownV2`].
+///
+/// ## Examples
+///
+/// ```
+/// use teloxide_core::utils::escape_code;
+///
+/// assert_eq!(
+///     escape_code("let s = \"`\\\\`\";"),
+///     "let s = \"\\`\\\\\\\\\\`\";"
+/// );
+/// ```
+pub fn escape_code(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    push_markdown_v2(text, true, &mut out);
+    out
+}
+
+/// Escapes `text` for the given `parse_mode`.
+pub fn escape(text: &str, parse_mode: ParseMode) -> String {
+    let mut out = String::with_capacity(text.len());
+    push(text, parse_mode, &mut out);
+    out
+}
+
+/// Value which is displayed escaped for a [`ParseMode`].
+///
+/// This is used by [`html!`] and [`markdown_v2!`] macros, but can also be used
+/// directly with [`format!`] or other formatting macros. Both `Display` and
+/// `Debug` implementations of the value are escaped, formatting parameters
+/// like width are applied to the escaped text.
+///
+/// ## Examples
+///
+/// ```
+/// use teloxide_core::utils::Escaped;
+///
+/// let name = "<script>";
+/// let text = format!(
+///     "Hi, <b>{}</b>! {:?}",
+///     Escaped::html(name),
+///     Escaped::html(name)
+/// );
+///
+/// assert_eq!(
+///     text,
+///     "Hi, <b>&lt;script&gt;</b>! &quot;&lt;script&gt;&quot;"
+/// );
+/// ```
+///
+/// [`html!`]: crate::html
+/// [`markdown_v2!`]: crate::markdown_v2
+#[derive(Clone, Copy)]
+pub struct Escaped<T> {
+    value: T,
+    parse_mode: ParseMode,
+}
+
+impl<T> Escaped<T> {
+    /// Wraps `value` to be escaped for `parse_mode`.
+    pub fn new(value: T, parse_mode: ParseMode) -> Self {
+        Self { value, parse_mode }
+    }
+
+    /// Wraps `value` to be escaped for [`ParseMode::Html`].
+    pub fn html(value: T) -> Self {
+        Self::new(value, ParseMode::Html)
+    }
+
+    /// Wraps `value` to be escaped for [`ParseMode::MarkdownV2`].
+    pub fn markdown_v2(value: T) -> Self {
+        Self::new(value, ParseMode::MarkdownV2)
+    }
+}
+
+impl<T: Display> Display for Escaped<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = match f.precision() {
+            Some(precision) => format!("{:.*}", precision, self.value),
+            None => self.value.to_string(),
+        };
+        pad(f, &escape(&value, self.parse_mode))
+    }
+}
+
+impl<T: Debug> Debug for Escaped<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = match f.precision() {
+            Some(precision) => format!("{:.*?}", precision, self.value),
+            None => format!("{:?}", self.value),
+        };
+        pad(f, &escape(&value, self.parse_mode))
+    }
+}
+
+/// Pads already escaped `text` according to width, fill and alignment of `f`.
+///
+/// Unlike [`fmt::Formatter::pad`] this ignores the precision, which is
+/// applied to the value before escaping, so the escaped text is never
+/// truncated.
+fn pad(f: &mut fmt::Formatter<'_>, text: &str) -> fmt::Result {
+    let len = text.chars().count();
+    let padding = f.width().map_or(0, |width| width.saturating_sub(len));
+    let (before, after) = match f.align() {
+        Some(Alignment::Right) => (padding, 0),
+        Some(Alignment::Center) => (padding / 2, (padding + 1) / 2),
+        Some(Alignment::Left) | None => (0, padding),
+    };
+
+    let fill = f.fill();
+    for _ in 0..before {
+        f.write_char(fill)?;
+    }
+    f.write_str(text)?;
+    for _ in 0..after {
+        f.write_char(fill)?;
+    }
+
+    Ok(())
+}
+
+/// Formats a [`ParseMode::Html`] string, escaping all arguments.
+///
+/// Accepts the same arguments as [`format!`], but the arguments are escaped
+/// with [`Escaped::html`], while the format string is left as is, so it can
+/// contain markup.
+///
+/// **Note**: arguments captured implicitly (`{name}`) can't be escaped, so
+/// they are rejected at compile time, pass them explicitly (`{name}`,
+/// `name = name`).
+///
+/// ## Examples
+///
+/// ```
+/// let name = "<Ferris>";
+/// let text = teloxide_core::html!("Hi, <b>{}</b>! You have {n} messages", name, n = 2);
+///
+/// assert_eq!(text, "Hi, <b>&lt;Ferris&gt;</b>! You have 2 messages");
+/// ```
+///
+/// ```compile_fail
+/// let name = "<Ferris>";
+/// let text = teloxide_core::html!("Hi, <b>{name}</b>!");
+/// ```
+///
+/// [`ParseMode::Html`]: crate::types::ParseMode::Html
+/// [`Escaped::html`]: crate::utils::Escaped::html
+#[macro_export]
+macro_rules! html {
+    ($fmt:literal $($args:tt)*) => {
+        $crate::__format_escaped!(@ Html, $fmt, [] [] $($args)*)
+    };
+}
+
+/// Formats a [`ParseMode::MarkdownV2`] string, escaping all arguments.
+///
+/// Accepts the same arguments as [`format!`], but the arguments are escaped
+/// with [`Escaped::markdown_v2`], while the format string is left as is, so it
+/// can contain markup.
+///
+/// **Note**: arguments captured implicitly (`{name}`) can't be escaped, so
+/// they are rejected at compile time, pass them explicitly (`{name}`,
+/// `name = name`). Arguments are escaped for regular text, use [`escape_code`]
+/// for arguments inside of code and pre entities.
+///
+/// ## Examples
+///
+/// ```
+/// let name = "snake_case";
+/// let text = teloxide_core::markdown_v2!("Hi, *{}*\\! You have {n} messages", name, n = 2.5);
+///
+/// assert_eq!(text, "Hi, *snake\\_case*\\! You have 2\\.5 messages");
+/// ```
+///
+/// [`ParseMode::MarkdownV2`]: crate::types::ParseMode::MarkdownV2
+/// [`Escaped::markdown_v2`]: crate::utils::Escaped::markdown_v2
+/// [`escape_code`]: crate::utils::escape_code
+#[macro_export]
+macro_rules! markdown_v2 {
+    ($fmt:literal $($args:tt)*) => {
+        $crate::__format_escaped!(@ MarkdownV2, $fmt, [] [] $($args)*)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __format_escaped {
+    (@ $mode:ident, $fmt:literal, [$($done:tt)*] [$($names:tt)*] $(,)?) => {{
+        const _: () = $crate::utils::__check_captures($fmt, &[$($names)*]);
+        ::std::format!($fmt $($done)*)
+    }};
+    (@ $mode:ident, $fmt:literal, [$($done:tt)*] [$($names:tt)*], $name:ident = $arg:expr $(, $($rest:tt)*)?) => {
+        $crate::__format_escaped!(
+            @ $mode,
+            $fmt,
+            [$($done)*, $name = $crate::utils::Escaped::new(&$arg, $crate::types::ParseMode::$mode)]
+            [$($names)* ::std::stringify!($name),]
+            $(, $($rest)*)?
+        )
+    };
+    (@ $mode:ident, $fmt:literal, [$($done:tt)*] [$($names:tt)*], $arg:expr $(, $($rest:tt)*)?) => {
+        $crate::__format_escaped!(
+            @ $mode,
+            $fmt,
+            [$($done)*, $crate::utils::Escaped::new(&$arg, $crate::types::ParseMode::$mode)]
+            [$($names)*]
+            $(, $($rest)*)?
+        )
+    };
+}
+
+/// Panics if `fmt` has an implicitly captured argument, i.e. a named argument
+/// which is not in `names`.
+///
+/// This is called in a const context by [`html!`] and [`markdown_v2!`], so
+/// such arguments are rejected at compile time.
+///
+/// [`html!`]: crate::html
+/// [`markdown_v2!`]: crate::markdown_v2
+#[doc(hidden)]
+pub const fn __check_captures(fmt: &str, names: &[&str]) {
+    let fmt = fmt.as_bytes();
+    let mut i = 0;
+    while i < fmt.len() {
+        if fmt[i] != b'{' {
+            i += 1;
+            continue;
+        }
+
+        // `{{` is an escaped brace
+        if i + 1 < fmt.len() && fmt[i + 1] == b'{' {
+            i += 2;
+            continue;
+        }
+
+        let start = i + 1;
+        let mut end = start;
+        while end < fmt.len() && fmt[end] != b'}' && fmt[end] != b':' {
+            end += 1;
+        }
+
+        let named = end > start && !fmt[start].is_ascii_digit();
+        if named && !contains(names, fmt, start, end) {
+            panic!("implicitly captured arguments are not escaped, pass them explicitly");
+        }
+
+        i = end;
+    }
+}
+
+/// Returns `true` if `names` contain `fmt[start..end]`.
+const fn contains(names: &[&str], fmt: &[u8], start: usize, end: usize) -> bool {
+    let mut n = 0;
+    while n < names.len() {
+        let name = names[n].as_bytes();
+        if name.len() == end - start {
+            let mut k = 0;
+            while k < name.len() && name[k] == fmt[start + k] {
+                k += 1;
+            }
+            if k == name.len() {
+                return true;
+            }
+        }
+        n += 1;
+    }
+
+    false
+}
+
+pub(crate) fn push(text: &str, parse_mode: ParseMode, out: &mut String) {
+    #[allow(deprecated)]
+    match parse_mode {
+        ParseMode::Html => push_html(text, out),
+        ParseMode::MarkdownV2 => push_markdown_v2(text, false, out),
+        ParseMode::Markdown => {
+            for c in text.chars() {
+                if matches!(c, '_' | '*' | '`' | '[') {
+                    out.push('\\');
+                }
+                out.push(c);
+            }
+        }
+    }
+}
+
+pub(crate) fn push_html(text: &str, out: &mut String) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+}
+
+pub(crate) fn push_markdown_v2(text: &str, in_code: bool, out: &mut String) {
+    for c in text.chars() {
+        let escape = if in_code {
+            matches!(c, '`' | '\\')
+        } else {
+            matches!(
+                c,
+                '_' | '*'
+                    | '['
+                    | ']'
+                    | '('
+                    | ')'
+                    | '~'
+                    | '`'
+                    | '>'
+                    | '#'
+                    | '+'
+                    | '-'
+                    | '='
+                    | '|'
+                    | '{'
+                    | '}'
+                    | '.'
+                    | '!'
+                    | '\\'
+            )
+        };
+
+        if escape {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+}
+
+pub(crate) fn push_markdown_v2_url(url: &str, out: &mut String) {
+    for c in url.chars() {
+        if matches!(c, ')' | '\\') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        types::ParseMode,
+        utils::{escape, parse},
+    };
+
+    use super::__check_captures;
+
+    #[test]
+    fn macros() {
+        let name = String::from("_a_ <b>");
+        let text = html!("<i>{:>15}</i> {} {x:?}", name, 1, x = name,);
+        assert_eq!(text, "<i>  _a_ &lt;b&gt;</i> 1 &quot;_a_ &lt;b&gt;&quot;");
+        assert_eq!(parse::html(&text).unwrap().0, "  _a_ <b> 1 \"_a_ <b>\"");
+
+        let text = markdown_v2!("*{}* {x}", name, x = name.len());
+        assert_eq!(text, "*\\_a\\_ <b\\>* 7");
+        assert_eq!(parse::markdown_v2(&text).unwrap().0, "_a_ <b> 7");
+
+        // Precision applies to the value, not to the escaped text
+        assert_eq!(html!("{:.2}", 1.23456), "1.23");
+        assert_eq!(
+            html!("{:.3}|{:^9.2}|", "&x<y", "&x"),
+            "&amp;x&lt;| &amp;x  |"
+        );
+        assert_eq!(markdown_v2!("{:.2}", 1.23456), "1\\.23");
+        assert_eq!(markdown_v2!("{:.3}", "a.b!"), "a\\.b");
+
+        __check_captures("{} {0:?} {x} {{y}} {:w$}", &["x"]);
+        assert!(std::panic::catch_unwind(|| __check_captures("{x} {y}", &["x"])).is_err());
+
+        #[allow(deprecated)]
+        let text = escape("_a_ [b]", ParseMode::Markdown);
+        assert_eq!(text, "\\_a\\_ \\[b]");
+    }
+}
@@ -0,0 +1,634 @@
+//! Parsing of markup to text with [`MessageEntity`]s.
+//!
+//! Parsers follow the rules described in [`ParseMode`] docs, so malformed
+//! markup (e.g. in templates) can be detected before it's sent to telegram.
+//! Errors contain the offset (in bytes) of the problem in the markup.
+//!
+//! ## Examples
+//!
+//! ```
+//! use teloxide_core::{types::MessageEntity, utils::parse};
+//!
+//! let (text, entities) = parse::html("<b>Hello, <i>world</i>!</b>").unwrap();
+//! assert_eq!(text, "Hello, world!");
+//! assert_eq!(
+//!     entities,
+//!     [MessageEntity::bold(0, 13), MessageEntity::italic(7, 5)]
+//! );
+//!
+//! let err = parse::markdown_v2("*Hello, _world_!*").unwrap_err();
+//! assert_eq!(err.offset, 15);
+//! ```
+//!
+//! [`ParseMode`]: crate::types::ParseMode
+
+use reqwest::Url;
+
+use crate::types::{MessageEntity, MessageEntityKind as Kind};
+
+/// Parses [`ParseMode::Html`] markup.
+///
+/// [`ParseMode::Html`]: crate::types::ParseMode::Html
+pub fn html(markup: &str) -> Result<(String, Vec<MessageEntity>), ParseError> {
+    let mut out = Builder::default();
+    let mut stack: Vec<HtmlTag<'_>> = Vec::new();
+    let mut pos = 0;
+
+    while let Some(c) = markup[pos..].chars().next() {
+        match c {
+            '<' => {
+                let end = markup[pos..]
+                    .find('>')
+                    .map(|end| pos + end)
+                    .ok_or_else(|| ParseError::new(pos, ParseErrorKind::MalformedTag))?;
+
+                html_tag(&markup[pos + 1..end], pos, &mut stack, &mut out)?;
+                pos = end + 1;
+            }
+            '&' => match html_entity(&markup[pos..]) {
+                Some((c, len)) => {
+                    out.push(c);
+                    pos += len;
+                }
+                None => {
+                    out.push('&');
+                    pos += 1;
+                }
+            },
+            c => {
+                out.push(c);
+                pos += c.len_utf8();
+            }
+        }
+    }
+
+    match stack.pop() {
+        Some(tag) => Err(ParseError::new(
+            tag.offset,
+            ParseErrorKind::Unclosed(tag.name.to_owned()),
+        )),
+        None => Ok(out.finish()),
+    }
+}
+
+/// Parses [`ParseMode::MarkdownV2`] markup.
+///
+/// [`ParseMode::MarkdownV2`]: crate::types::ParseMode::MarkdownV2
+pub fn markdown_v2(markup: &str) -> Result<(String, Vec<MessageEntity>), ParseError> {
+    let mut out = Builder::default();
+    let mut stack: Vec<Marker> = Vec::new();
+    let mut chars = markup.char_indices().peekable();
+
+    while let Some((pos, c)) = chars.next() {
+        let in_code = matches!(stack.last(), Some(marker) if marker.marker.starts_with('`'));
+
+        if in_code {
+            match c {
+                '\\' => match chars.next() {
+                    Some((_, c @ ('`' | '\\'))) => out.push(c),
+                    _ => return Err(ParseError::new(pos, ParseErrorKind::Unescaped('\\'))),
+                },
+                '`' => {
+                    let marker = stack.pop().unwrap();
+                    if marker.marker == "```" {
+                        if !markup[pos..].starts_with("```") {
+                            return Err(ParseError::new(pos, ParseErrorKind::Unescaped('`')));
+                        }
+                        chars.nth(1);
+                    }
+                    out.close(marker.entity, None);
+                }
+                c => out.push(c),
+            }
+            continue;
+        }
+
+        let (marker, kind) = match c {
+            '\\' => match chars.next() {
+                Some((_, c)) if matches!(c as u32, 1..=126) => {
+                    out.push(c);
+                    continue;
+                }
+                _ => return Err(ParseError::new(pos, ParseErrorKind::Unescaped('\\'))),
+            },
+            // `\r` can be used to separate markers, e.g. `_` and `__`
+            '\r' => continue,
+            '*' => ("*", Kind::Bold),
+            '~' => ("~", Kind::Strikethrough),
+            '_' if chars.next_if(|&(_, c)| c == '_').is_some() => ("__", Kind::Underline),
+            '_' => ("_", Kind::Italic),
+            '|' if chars.next_if(|&(_, c)| c == '|').is_some() => ("||", Kind::Spoiler),
+            '`' if markup[pos..].starts_with("```") => {
+                chars.nth(1);
+
+                // The first line is the language, if it's not empty
+                let rest = &markup[pos + 3..];
+                let language = match rest.find('\n') {
+                    Some(end) if !rest[..end].contains(|c: char| c.is_whitespace() || c == '`') => {
+                        chars.nth(rest[..end].chars().count());
+                        Some(rest[..end].to_owned()).filter(|language| !language.is_empty())
+                    }
+                    _ => None,
+                };
+
+                let entity = out.open(Some(Kind::Pre { language }));
+                stack.push(Marker {
+                    marker: "```",
+                    offset: pos,
+                    entity,
+                });
+                continue;
+            }
+            '`' => ("`", Kind::Code),
+            '[' => {
+                let entity = out.open(None);
+                stack.push(Marker {
+                    marker: "[",
+                    offset: pos,
+                    entity,
+                });
+                continue;
+            }
+            ']' if matches!(stack.last(), Some(marker) if marker.marker == "[") => {
+                let url_start = pos + 1;
+                if chars.next_if(|&(_, c)| c == '(').is_none() {
+                    return Err(ParseError::new(url_start, ParseErrorKind::MissingUrl));
+                }
+
+                let mut url = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, ')')) => break,
+                        Some((_, '\\')) if chars.peek().is_some() => {
+                            url.push(chars.next().unwrap().1)
+                        }
+                        Some((_, c)) => url.push(c),
+                        None => {
+                            return Err(ParseError::new(
+                                url_start,
+                                ParseErrorKind::Unclosed("(".to_owned()),
+                            ))
+                        }
+                    }
+                }
+
+                let url = Url::parse(&url).map_err(|err| {
+                    ParseError::new(url_start + 1, ParseErrorKind::InvalidUrl(err))
+                })?;
+                let marker = stack.pop().unwrap();
+                out.close(marker.entity, Some(Kind::TextLink { url }));
+                continue;
+            }
+            ']' | '(' | ')' | '>' | '#' | '+' | '-' | '=' | '|' | '{' | '}' | '.' | '!' => {
+                return Err(ParseError::new(pos, ParseErrorKind::Unescaped(c)));
+            }
+            c => {
+                out.push(c);
+                continue;
+            }
+        };
+
+        match stack.iter().rposition(|m| m.marker == marker) {
+            // Entities must be closed in the reverse order
+            Some(i) if i != stack.len() - 1 => {
+                let inner = stack.last().unwrap();
+                return Err(ParseError::new(
+                    inner.offset,
+                    ParseErrorKind::Unclosed(inner.marker.to_owned()),
+                ));
+            }
+            Some(_) => {
+                let marker = stack.pop().unwrap();
+                out.close(marker.entity, None);
+            }
+            None => {
+                let entity = out.open(Some(kind));
+                stack.push(Marker {
+                    marker,
+                    offset: pos,
+                    entity,
+                });
+            }
+        }
+    }
+
+    match stack.pop() {
+        Some(marker) => Err(ParseError::new(
+            marker.offset,
+            ParseErrorKind::Unclosed(marker.marker.to_owned()),
+        )),
+        None => Ok(out.finish()),
+    }
+}
+
+/// An error returned by [`html`] and [`markdown_v2`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{kind} (at byte {offset})")]
+pub struct ParseError {
+    /// Offset in bytes of the error in the markup.
+    pub offset: usize,
+
+    pub kind: ParseErrorKind,
+}
+
+impl ParseError {
+    fn new(offset: usize, kind: ParseErrorKind) -> Self {
+        Self { offset, kind }
+    }
+}
+
+/// Kind of a [`ParseError`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[non_exhaustive]
+pub enum ParseErrorKind {
+    /// The tag is not supported by telegram.
+    #[error("unsupported tag `{0}`")]
+    UnsupportedTag(String),
+
+    /// The tag can't be parsed, e.g. `<` is not escaped.
+    #[error("malformed tag")]
+    MalformedTag,
+
+    /// The end tag doesn't match the last opened tag.
+    #[error("unexpected end tag `{0}`")]
+    UnexpectedEndTag(String),
+
+    /// The tag or marker is not closed.
+    #[error("`{0}` is not closed")]
+    Unclosed(String),
+
+    /// The character must be escaped.
+    #[error("character `{0}` is reserved and must be escaped with the preceding '\\'")]
+    Unescaped(char),
+
+    /// Code and pre can't contain other entities.
+    #[error("entities can't be nested in code or pre")]
+    NestedInCode,
+
+    /// The link has no URL.
+    #[error("URL of the link is missing")]
+    MissingUrl,
+
+    /// URL of the link is invalid.
+    #[error("invalid URL: {0}")]
+    InvalidUrl(url::ParseError),
+}
+
+/// Builder of text and entities, tracks UTF-16 offsets.
+#[derive(Default)]
+struct Builder {
+    text: String,
+    len_utf16: usize,
+    /// Entities, `None` kind is used for entities which kind is not yet known.
+    entities: Vec<(Option<Kind>, usize, usize)>,
+}
+
+impl Builder {
+    fn push(&mut self, c: char) {
+        self.text.push(c);
+        self.len_utf16 += c.len_utf16();
+    }
+
+    /// Starts an entity, returns its index.
+    fn open(&mut self, kind: Option<Kind>) -> usize {
+        self.entities.push((kind, self.len_utf16, 0));
+        self.entities.len() - 1
+    }
+
+    /// Ends an entity, optionally setting its kind.
+    fn close(&mut self, entity: usize, kind: Option<Kind>) {
+        let (old_kind, offset, length) = &mut self.entities[entity];
+        *length = self.len_utf16 - *offset;
+        if kind.is_some() {
+            *old_kind = kind;
+        }
+    }
+
+    fn finish(self) -> (String, Vec<MessageEntity>) {
+        let entities = self
+            .entities
+            .into_iter()
+            .filter(|&(_, _, length)| length != 0)
+            .filter_map(|(kind, offset, length)| Some(MessageEntity::new(kind?, offset, length)))
+            .collect();
+
+        (self.text, entities)
+    }
+}
+
+/// An opened HTML tag.
+struct HtmlTag<'a> {
+    name: &'a str,
+    offset: usize,
+    /// `None` for `code` inside of `pre`, which doesn't create an entity.
+    entity: Option<usize>,
+}
+
+/// An opened MarkdownV2 entity.
+struct Marker {
+    marker: &'static str,
+    offset: usize,
+    entity: usize,
+}
+
+/// Handles contents of `<...>` at `offset`.
+fn html_tag<'a>(
+    tag: &'a str,
+    offset: usize,
+    stack: &mut Vec<HtmlTag<'a>>,
+    out: &mut Builder,
+) -> Result<(), ParseError> {
+    let err = |kind| ParseError::new(offset, kind);
+
+    if let Some(name) = tag.strip_prefix('/') {
+        let name = name.trim();
+        return match stack.pop() {
+            Some(open) if open.name.eq_ignore_ascii_case(name) => {
+                if let Some(entity) = open.entity {
+                    out.close(entity, None);
+                }
+                Ok(())
+            }
+            _ => Err(err(ParseErrorKind::UnexpectedEndTag(name.to_owned()))),
+        };
+    }
+
+    let name_len = tag.find(|c: char| c.is_whitespace()).unwrap_or(tag.len());
+    let (name, attrs) = tag.split_at(name_len);
+    if name.is_empty() {
+        return Err(err(ParseErrorKind::MalformedTag));
+    }
+    let attrs = html_attributes(attrs).ok_or_else(|| err(ParseErrorKind::MalformedTag))?;
+    let attr = |key: &str| {
+        attrs
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, value)| value.as_str())
+    };
+
+    let in_pre = stack
+        .iter()
+        .any(|open| open.name.eq_ignore_ascii_case("pre"));
+    let in_code = stack
+        .iter()
+        .any(|open| open.name.eq_ignore_ascii_case("code"));
+
+    let kind = match &*name.to_ascii_lowercase() {
+        // `<pre><code class="language-rust">` specifies the language of `pre`
+        "code" if in_pre && !in_code => {
+            let pre = stack.last().and_then(|open| open.entity);
+            let language = attr("class").and_then(|class| class.strip_prefix("language-"));
+            if let (Some(pre), Some(language)) = (pre, language) {
+                out.entities[pre].0 = Some(Kind::Pre {
+                    language: Some(language.to_owned()),
+                });
+            }
+
+            stack.push(HtmlTag {
+                name,
+                offset,
+                entity: None,
+            });
+            return Ok(());
+        }
+        _ if in_pre || in_code => return Err(err(ParseErrorKind::NestedInCode)),
+        "b" | "strong" => Kind::Bold,
+        "i" | "em" => Kind::Italic,
+        "u" | "ins" => Kind::Underline,
+        "s" | "strike" | "del" => Kind::Strikethrough,
+        "tg-spoiler" => Kind::Spoiler,
+        "span" if attr("class") == Some("tg-spoiler") => Kind::Spoiler,
+        "a" => {
+            let href = attr("href").ok_or_else(|| err(ParseErrorKind::MissingUrl))?;
+            let url = Url::parse(href).map_err(|e| err(ParseErrorKind::InvalidUrl(e)))?;
+            Kind::TextLink { url }
+        }
+        "code" => Kind::Code,
+        "pre" => Kind::Pre { language: None },
+        _ => return Err(err(ParseErrorKind::UnsupportedTag(name.to_owned()))),
+    };
+
+    let entity = out.open(Some(kind));
+    stack.push(HtmlTag {
+        name,
+        offset,
+        entity: Some(entity),
+    });
+    Ok(())
+}
+
+/// Parses attributes of a tag, returns `None` if they are malformed.
+fn html_attributes(mut attrs: &str) -> Option<Vec<(&str, String)>> {
+    let mut res = Vec::new();
+
+    loop {
+        attrs = attrs.trim_start();
+        if attrs.is_empty() {
+            return Some(res);
+        }
+
+        let key_len = attrs
+            .find(|c: char| c == '=' || c.is_whitespace())
+            .unwrap_or(attrs.len());
+        let (key, rest) = attrs.split_at(key_len);
+
+        let rest = rest.trim_start();
+        let (value, rest) = match rest.strip_prefix('=') {
+            Some(rest) => {
+                let rest = rest.trim_start();
+                match rest.chars().next()? {
+                    quote @ ('"' | '\'') => {
+                        let end = rest[1..].find(quote)? + 1;
+                        (&rest[1..end], &rest[end + 1..])
+                    }
+                    _ => {
+                        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+                        rest.split_at(end)
+                    }
+                }
+            }
+            None => ("", rest),
+        };
+
+        res.push((key, html_unescape(value)));
+        attrs = rest;
+    }
+}
+
+fn html_unescape(text: &str) -> String {
+    let mut res = String::with_capacity(text.len());
+    let mut pos = 0;
+
+    while let Some(c) = text[pos..].chars().next() {
+        let entity = match c {
+            '&' => html_entity(&text[pos..]),
+            _ => None,
+        };
+
+        match entity {
+            Some((c, len)) => {
+                res.push(c);
+                pos += len;
+            }
+            None => {
+                res.push(c);
+                pos += c.len_utf8();
+            }
+        }
+    }
+
+    res
+}
+
+/// Parses an HTML entity at the start of `text`, returns the character and
+/// the length of the entity.
+fn html_entity(text: &str) -> Option<(char, usize)> {
+    // Entities are short, don't scan the whole text
+    let (end, _) = text.char_indices().take(12).find(|&(_, c)| c == ';')?;
+    let name = text.get(1..end)?;
+
+    let c = match name {
+        "lt" => '<',
+        "gt" => '>',
+        "amp" => '&',
+        "quot" => '"',
+        _ => {
+            let code = name.strip_prefix('#')?;
+            let code = match code.strip_prefix(|c| c == 'x' || c == 'X') {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => code.parse().ok()?,
+            };
+            char::from_u32(code)?
+        }
+    };
+
+    Some((c, end + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        types::{MessageEntity, UserId},
+        utils::render,
+    };
+
+    use reqwest::Url;
+
+    use super::{html, markdown_v2, ParseErrorKind};
+
+    #[test]
+    fn html_entities() {
+        let (text, entities) = html(
+            "😀 <B>b&amp;<i>i</i></b> &lt;x&#62; &foo; <a href='tg://user/?id=1'>link</a> <span \
+             class=\"tg-spoiler\">s</span><pre><code class=\"language-rust\">fn</code></pre>",
+        )
+        .unwrap();
+
+        assert_eq!(text, "😀 b&i <x> &foo; link sfn");
+        assert_eq!(
+            entities,
+            [
+                MessageEntity::bold(3, 3),
+                MessageEntity::italic(5, 1),
+                MessageEntity::text_mention_id(UserId(1), 17, 4),
+                MessageEntity::spoiler(22, 1),
+                MessageEntity::pre(Some("rust".to_owned()), 23, 2),
+            ]
+        );
+        assert_eq!(
+            html(&render::html(&text, &entities).unwrap()).unwrap(),
+            (text, entities)
+        );
+    }
+
+    #[test]
+    fn html_errors() {
+        let err = |markup| html(markup).unwrap_err();
+
+        assert_eq!(err("a <b>b</i>").offset, 6);
+        assert_eq!(
+            err("a <b>b</i>").kind,
+            ParseErrorKind::UnexpectedEndTag("i".to_owned())
+        );
+        assert_eq!(err("a <b>b").kind, ParseErrorKind::Unclosed("b".to_owned()));
+        assert_eq!(err("a <b>b").offset, 2);
+        assert_eq!(err("1 < 2").kind, ParseErrorKind::MalformedTag);
+        assert_eq!(
+            err("<x>").kind,
+            ParseErrorKind::UnsupportedTag("x".to_owned())
+        );
+        assert_eq!(err("<code><b>").kind, ParseErrorKind::NestedInCode);
+        assert!(matches!(
+            err("<a href=\"x\">").kind,
+            ParseErrorKind::InvalidUrl(_)
+        ));
+    }
+
+    #[test]
+    fn markdown_v2_entities() {
+        let (text, entities) = markdown_v2(
+            "😀 *b\\_ _i_* _\r__u__i_ ||s|| [link](tg://user/?id=1) `c\\`` ```rust\nfn\n```",
+        )
+        .unwrap();
+
+        assert_eq!(text, "😀 b_ i ui s link c` fn\n");
+        assert_eq!(
+            entities,
+            [
+                MessageEntity::bold(3, 4),
+                MessageEntity::italic(6, 1),
+                MessageEntity::italic(8, 2),
+                MessageEntity::underline(8, 1),
+                MessageEntity::spoiler(11, 1),
+                MessageEntity::text_mention_id(UserId(1), 13, 4),
+                MessageEntity::code(18, 2),
+                MessageEntity::pre(Some("rust".to_owned()), 21, 3),
+            ]
+        );
+        assert_eq!(
+            markdown_v2(&render::markdown_v2(&text, &entities).unwrap()).unwrap(),
+            (text, entities)
+        );
+    }
+
+    #[test]
+    fn markdown_v2_errors() {
+        let err = |markup| markdown_v2(markup).unwrap_err();
+
+        assert_eq!(err("a.").kind, ParseErrorKind::Unescaped('.'));
+        assert_eq!(err("a.").offset, 1);
+        assert_eq!(
+            err("*a _b* c_").kind,
+            ParseErrorKind::Unclosed("_".to_owned())
+        );
+        assert_eq!(err("*a _b* c_").offset, 3);
+        assert_eq!(err("x `a").kind, ParseErrorKind::Unclosed("`".to_owned()));
+        assert_eq!(err("[a]").kind, ParseErrorKind::MissingUrl);
+        assert_eq!(
+            err("[a](b c").kind,
+            ParseErrorKind::Unclosed("(".to_owned())
+        );
+        assert!(matches!(err("[a](b)").kind, ParseErrorKind::InvalidUrl(_)));
+    }
+
+    #[test]
+    fn malformed_input() {
+        let (text, entities) = html("<a href=\"http://x/é;\">a</a> &é;").unwrap();
+        assert_eq!(text, "a &é;");
+        assert_eq!(
+            entities,
+            [MessageEntity::text_link(
+                Url::parse("http://x/é;").unwrap(),
+                0,
+                1
+            )]
+        );
+
+        let many = "&".repeat(100_000);
+        assert_eq!(html(&many).unwrap().0, many);
+
+        let err = markdown_v2("[a](http://x/\\").unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::Unclosed("(".to_owned()));
+        assert_eq!(err.offset, 3);
+    }
+}
@@ -0,0 +1,335 @@
+//! Rendering of text with [`MessageEntity`]s to markup.
+//!
+//! This is useful to re-send formatted text of a message (e.g. to echo a
+//! message with its formatting), or to store it in a readable form.
+//!
+//! ## Examples
+//!
+//! ```
+//! use teloxide_core::{types::MessageEntity, utils::render};
+//!
+//! let text = "Hello, world!";
+//! let entities = [MessageEntity::bold(0, 13), MessageEntity::italic(7, 5)];
+//!
+//! assert_eq!(
+//!     render::html(text, &entities).unwrap(),
+//!     "<b>Hello, <i>world</i>!</b>"
+//! );
+//! assert_eq!(
+//!     render::markdown_v2(text, &entities).unwrap(),
+//!     "*Hello, _world_\\!*"
+//! );
+//! ```
+
+use std::cmp::Reverse;
+
+use crate::{
+    types::{EntityOffsetError, MessageEntity, MessageEntityKind as Kind, MessageEntityRef},
+    utils::escape::{push_html, push_markdown_v2, push_markdown_v2_url},
+};
+
+/// Renders `text` with `entities` to [`ParseMode::Html`] markup.
+///
+/// Entities which are detected by telegram automatically (mentions, hashtags,
+/// URLs, etc) are rendered as plain text. Returns an error if `entities` have
+/// invalid offsets.
+///
+/// [`ParseMode::Html`]: crate::types::ParseMode::Html
+pub fn html(text: &str, entities: &[MessageEntity]) -> Result<String, EntityOffsetError> {
+    render::<Html>(text, entities)
+}
+
+/// Renders `text` with `entities` to [`ParseMode::MarkdownV2`] markup.
+///
+/// Entities which are detected by telegram automatically (mentions, hashtags,
+/// URLs, etc) are rendered as plain text. Returns an error if `entities` have
+/// invalid offsets.
+///
+/// [`ParseMode::MarkdownV2`]: crate::types::ParseMode::MarkdownV2
+pub fn markdown_v2(text: &str, entities: &[MessageEntity]) -> Result<String, EntityOffsetError> {
+    render::<MarkdownV2>(text, entities)
+}
+
+/// Markup language.
+trait Markup {
+    /// Appends the opening tag of an entity, returns `false` if the entity is
+    /// rendered as plain text.
+    fn open(kind: &Kind, out: &mut String) -> bool;
+
+    /// Appends the closing tag of an entity.
+    fn close(kind: &Kind, out: &mut String);
+
+    /// Appends escaped text.
+    fn escape(text: &str, in_code: bool, out: &mut String);
+}
+
+/// An entity opened during rendering.
+struct Open<'a> {
+    end: usize,
+    kind: &'a Kind,
+    /// `false` if the entity has no markup.
+    tagged: bool,
+}
+
+fn render<M: Markup>(text: &str, entities: &[MessageEntity]) -> Result<String, EntityOffsetError> {
+    let mut entities = MessageEntityRef::parse(text, entities)?;
+    entities.retain(|e| !e.range().is_empty());
+    // Outer entities go first
+    entities.sort_by_key(|e| (e.start(), Reverse(e.end())));
+
+    let mut out = String::with_capacity(text.len());
+    let mut stack: Vec<Open<'_>> = Vec::new();
+    let mut entities = entities.iter().peekable();
+    let mut pos = 0;
+
+    loop {
+        // Close entities which end here. If an entity overlaps (rather than
+        // contains) entities opened after it, they are closed and reopened.
+        if let Some(i) = stack.iter().position(|open| open.end <= pos) {
+            let closed = stack.split_off(i);
+            for open in closed.iter().rev().filter(|open| open.tagged) {
+                M::close(open.kind, &mut out);
+            }
+            for open in closed.into_iter().filter(|open| open.end > pos) {
+                push::<M>(&mut stack, open.end, open.kind, &mut out);
+            }
+        }
+
+        while let Some(entity) = entities.next_if(|e| e.start() == pos) {
+            push::<M>(&mut stack, entity.end(), entity.kind(), &mut out);
+        }
+
+        let next = entities
+            .peek()
+            .map(|e| e.start())
+            .into_iter()
+            .chain(stack.iter().map(|open| open.end))
+            .min();
+
+        let in_code = stack.iter().any(|open| is_code(open.kind));
+        match next {
+            Some(next) => {
+                M::escape(&text[pos..next], in_code, &mut out);
+                pos = next;
+            }
+            None => {
+                M::escape(&text[pos..], in_code, &mut out);
+                break;
+            }
+        }
+    }
+
+    Ok(out)
+}
+
+fn push<'a, M: Markup>(stack: &mut Vec<Open<'a>>, end: usize, kind: &'a Kind, out: &mut String) {
+    // Code can't contain other entities
+    let tagged = !stack.iter().any(|open| is_code(open.kind)) && M::open(kind, out);
+    stack.push(Open { end, kind, tagged });
+}
+
+fn is_code(kind: &Kind) -> bool {
+    matches!(kind, Kind::Code | Kind::Pre { .. })
+}
+
+fn user_url(kind: &Kind) -> Option<String> {
+    match kind {
+        Kind::TextMention { user } => Some(user.url().to_string()),
+        _ => None,
+    }
+}
+
+struct Html;
+
+impl Markup for Html {
+    fn open(kind: &Kind, out: &mut String) -> bool {
+        match kind {
+            Kind::Bold => out.push_str("<b>"),
+            Kind::Italic => out.push_str("<i>"),
+            Kind::Underline => out.push_str("<u>"),
+            Kind::Strikethrough => out.push_str("<s>"),
+            Kind::Spoiler => out.push_str("<tg-spoiler>"),
+            Kind::Code => out.push_str("<code>"),
+            Kind::Pre { language: None } => out.push_str("<pre>"),
+            Kind::Pre {
+                language: Some(language),
+            } => {
+                out.push_str("<pre><code class=\"language-");
+                push_html(language, out);
+                out.push_str("\">");
+            }
+            Kind::TextLink { url } => {
+                out.push_str("<a href=\"");
+                push_html(url.as_str(), out);
+                out.push_str("\">");
+            }
+            Kind::TextMention { .. } => {
+                out.push_str("<a href=\"");
+                push_html(&user_url(kind).unwrap(), out);
+                out.push_str("\">");
+            }
+            Kind::Mention
+            | Kind::Hashtag
+            | Kind::Cashtag
+            | Kind::BotCommand
+            | Kind::Url
+            | Kind::Email
+            | Kind::PhoneNumber => return false,
+        }
+
+        true
+    }
+
+    fn close(kind: &Kind, out: &mut String) {
+        out.push_str(match kind {
+            Kind::Bold => "</b>",
+            Kind::Italic => "</i>",
+            Kind::Underline => "</u>",
+            Kind::Strikethrough => "</s>",
+            Kind::Spoiler => "</tg-spoiler>",
+            Kind::Code => "</code>",
+            Kind::Pre { language: None } => "</pre>",
+            Kind::Pre { language: Some(_) } => "</code></pre>",
+            Kind::TextLink { .. } | Kind::TextMention { .. } => "</a>",
+            _ => "",
+        })
+    }
+
+    fn escape(text: &str, _in_code: bool, out: &mut String) {
+        push_html(text, out)
+    }
+}
+
+struct MarkdownV2;
+
+impl MarkdownV2 {
+    /// Appends a marker, separating it from a preceding `_` with `\r` (which
+    /// is ignored by telegram), so e.g. italic and underline markers are not
+    /// confused.
+    fn marker(marker: &str, out: &mut String) {
+        if marker.starts_with('_') && out.ends_with('_') {
+            // `_` is escaped if it's preceded by an odd number of `\`
+            let backslashes = out[..out.len() - 1]
+                .chars()
+                .rev()
+                .take_while(|&c| c == '\\')
+                .count();
+            if backslashes % 2 == 0 {
+                out.push('\r');
+            }
+        }
+        out.push_str(marker);
+    }
+}
+
+impl Markup for MarkdownV2 {
+    fn open(kind: &Kind, out: &mut String) -> bool {
+        match kind {
+            Kind::Bold => Self::marker("*", out),
+            Kind::Italic => Self::marker("_", out),
+            Kind::Underline => Self::marker("__", out),
+            Kind::Strikethrough => Self::marker("~", out),
+            Kind::Spoiler => Self::marker("||", out),
+            Kind::Code => Self::marker("`", out),
+            Kind::Pre { language } => {
+                Self::marker("```", out);
+                if let Some(language) = language {
+                    push_markdown_v2(language, true, out);
+                }
+                out.push('\n');
+            }
+            Kind::TextLink { .. } | Kind::TextMention { .. } => Self::marker("[", out),
+            Kind::Mention
+            | Kind::Hashtag
+            | Kind::Cashtag
+            | Kind::BotCommand
+            | Kind::Url
+            | Kind::Email
+            | Kind::PhoneNumber => return false,
+        }
+
+        true
+    }
+
+    fn close(kind: &Kind, out: &mut String) {
+        match kind {
+            Kind::Bold => Self::marker("*", out),
+            Kind::Italic => Self::marker("_", out),
+            Kind::Underline => Self::marker("__", out),
+            Kind::Strikethrough => Self::marker("~", out),
+            Kind::Spoiler => Self::marker("||", out),
+            Kind::Code => Self::marker("`", out),
+            Kind::Pre { .. } => Self::marker("```", out),
+            Kind::TextLink { url } => {
+                out.push_str("](");
+                push_markdown_v2_url(url.as_str(), out);
+                out.push(')');
+            }
+            Kind::TextMention { .. } => {
+                out.push_str("](");
+                push_markdown_v2_url(&user_url(kind).unwrap(), out);
+                out.push(')');
+            }
+            _ => {}
+        }
+    }
+
+    fn escape(text: &str, in_code: bool, out: &mut String) {
+        push_markdown_v2(text, in_code, out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::{MessageEntity, User, UserId};
+
+    use super::{html, markdown_v2};
+
+    #[test]
+    fn overlapping_and_escaped() {
+        let user: User = serde_json::from_value(serde_json::json!({
+            "id": 2,
+            "is_bot": false,
+            "first_name": "A",
+        }))
+        .unwrap();
+
+        let text = "😀 b_i <x> c`d link me";
+        let entities = [
+            MessageEntity::bold(3, 3),
+            MessageEntity::italic(5, 5),
+            MessageEntity::code(11, 3),
+            MessageEntity::text_mention_id(UserId(1), 15, 4),
+            MessageEntity::text_mention(user, 20, 2),
+        ];
+
+        assert_eq!(
+            html(text, &entities).unwrap(),
+            "😀 <b>b_<i>i</i></b><i> &lt;x&gt;</i> <code>c`d</code> <a \
+             href=\"tg://user/?id=1\">link</a> <a href=\"tg://user/?id=2\">me</a>"
+        );
+        assert_eq!(
+            markdown_v2(text, &entities).unwrap(),
+            "😀 *b\\__i_*_ <x\\>_ `c\\`d` [link](tg://user/?id=1) [me](tg://user/?id=2)"
+        );
+    }
+
+    #[test]
+    fn special_cases() {
+        let entities = [MessageEntity::italic(0, 2), MessageEntity::underline(0, 1)];
+        assert_eq!(html("ab", &entities).unwrap(), "<i><u>a</u>b</i>");
+        assert_eq!(markdown_v2("ab", &entities).unwrap(), "_\r__a__b_");
+
+        let entities = [MessageEntity::pre(Some("rust".to_owned()), 0, 2)];
+        assert_eq!(
+            html("fn", &entities).unwrap(),
+            "<pre><code class=\"language-rust\">fn</code></pre>"
+        );
+        assert_eq!(markdown_v2("fn", &entities).unwrap(), "```rust\nfn```");
+
+        // Code can't contain other entities
+        let entities = [MessageEntity::code(0, 2), MessageEntity::bold(0, 1)];
+        assert_eq!(html("ab", &entities).unwrap(), "<code>ab</code>");
+        assert_eq!(markdown_v2("ab", &entities).unwrap(), "`ab`");
+    }
+}
@@ -0,0 +1,212 @@
+//! Splitting of long texts with [`MessageEntity`]s into multiple messages.
+//!
+//! ## Examples
+//!
+//! ```
+//! use teloxide_core::{types::MessageEntity, utils::split};
+//!
+//! let text = "First paragraph.\n\nSecond paragraph.";
+//! let entities = [MessageEntity::bold(6, 20)];
+//!
+//! let chunks = split::text(text, &entities, 20);
+//! assert_eq!(
+//!     chunks,
+//!     [
+//!         (
+//!             "First paragraph.".to_owned(),
+//!             vec![MessageEntity::bold(6, 10)]
+//!         ),
+//!         (
+//!             "Second paragraph.".to_owned(),
+//!             vec![MessageEntity::bold(0, 8)]
+//!         ),
+//!     ]
+//! );
+//! ```
+
+use crate::{
+    payloads::setters::*,
+    requests::{Request, Requester},
+    types::{Message, MessageEntity, Recipient},
+    utils::text_builder::clip,
+};
+
+/// Maximum length of a message text, in UTF-16 code units.
+pub const MESSAGE_LIMIT: usize = 4096;
+
+/// Maximum length of a media caption, in UTF-16 code units.
+pub const CAPTION_LIMIT: usize = 1024;
+
+/// Splits `text` with `entities` into chunks of at most `limit` UTF-16 code
+/// units (the way telegram measures length).
+///
+/// Chunks are split at the last paragraph break (`\n\n`) which fits into the
+/// limit, falling back to a line break, whitespace and, finally, to an
+/// arbitrary character boundary. Whitespace at the split point is not included
+/// into the chunks, chunks consisting only of whitespace are skipped.
+///
+/// Entities are clipped to the chunks they intersect, so an entity crossing a
+/// split point is present in both chunks. Entities (or their parts) which lie
+/// outside of `text` are dropped.
+///
+/// Use [`MESSAGE_LIMIT`] or [`CAPTION_LIMIT`] as the `limit`.
+///
+/// ## Panics
+///
+/// If `limit` is less than 2 (such limit can't fit a surrogate pair).
+pub fn text(
+    text: &str,
+    entities: &[MessageEntity],
+    limit: usize,
+) -> Vec<(String, Vec<MessageEntity>)> {
+    assert!(limit >= 2, "`limit` must be at least 2");
+
+    // (byte offset, UTF-16 offset) of all char boundaries
+    let mut bounds = Vec::with_capacity(text.len() + 1);
+    let mut len_utf16 = 0;
+    for (i, c) in text.char_indices() {
+        bounds.push((i, len_utf16));
+        len_utf16 += c.len_utf16();
+    }
+    bounds.push((text.len(), len_utf16));
+
+    let index = |byte| bounds.binary_search_by_key(&byte, |&(b, _)| b).unwrap();
+
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while start < text.len() {
+        let (_, start_utf16) = bounds[index(start)];
+        let max = bounds[bounds.partition_point(|&(_, u)| u <= start_utf16 + limit) - 1].0;
+
+        let (end, next) = if max == text.len() {
+            (max, max)
+        } else {
+            let end = start + split_point(&text[start..max]).unwrap_or(max - start);
+            let rest = &text[end..];
+            (end, end + rest.len() - rest.trim_start().len())
+        };
+
+        let chunk = &text[start..end];
+        if !chunk.trim().is_empty() {
+            let (_, end_utf16) = bounds[index(end)];
+            chunks.push((chunk.to_owned(), clip(entities, start_utf16, end_utf16)));
+        }
+
+        start = next;
+    }
+
+    chunks
+}
+
+/// Splits `text` with `entities` by [`text`] and sends the chunks in order as
+/// separate messages.
+///
+/// Stops at the first error, messages which were already sent are not
+/// deleted.
+pub async fn send_message<R, C>(
+    bot: &R,
+    chat_id: C,
+    text: &str,
+    entities: &[MessageEntity],
+) -> Result<Vec<Message>, R::Err>
+where
+    R: Requester,
+    C: Into<Recipient>,
+{
+    let chat_id = chat_id.into();
+    let mut messages = Vec::new();
+
+    for (text, entities) in self::text(text, entities, MESSAGE_LIMIT) {
+        let mut request = bot.send_message(chat_id.clone(), text);
+        if !entities.is_empty() {
+            request = request.entities(entities);
+        }
+
+        messages.push(request.send().await?);
+    }
+
+    Ok(messages)
+}
+
+/// Returns the byte offset in `window` at which it should be split, with
+/// trailing whitespace removed.
+fn split_point(window: &str) -> Option<usize> {
+    let paragraph = || window.rfind("\n\n");
+    let line = || window.rfind('\n');
+    let word = || {
+        window
+            .char_indices()
+            .rev()
+            .find(|&(_, c)| c.is_whitespace())
+            .map(|(i, _)| i)
+    };
+
+    let point = paragraph()
+        .filter(|&i| i > 0)
+        .or_else(|| line().filter(|&i| i > 0))
+        .or_else(|| word().filter(|&i| i > 0))?;
+
+    match window[..point].trim_end().len() {
+        0 => Some(point),
+        trimmed => Some(trimmed),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::MessageEntity;
+
+    use super::text;
+
+    #[test]
+    fn split_points() {
+        let chunks = text("aa bb\ncc dd\n\nee ff", &[MessageEntity::bold(3, 12)], 13);
+        assert_eq!(
+            chunks,
+            [
+                ("aa bb\ncc dd".to_owned(), vec![MessageEntity::bold(3, 8)]),
+                ("ee ff".to_owned(), vec![MessageEntity::bold(0, 2)]),
+            ]
+        );
+
+        let chunks = text("aa bb\ncc dd ee", &[], 10);
+        assert_eq!(chunks[0].0, "aa bb");
+        assert_eq!(chunks[1].0, "cc dd ee");
+
+        let chunks = text("aa bb  cc", &[MessageEntity::italic(0, 9)], 6);
+        assert_eq!(
+            chunks,
+            [
+                ("aa bb".to_owned(), vec![MessageEntity::italic(0, 5)]),
+                ("cc".to_owned(), vec![MessageEntity::italic(0, 2)]),
+            ]
+        );
+
+        // Surrogate pairs are not split
+        let chunks = text("😀😀😀", &[MessageEntity::code(2, 4)], 3);
+        assert_eq!(
+            chunks,
+            [
+                ("😀".to_owned(), vec![]),
+                ("😀".to_owned(), vec![MessageEntity::code(0, 2)]),
+                ("😀".to_owned(), vec![MessageEntity::code(0, 2)]),
+            ]
+        );
+
+        assert!(text("   \n\n  ", &[], 2).is_empty());
+
+        // Out of range entities don't overflow
+        let chunks = text(
+            "aa bb",
+            &[
+                MessageEntity::bold(3, usize::MAX),
+                MessageEntity::italic(usize::MAX, 1),
+            ],
+            10,
+        );
+        assert_eq!(
+            chunks,
+            [("aa bb".to_owned(), vec![MessageEntity::bold(3, 2)])]
+        );
+    }
+}
@@ -0,0 +1,431 @@
+use std::{cmp, mem, ops::Range};
+
+use reqwest::Url;
+
+use crate::types::{
+    EntityOffsetError, MessageEntity, MessageEntityKind, MessageEntityRef, User, UserId,
+};
+
+/// Builder of formatted text.
+///
+/// Builds a text together with [`MessageEntity`]s describing its formatting,
+/// which can be passed to `entities` or `caption_entities` of a request
+/// instead of a [`ParseMode`]. This way no escaping is needed and offsets of
+/// entities are always correct (telegram measures them in UTF-16 code units,
+/// which is easy to get wrong).
+///
+/// Entities can be nested by passing a builder to a formatting method.
+///
+/// ## Examples
+///
+/// ```
+/// use teloxide_core::{
+///     types::{MessageEntity, UserId},
+///     utils::TextBuilder,
+/// };
+///
+/// let (text, entities) = TextBuilder::new()
+///     .plain("Hi, ")
+///     .text_mention_id("🦀 Ferris", UserId(1))
+///     .plain("! This is ")
+///     .bold(TextBuilder::new().plain("bold and ").italic("italic"))
+///     .build();
+///
+/// assert_eq!(text, "Hi, 🦀 Ferris! This is bold and italic");
+/// assert_eq!(
+///     entities,
+///     [
+///         MessageEntity::text_mention_id(UserId(1), 4, 9),
+///         MessageEntity::bold(23, 15),
+///         MessageEntity::italic(32, 6),
+///     ]
+/// );
+/// ```
+///
+/// [`ParseMode`]: crate::types::ParseMode
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[must_use]
+pub struct TextBuilder {
+    text: String,
+    entities: Vec<MessageEntity>,
+    /// Length of `text` in UTF-16 code units.
+    len_utf16: usize,
+}
+
+impl TextBuilder {
+    /// Creates an empty builder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends unformatted text.
+    pub fn plain(mut self, text: impl AsRef<str>) -> Self {
+        let text = text.as_ref();
+        self.text.push_str(text);
+        self.len_utf16 += text.encode_utf16().count();
+        self
+    }
+
+    /// Appends `text` with all its entities.
+    pub fn push(mut self, text: impl Into<TextBuilder>) -> Self {
+        let text = text.into();
+        let offset = self.len_utf16;
+
+        self.text.push_str(&text.text);
+        self.len_utf16 += text.len_utf16;
+        self.entities
+            .extend(text.entities.into_iter().map(|entity| MessageEntity {
+                offset: entity.offset + offset,
+                ..entity
+            }));
+        self
+    }
+
+    /// Appends `text` wrapped in an entity of the given kind.
+    ///
+    /// No entity is added if `text` is empty.
+    pub fn entity(mut self, kind: MessageEntityKind, text: impl Into<TextBuilder>) -> Self {
+        let text = text.into();
+        if text.text.is_empty() {
+            return self;
+        }
+
+        self.entities
+            .push(MessageEntity::new(kind, self.len_utf16, text.len_utf16));
+        self.push(text)
+    }
+
+    /// Appends bold text.
+    pub fn bold(self, text: impl Into<TextBuilder>) -> Self {
+        self.entity(MessageEntityKind::Bold, text)
+    }
+
+    /// Appends italic text.
+    pub fn italic(self, text: impl Into<TextBuilder>) -> Self {
+        self.entity(MessageEntityKind::Italic, text)
+    }
+
+    /// Appends underlined text.
+    pub fn underline(self, text: impl Into<TextBuilder>) -> Self {
+        self.entity(MessageEntityKind::Underline, text)
+    }
+
+    /// Appends strikethrough text.
+    pub fn strikethrough(self, text: impl Into<TextBuilder>) -> Self {
+        self.entity(MessageEntityKind::Strikethrough, text)
+    }
+
+    /// Appends spoiler text.
+    pub fn spoiler(self, text: impl Into<TextBuilder>) -> Self {
+        self.entity(MessageEntityKind::Spoiler, text)
+    }
+
+    /// Appends monowidth text.
+    ///
+    /// Note that code can't contain other entities.
+    pub fn code(self, text: impl AsRef<str>) -> Self {
+        self.entity(MessageEntityKind::Code, text.as_ref())
+    }
+
+    /// Appends monowidth block, optionally with a programming language.
+    ///
+    /// Note that pre can't contain other entities.
+    pub fn pre(self, text: impl AsRef<str>, language: Option<String>) -> Self {
+        self.entity(MessageEntityKind::Pre { language }, text.as_ref())
+    }
+
+    /// Appends a clickable text URL.
+    pub fn link(self, text: impl Into<TextBuilder>, url: Url) -> Self {
+        self.entity(MessageEntityKind::TextLink { url }, text)
+    }
+
+    /// Appends a mention of `user`.
+    ///
+    /// If you don't have a complete [`User`] value, please use
+    /// [`TextBuilder::text_mention_id`] instead.
+    pub fn text_mention(self, text: impl Into<TextBuilder>, user: User) -> Self {
+        self.entity(MessageEntityKind::TextMention { user }, text)
+    }
+
+    /// Appends a mention of the user with `user_id` (a text link in the form
+    /// of `tg://user/?id=...`).
+    pub fn text_mention_id(self, text: impl Into<TextBuilder>, user_id: UserId) -> Self {
+        self.link(text, user_id.url())
+    }
+
+    /// Creates a builder from text with entities, e.g. from a caption of a
+    /// message, so it can be edited.
+    ///
+    /// Returns an error if `entities` have invalid offsets.
+    pub fn from_parts(
+        text: impl Into<String>,
+        entities: Vec<MessageEntity>,
+    ) -> Result<Self, EntityOffsetError> {
+        let text = text.into();
+        MessageEntityRef::parse(&text, &entities)?;
+
+        Ok(Self {
+            entities,
+            ..Self::from(text)
+        })
+    }
+
+    /// Returns the part of the text in the byte range `range`, with entities
+    /// clipped to it.
+    ///
+    /// Ranges of [`MessageEntityRef`]s can be used here.
+    ///
+    /// ## Panics
+    ///
+    /// If `range` is out of bounds or doesn't lie on `char` boundaries.
+    pub fn slice(&self, range: Range<usize>) -> Self {
+        let text = &self.text[range.clone()];
+        let start = self.len_utf16_before(range.start);
+        let len_utf16 = text.encode_utf16().count();
+
+        Self {
+            text: text.to_owned(),
+            entities: clip(&self.entities, start, start + len_utf16),
+            len_utf16,
+        }
+    }
+
+    /// Inserts `text` with all its entities at the byte index `at`.
+    ///
+    /// Entities which contain `at` (not at their boundaries) are extended to
+    /// contain the inserted text, entities which start at or after `at` are
+    /// moved.
+    ///
+    /// ## Panics
+    ///
+    /// If `at` is out of bounds or doesn't lie on a `char` boundary.
+    pub fn insert(mut self, at: usize, text: impl Into<TextBuilder>) -> Self {
+        let text = text.into();
+        let offset = self.len_utf16_before(at);
+
+        for entity in &mut self.entities {
+            if entity.offset >= offset {
+                entity.offset += text.len_utf16;
+            } else if entity.offset + entity.length > offset {
+                entity.length += text.len_utf16;
+            }
+        }
+
+        self.text.insert_str(at, &text.text);
+        self.len_utf16 += text.len_utf16;
+        self.entities
+            .extend(text.entities.into_iter().map(|entity| MessageEntity {
+                offset: entity.offset + offset,
+                ..entity
+            }));
+        self
+    }
+
+    /// Removes the byte range `range` from the text.
+    ///
+    /// Entities are shrunk and moved accordingly, entities which become empty
+    /// are removed.
+    ///
+    /// ## Panics
+    ///
+    /// If `range` is out of bounds or doesn't lie on `char` boundaries.
+    pub fn remove(mut self, range: Range<usize>) -> Self {
+        let start = self.len_utf16_before(range.start);
+        let end = start + self.text[range.clone()].encode_utf16().count();
+        let removed = end - start;
+
+        let shift = |offset: usize| match offset {
+            _ if offset <= start => offset,
+            _ if offset >= end => offset - removed,
+            _ => start,
+        };
+
+        self.entities = self
+            .entities
+            .into_iter()
+            .filter_map(|entity| {
+                let entity_start = shift(entity.offset);
+                let entity_end = shift(entity.offset + entity.length);
+
+                (entity_start < entity_end).then(|| MessageEntity {
+                    offset: entity_start,
+                    length: entity_end - entity_start,
+                    ..entity
+                })
+            })
+            .collect();
+        self.text.replace_range(range, "");
+        self.len_utf16 -= removed;
+        self
+    }
+
+    /// Merges entities of the same kind which are adjacent or overlap.
+    ///
+    /// This is useful after [`push`]ing or [`insert`]ing text, which may split
+    /// e.g. bold text into several entities.
+    ///
+    /// [`push`]: TextBuilder::push
+    /// [`insert`]: TextBuilder::insert
+    pub fn merge_adjacent(mut self) -> Self {
+        let mut entities = mem::take(&mut self.entities);
+        entities.sort_by_key(|entity| entity.offset);
+
+        for entity in entities {
+            let end = entity.offset + entity.length;
+            let adjacent = self.entities.iter_mut().find(|merged| {
+                merged.kind == entity.kind && merged.offset + merged.length >= entity.offset
+            });
+
+            match adjacent {
+                Some(merged) => {
+                    merged.length = cmp::max(merged.offset + merged.length, end) - merged.offset
+                }
+                None => self.entities.push(entity),
+            }
+        }
+
+        self
+    }
+
+    /// Returns the text built so far.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns the entities built so far.
+    pub fn entities(&self) -> &[MessageEntity] {
+        &self.entities
+    }
+
+    /// Returns the text and its entities.
+    pub fn build(self) -> (String, Vec<MessageEntity>) {
+        (self.text, self.entities)
+    }
+
+    /// Returns length of the text before the byte index `at`, in UTF-16 code
+    /// units.
+    fn len_utf16_before(&self, at: usize) -> usize {
+        self.text[..at].encode_utf16().count()
+    }
+}
+
+/// Clips `entities` to `start..end` (in UTF-16 code units) and shifts them to
+/// be relative to `start`.
+pub(super) fn clip(entities: &[MessageEntity], start: usize, end: usize) -> Vec<MessageEntity> {
+    entities
+        .iter()
+        .filter_map(|entity| {
+            let entity_start = entity.offset.max(start);
+            let entity_end = entity.offset.saturating_add(entity.length).min(end);
+
+            (entity_start < entity_end).then(|| {
+                MessageEntity::new(
+                    entity.kind.clone(),
+                    entity_start - start,
+                    entity_end - entity_start,
+                )
+            })
+        })
+        .collect()
+}
+
+impl From<&str> for TextBuilder {
+    fn from(text: &str) -> Self {
+        Self::new().plain(text)
+    }
+}
+
+impl From<String> for TextBuilder {
+    fn from(text: String) -> Self {
+        let len_utf16 = text.encode_utf16().count();
+        Self {
+            text,
+            entities: Vec::new(),
+            len_utf16,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::{EntityOffsetError, MessageEntity, MessageEntityRef};
+
+    use super::TextBuilder;
+
+    #[test]
+    fn nested_utf16() {
+        let (text, entities) = TextBuilder::new()
+            .plain("😀 ")
+            .bold(
+                TextBuilder::new()
+                    .plain("b ")
+                    .italic(TextBuilder::new().plain("𝕚 ").spoiler("s"))
+                    .plain(" b"),
+            )
+            .code("")
+            .pre("fn main() {}", Some("rust".to_owned()))
+            .build();
+
+        assert_eq!(text, "😀 b 𝕚 s bfn main() {}");
+        assert_eq!(
+            entities,
+            [
+                MessageEntity::bold(3, 8),
+                MessageEntity::italic(5, 4),
+                MessageEntity::spoiler(8, 1),
+                MessageEntity::pre(Some("rust".to_owned()), 11, 12),
+            ]
+        );
+
+        let parsed: Vec<_> = MessageEntityRef::parse(&text, &entities)
+            .unwrap()
+            .iter()
+            .map(|e| e.text())
+            .collect();
+        assert_eq!(parsed, ["b 𝕚 s b", "𝕚 s", "s", "fn main() {}"]);
+    }
+
+    #[test]
+    fn editing() {
+        let text =
+            TextBuilder::from_parts("a😀 bold text", vec![MessageEntity::bold(4, 9)]).unwrap();
+        assert_eq!(
+            text.slice(6..10).build(),
+            ("bold".to_owned(), vec![MessageEntity::bold(0, 4)])
+        );
+
+        let text = text.insert(10, " and").remove(0..6);
+        assert_eq!(text.text(), "bold and text");
+        assert_eq!(text.entities(), [MessageEntity::bold(0, 13)]);
+
+        let text = text
+            .insert(0, TextBuilder::new().italic("From Bob").plain("\n"))
+            .remove(13..17);
+        assert_eq!(text.text(), "From Bob\nbold text");
+        assert_eq!(
+            text.entities(),
+            [MessageEntity::bold(9, 9), MessageEntity::italic(0, 8)]
+        );
+
+        let (_, entities) = TextBuilder::new()
+            .bold("a")
+            .bold("b")
+            .italic("c")
+            .bold("d")
+            .merge_adjacent()
+            .build();
+        assert_eq!(
+            entities,
+            [
+                MessageEntity::bold(0, 2),
+                MessageEntity::italic(2, 1),
+                MessageEntity::bold(3, 1)
+            ]
+        );
+
+        assert_eq!(
+            TextBuilder::from_parts("a", vec![MessageEntity::bold(0, 2)]),
+            Err(EntityOffsetError::OutOfBounds { offset: 2, len: 1 })
+        );
+    }
+}